@@ -13,7 +13,7 @@ use bevy::{
 
 use bevy::diagnostic::FrameTimeDiagnosticsPlugin;
 
-use kittygame::{kittygame_update, multiplatform_defs::{BlitSubFlags, LineFunc, RectFunc, SwitchPalletteFunc, TextStrFunc}};
+use kittygame::{kittygame_update, multiplatform_defs::{BlitSubFlags, LineFunc, LogFunc, RectFunc, SwitchPalletteFunc, TextStrFunc, ToneFunc}};
 use kittygame::multiplatform_defs;
 
 /// In-game resolution width.
@@ -256,6 +256,7 @@ fn kittygame_update_bevy(mut commands: Commands, asset_server: Res<AssetServer>,
         (KeyCode::KeyZ, kittygame::multiplatform_defs::BUTTON_1),
         (KeyCode::Space, kittygame::multiplatform_defs::BUTTON_1),
         (KeyCode::KeyX, kittygame::multiplatform_defs::BUTTON_2),
+        (KeyCode::Escape, kittygame::multiplatform_defs::BUTTON_START),
     ];
 
     for (keycode, input_bit) in INPUT_MAPPING.into_iter() {
@@ -272,6 +273,13 @@ fn kittygame_update_bevy(mut commands: Commands, asset_server: Res<AssetServer>,
  
 
 
-    kittygame_update(blit_sub, line, rect, text_str, switch_palette, RES_WIDTH as u32, RES_HEIGHT as u32, &btns_pressed_this_frame, &gamepads);
+    let log: &mut LogFunc = &mut |s| {
+        info!("{}", s);
+    };
+
+    // no synth here yet, so music and sound effects are dropped.
+    let tone: &mut ToneFunc = &mut |_, _, _, _| {};
+
+    kittygame_update(blit_sub, line, rect, text_str, switch_palette, log, tone, RES_WIDTH as u32, RES_HEIGHT as u32, &btns_pressed_this_frame, &gamepads);
 
 }
\ No newline at end of file
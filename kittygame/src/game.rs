@@ -1,13 +1,33 @@
 pub mod game_constants;
 pub mod mapchunk;
+pub mod chunk_index;
 pub mod game_map;
 pub mod game_state;
 pub mod entities;
 pub mod camera;
 pub mod rng;
+pub mod fixed;
 pub mod collision;
+pub mod contacts;
 pub mod music;
 pub mod menus;
 pub mod popup_text;
 pub mod ability_cards;
-pub mod cloud;
\ No newline at end of file
+pub mod card_content;
+pub mod cloud;
+pub mod map_generators;
+pub mod navigation;
+pub mod species;
+pub mod hazards;
+pub mod pickups;
+pub mod warp;
+pub mod dynamic_solids;
+pub mod state_hash;
+pub mod replay;
+pub mod options;
+pub mod storage;
+pub mod menu_widgets;
+pub mod tutorial;
+pub mod achievements;
+pub mod run_stats;
+pub mod high_scores;
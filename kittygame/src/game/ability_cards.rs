@@ -1,42 +1,58 @@
-use crate::spritesheet::{PresetSprites, Sprite};
+use crate::spritesheet::Sprite;
 
-#[derive(PartialEq)]
+use super::{card_content::{CardCombo, CardDef, DECK_RULES}, fixed::Fixed};
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
 pub enum AbilityCardTypes {
     Kitty,
     Piggy,
     Lizard,
     Bird,
+    // only found as pickups.
+    Magnet,
+    Freeze,
+    Dash,
+    DoubleJump,
+}
+
+impl AbilityCardTypes {
+    /// Cards without their own art are drawn as a blank card with a letter on it.
+    pub fn sprite(self: &Self) -> Option<&'static Sprite> {
+        CardDef::get(*self).sprite.as_ref().map(Sprite::from_preset)
+    }
+
+    pub fn letter(self: &Self) -> &'static str {
+        CardDef::get(*self).letter
+    }
+
+    pub fn name(self: &Self) -> &'static str {
+        CardDef::get(*self).name
+    }
 }
 
+pub const CARD_WIDTH_PX: u32 = 12;
+pub const CARD_HEIGHT_PX: u32 = 12;
+
+#[derive(Hash)]
 pub struct AbilityCard {
     pub card_type: AbilityCardTypes,
-    pub sprite: &'static Sprite,
-    pub target_x: f32,
-    pub target_y: f32,
-    pub x_pos: f32,
-    pub y_pos: f32,
+    pub target_x: Fixed,
+    pub target_y: Fixed,
+    pub x_pos: Fixed,
+    pub y_pos: Fixed,
 }
 
-pub const N_CARDS: usize = 5;
-
+#[derive(Hash)]
 pub struct AbilityCardStack {
     pub cards: Vec<Option<AbilityCard>>,
 }
 
 impl AbilityCard {
-    pub fn new(card: AbilityCardTypes, x_pos: f32, y_pos: f32) -> AbilityCard {
-        let preset_sprite_type = match card {
-            AbilityCardTypes::Kitty => PresetSprites::KittyCard,
-            AbilityCardTypes::Piggy => PresetSprites::PiggyCard,
-            AbilityCardTypes::Lizard => PresetSprites::LizardCard,
-            AbilityCardTypes::Bird => PresetSprites::BirdCard,
-        };
-
+    pub fn new(card: AbilityCardTypes, x_pos: Fixed, y_pos: Fixed) -> AbilityCard {
         AbilityCard {
             card_type: card,
-            sprite: Sprite::from_preset(&preset_sprite_type),
-            target_x: 0.0,
-            target_y: 0.0,
+            target_x: Fixed::ZERO,
+            target_y: Fixed::ZERO,
             x_pos,
             y_pos,
         }
@@ -46,13 +62,24 @@ impl AbilityCard {
 pub enum AbilityCardUsageResult {
     NothingHappened,
     GainedTime(u32),
-    EnabledFlyAndTime(u32),
-    EnabledWarpAndTime(u32),
+    // the time, and how much of it is given up if this is what turns flying (or warping) on.
+    EnabledFlyAndTime(u32, u32),
+    EnabledWarpAndTime(u32, u32),
+    // show where the nearest kitty nobody has found yet is, for this many frames.
+    ShowedNearestKitty(u32),
+    // stop the countdown for this many frames.
+    FrozeCountdown(u32),
+    // shoot forward for this many frames.
+    Dashed(u32),
+    // save up this many more mid-air jumps, to use whenever.
+    GainedAirJumps(u32),
+    // a mixed pair of cards, which does more than either would alone.
+    Combo(&'static CardCombo, [u32; 2]),
 }
 
 impl AbilityCardStack {
-    pub fn try_push_card(self: &mut Self, card: AbilityCardTypes, x_pos: f32, y_pos: f32) {
-        if self.cards.len() < N_CARDS {
+    pub fn try_push_card(self: &mut Self, card: AbilityCardTypes, x_pos: Fixed, y_pos: Fixed) {
+        if self.cards.len() < DECK_RULES.stack_capacity {
             self.cards.push(Some(AbilityCard::new(card, x_pos, y_pos)));
         }
     }
@@ -61,7 +88,7 @@ impl AbilityCardStack {
         for card in &mut self.cards.iter_mut() {
             match card {
                 Some(c) => {
-                    const CARD_PID_P: f32 = 0.125;
+                    const CARD_PID_P: Fixed = Fixed::from_f32(0.125);
                     c.x_pos += CARD_PID_P * (c.target_x - c.x_pos);
                     c.y_pos += CARD_PID_P * (c.target_y - c.y_pos);
                 },
@@ -70,58 +97,54 @@ impl AbilityCardStack {
         }
     }
 
+    pub fn count(self: &Self, card_type: AbilityCardTypes) -> u32 {
+        self.cards.iter().flatten().filter(|card| card.card_type == card_type).count() as u32
+    }
+
     pub fn try_use_cards(self: &mut Self) -> AbilityCardUsageResult {
         // if there is a first card, that's the use type.
-        if self.cards.is_empty() {
-            return AbilityCardUsageResult::NothingHappened;
-        }
-        match &self.cards[self.cards.len() - 1] {
-            None => {}
-            Some(card) => {
-                let active_card_type = &card.card_type;
-                let mut cards_to_consume = [false; N_CARDS];
-                cards_to_consume[self.cards.len() - 1] = true;
-                let mut n_consumed = 1;
-                // consume all adjacent cards of same type
-                for (i, other_card) in self.cards[0..self.cards.len() - 1].iter().enumerate().rev()
-                {
-                    match other_card {
-                        Some(oc) => {
-                            if oc.card_type == card.card_type {
-                                cards_to_consume[i] = true;
-                                n_consumed += 1;
-                            } else {
-                                break;
-                            }
-                        }
-                        None => {}
-                    }
-                }
-
-                // apply ability of card
-                let abil_card = match &active_card_type {
-                    AbilityCardTypes::Kitty => AbilityCardUsageResult::GainedTime(
-                        (n_consumed as f32 * (n_consumed as f32 + 1.0) / 2.0) as u32,
-                    ),
-                    AbilityCardTypes::Piggy => {
-                        AbilityCardUsageResult::GainedTime(n_consumed * 10)
-                    },
-                    AbilityCardTypes::Lizard => {
-                        AbilityCardUsageResult::EnabledWarpAndTime(n_consumed * 3)
-                    },
-                    AbilityCardTypes::Bird => AbilityCardUsageResult::EnabledFlyAndTime(n_consumed * 3),
-                };
-
-                // remove cards off the end (to ensure correct ordering)
-                for i in (0..cards_to_consume.len()).rev() {
-                    if cards_to_consume[i] {
-                        self.cards.remove(i);
+        let active_card_type = match self.cards.last() {
+            Some(Some(card)) => card.card_type,
+            _ => return AbilityCardUsageResult::NothingHappened,
+        };
+
+        // consume all adjacent cards of same type. If the next card down makes a combo with them,
+        // consume it and all the cards of its type under it too. They're all from first_consumed up.
+        let mut first_consumed = self.cards.len();
+        let mut n_consumed = [0; 2];
+        let mut combo: Option<(&'static CardCombo, AbilityCardTypes)> = None;
+        for (i, other_card) in self.cards.iter().enumerate().rev() {
+            let oc = match other_card {
+                Some(oc) => oc,
+                None => continue,
+            };
+            match combo {
+                None if oc.card_type == active_card_type => n_consumed[0] += 1,
+                None => match CardCombo::find(active_card_type, oc.card_type) {
+                    Some(c) => {
+                        combo = Some((c, oc.card_type));
+                        n_consumed[1] += 1;
                     }
-                }
-                // self.cards.remove(self.cards.len() - 1);
-                return abil_card;
+                    None => break,
+                },
+                Some((_, combo_card_type)) if oc.card_type == combo_card_type => n_consumed[1] += 1,
+                Some(_) => break,
+            }
+            first_consumed = i;
+        }
+
+        // apply ability of card
+        let abil_card = match combo {
+            Some((c, _)) => AbilityCardUsageResult::Combo(c, n_consumed),
+            None => CardDef::get(active_card_type).result(n_consumed[0]),
+        };
+
+        // remove cards off the end (to ensure correct ordering). Empty slots among them stay.
+        for i in (first_consumed..self.cards.len()).rev() {
+            if self.cards[i].is_some() {
+                self.cards.remove(i);
             }
         }
-        AbilityCardUsageResult::NothingHappened
+        abil_card
     }
 }
@@ -0,0 +1,130 @@
+// Badges for pulling off something notable. The game tells them what happens as AchievementEvents,
+// and any an event earns are unlocked for good and saved along with the options.
+
+use crate::spritesheet::PresetSprites;
+
+use super::{game_constants::FINAL_LEVEL, game_state::RunType};
+
+// winning a level with less than this many seconds left is a close call.
+const CLOSE_CALL_SECONDS: u32 = 5;
+// cards used at once, counting both halves of a combo.
+const BIG_COMBO_CARDS: u32 = 5;
+// seed 0, all the way through, in under this many minutes.
+const SEED_ZERO_TARGET_MINUTES: u32 = 15;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Achievement {
+    FoundLizard,
+    ReachedFinalLevel,
+    CloseCall,
+    BigCombo,
+    SeedZeroSpeedrun,
+}
+
+impl Achievement {
+    // in the order they're listed.
+    pub const ALL: [Achievement; 5] = [
+        Achievement::FoundLizard,
+        Achievement::ReachedFinalLevel,
+        Achievement::CloseCall,
+        Achievement::BigCombo,
+        Achievement::SeedZeroSpeedrun,
+    ];
+
+    pub fn name(self: &Self) -> &'static str {
+        match self {
+            Achievement::FoundLizard => "Lizard spotter",
+            Achievement::ReachedFinalLevel => "All the way",
+            Achievement::CloseCall => "Close call",
+            Achievement::BigCombo => "Combo chain",
+            Achievement::SeedZeroSpeedrun => "Speedrunner",
+        }
+    }
+
+    /// What to do for it, short enough to fit on a line.
+    pub fn description(self: &Self) -> String {
+        match self {
+            Achievement::FoundLizard => "find a lizard".to_string(),
+            Achievement::ReachedFinalLevel => format!["reach level {}", FINAL_LEVEL],
+            Achievement::CloseCall => format!["win with <{}s left", CLOSE_CALL_SECONDS],
+            Achievement::BigCombo => format!["{}-card combo", BIG_COMBO_CARDS],
+            Achievement::SeedZeroSpeedrun => format!["seed 0 in <{} min", SEED_ZERO_TARGET_MINUTES],
+        }
+    }
+
+    fn bit(self: &Self) -> u32 {
+        1 << Achievement::ALL.iter().position(|a| a == self).unwrap_or(0)
+    }
+
+    fn earned_by(self: &Self, event: &AchievementEvent) -> bool {
+        match (self, event) {
+            (Achievement::FoundLizard, AchievementEvent::FoundNpc(sprite)) => *sprite == PresetSprites::Lizard,
+            (Achievement::ReachedFinalLevel, AchievementEvent::ReachedLevel(level)) => *level >= FINAL_LEVEL,
+            (Achievement::CloseCall, AchievementEvent::WonLevel { countdown_timer_msec }) => *countdown_timer_msec < CLOSE_CALL_SECONDS * 60,
+            (Achievement::BigCombo, AchievementEvent::UsedCombo { n_cards }) => *n_cards >= BIG_COMBO_CARDS,
+            (Achievement::SeedZeroSpeedrun, AchievementEvent::WonGame { run_type: RunType::Speedrun(0), speedrun_timer_msec }) => {
+                *speedrun_timer_msec < SEED_ZERO_TARGET_MINUTES * 60 * 60
+            }
+            _ => false,
+        }
+    }
+}
+
+/// Something happened in a game that an achievement might care about.
+pub enum AchievementEvent {
+    FoundNpc(PresetSprites),
+    // a level was started.
+    ReachedLevel(u32),
+    // with this much of the countdown left.
+    WonLevel { countdown_timer_msec: u32 },
+    UsedCombo { n_cards: u32 },
+    // the final level was won.
+    WonGame { run_type: RunType, speedrun_timer_msec: u32 },
+}
+
+/// Which achievements have been unlocked, one bit each.
+#[derive(Clone, Copy)]
+pub struct Achievements {
+    unlocked: u32,
+}
+
+impl Achievements {
+    pub const N_BYTES: usize = 4;
+
+    pub fn new() -> Achievements {
+        Achievements { unlocked: 0 }
+    }
+
+    pub fn is_unlocked(self: &Self, achievement: Achievement) -> bool {
+        self.unlocked & achievement.bit() != 0
+    }
+
+    pub fn n_unlocked(self: &Self) -> usize {
+        Achievement::ALL.iter().filter(|a| self.is_unlocked(**a)).count()
+    }
+
+    /// Unlock whatever the event earns, and give back the ones that weren't unlocked already.
+    pub fn handle_event(self: &mut Self, event: &AchievementEvent) -> Vec<Achievement> {
+        let newly_unlocked: Vec<Achievement> = Achievement::ALL
+            .into_iter()
+            .filter(|a| !self.is_unlocked(*a) && a.earned_by(event))
+            .collect();
+        for achievement in newly_unlocked.iter() {
+            self.unlocked |= achievement.bit();
+        }
+        newly_unlocked
+    }
+
+    pub fn to_bytes(self: &Self) -> [u8; Achievements::N_BYTES] {
+        self.unlocked.to_le_bytes()
+    }
+
+    /// Bits for achievements this build doesn't know about are dropped.
+    pub fn from_bytes(bytes: &[u8]) -> Option<Achievements> {
+        let bytes: [u8; Achievements::N_BYTES] = bytes.get(..Achievements::N_BYTES)?.try_into().ok()?;
+        let known = Achievement::ALL.iter().fold(0, |acc, a| acc | a.bit());
+        Some(Achievements {
+            unlocked: u32::from_le_bytes(bytes) & known,
+        })
+    }
+}
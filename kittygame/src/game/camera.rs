@@ -1,12 +1,14 @@
 
+use super::fixed::Fixed;
+
 #[derive(Clone, Copy)]
 
 pub struct Camera {
-    pub current_viewing_x_offset: f32,
-    pub current_viewing_y_offset: f32,
+    pub current_viewing_x_offset: Fixed,
+    pub current_viewing_y_offset: Fixed,
 
-    pub current_viewing_x_target: f32,
-    pub current_viewing_y_target: f32,
+    pub current_viewing_x_target: Fixed,
+    pub current_viewing_y_target: Fixed,
 }
 
 impl Camera {
@@ -15,12 +17,12 @@ impl Camera {
         let x_err = self.current_viewing_x_target - self.current_viewing_x_offset;
         let y_err = self.current_viewing_y_target - self.current_viewing_y_offset;
 
-        const KP: f32 = 0.3;
+        const KP: Fixed = Fixed::from_f32(0.3);
         self.current_viewing_x_offset += KP * x_err;
         self.current_viewing_y_offset += KP * y_err;
     }
 
-    pub fn cvt_world_to_screen_coords(self: &Self, x_pos: f32, y_pos: f32) -> (f32, f32) {
+    pub fn cvt_world_to_screen_coords(self: &Self, x_pos: Fixed, y_pos: Fixed) -> (Fixed, Fixed) {
         (x_pos - self.current_viewing_x_offset, y_pos - self.current_viewing_y_offset)
     }
 }
\ No newline at end of file
@@ -0,0 +1,283 @@
+// Everything about the cards that's a design choice rather than code: what each card looks like,
+// what it does and how much, how often it turns up, which pairs make combos, which NPC gives which
+// card, and how many cards a player can hold. Rebalancing cards, or adding a variant of one, only
+// means editing these tables.
+
+use crate::spritesheet::PresetSprites;
+
+use super::ability_cards::{AbilityCardTypes, AbilityCardUsageResult};
+
+/// What using a run of cards of one type does. The amount comes from the card's CardFormula.
+#[derive(Clone, Copy)]
+pub enum CardEffect {
+    GainTime,
+    FlyAndTime,
+    WarpAndTime,
+    // amount is in frames.
+    ShowNearestKitty,
+    FreezeCountdown,
+    Dash,
+    AirJumps,
+}
+
+/// How much a run of n cards is worth.
+#[derive(Clone, Copy)]
+pub enum CardFormula {
+    // each card is worth this much.
+    PerCard(u32),
+    // 1 + 2 + ... + n, times this much. Rewards saving cards up.
+    Triangular(u32),
+}
+
+impl CardFormula {
+    pub fn amount(self: &Self, n_cards: u32) -> u32 {
+        match self {
+            CardFormula::PerCard(per_card) => n_cards * per_card,
+            CardFormula::Triangular(per_step) => n_cards * (n_cards + 1) / 2 * per_step,
+        }
+    }
+}
+
+pub struct CardDef {
+    pub card_type: AbilityCardTypes,
+    pub name: &'static str,
+    // cards without a sprite are drawn as a blank card with their letter on it.
+    pub sprite: Option<PresetSprites>,
+    pub letter: &'static str,
+    pub effect: CardEffect,
+    pub formula: CardFormula,
+    // relative chance of a card pickup being this card. 0 never shows up as a pickup.
+    pub pickup_weight: u32,
+    // for cards that turn on an ability: time taken off the amount when they do, rather than adding to it.
+    pub unlock_cost: u32,
+}
+
+pub const CARD_DEFS: [CardDef; 8] = [
+    CardDef {
+        card_type: AbilityCardTypes::Kitty,
+        name: "Kitty",
+        sprite: Some(PresetSprites::KittyCard),
+        letter: "K",
+        effect: CardEffect::GainTime,
+        formula: CardFormula::Triangular(1),
+        pickup_weight: 1,
+        unlock_cost: 0,
+    },
+    CardDef {
+        card_type: AbilityCardTypes::Piggy,
+        name: "Piggy",
+        sprite: Some(PresetSprites::PiggyCard),
+        letter: "P",
+        effect: CardEffect::GainTime,
+        formula: CardFormula::PerCard(10),
+        pickup_weight: 1,
+        unlock_cost: 0,
+    },
+    CardDef {
+        card_type: AbilityCardTypes::Lizard,
+        name: "Lizard",
+        sprite: Some(PresetSprites::LizardCard),
+        letter: "L",
+        effect: CardEffect::WarpAndTime,
+        formula: CardFormula::PerCard(3),
+        pickup_weight: 1,
+        unlock_cost: 3,
+    },
+    CardDef {
+        card_type: AbilityCardTypes::Bird,
+        name: "Bird",
+        sprite: Some(PresetSprites::BirdCard),
+        letter: "B",
+        effect: CardEffect::FlyAndTime,
+        formula: CardFormula::PerCard(3),
+        pickup_weight: 1,
+        unlock_cost: 3,
+    },
+    CardDef {
+        card_type: AbilityCardTypes::Magnet,
+        name: "Magnet",
+        sprite: None,
+        letter: "M",
+        effect: CardEffect::ShowNearestKitty,
+        formula: CardFormula::PerCard(10 * 60),
+        pickup_weight: 1,
+        unlock_cost: 0,
+    },
+    CardDef {
+        card_type: AbilityCardTypes::Freeze,
+        name: "Freeze",
+        sprite: None,
+        letter: "F",
+        effect: CardEffect::FreezeCountdown,
+        formula: CardFormula::PerCard(5 * 60),
+        pickup_weight: 1,
+        unlock_cost: 0,
+    },
+    CardDef {
+        card_type: AbilityCardTypes::Dash,
+        name: "Dash",
+        sprite: None,
+        letter: "D",
+        effect: CardEffect::Dash,
+        formula: CardFormula::PerCard(8),
+        pickup_weight: 1,
+        unlock_cost: 0,
+    },
+    CardDef {
+        card_type: AbilityCardTypes::DoubleJump,
+        name: "Double jump",
+        sprite: None,
+        letter: "J",
+        effect: CardEffect::AirJumps,
+        formula: CardFormula::PerCard(1),
+        pickup_weight: 1,
+        unlock_cost: 0,
+    },
+];
+
+impl CardDef {
+    pub fn get(card_type: AbilityCardTypes) -> &'static CardDef {
+        // every card type has a row.
+        CARD_DEFS.iter().find(|def| def.card_type == card_type).unwrap_or(&CARD_DEFS[0])
+    }
+
+    /// What using n_cards of this card at once does.
+    pub fn result(self: &Self, n_cards: u32) -> AbilityCardUsageResult {
+        self.result_of_amount(self.formula.amount(n_cards))
+    }
+
+    fn result_of_amount(self: &Self, amount: u32) -> AbilityCardUsageResult {
+        match self.effect {
+            CardEffect::GainTime => AbilityCardUsageResult::GainedTime(amount),
+            CardEffect::FlyAndTime => AbilityCardUsageResult::EnabledFlyAndTime(amount, self.unlock_cost),
+            CardEffect::WarpAndTime => AbilityCardUsageResult::EnabledWarpAndTime(amount, self.unlock_cost),
+            CardEffect::ShowNearestKitty => AbilityCardUsageResult::ShowedNearestKitty(amount),
+            CardEffect::FreezeCountdown => AbilityCardUsageResult::FrozeCountdown(amount),
+            CardEffect::Dash => AbilityCardUsageResult::Dashed(amount),
+            CardEffect::AirJumps => AbilityCardUsageResult::GainedAirJumps(amount),
+        }
+    }
+}
+
+/// Pick a card for a card pickup, by pickup_weight, from a random number.
+pub fn pickup_card(rand_val: u64) -> AbilityCardTypes {
+    let total_weight: u64 = CARD_DEFS.iter().map(|def| def.pickup_weight as u64).sum();
+    let mut roll = rand_val % total_weight.max(1);
+    for def in CARD_DEFS.iter() {
+        if roll < def.pickup_weight as u64 {
+            return def.card_type;
+        }
+        roll -= def.pickup_weight as u64;
+    }
+    AbilityCardTypes::Kitty
+}
+
+/// How many cards' worth one half of a combo is.
+#[derive(Clone, Copy)]
+pub enum ComboCards {
+    One,
+    // this many times all the cards used, of both kinds.
+    TimesUsed(u32),
+}
+
+/// One of the two things a combo does: what a card of card_type would, for that many cards.
+pub struct ComboEffect {
+    pub card_type: AbilityCardTypes,
+    pub cards: ComboCards,
+    // the amount is divided by this, for halves that would be too strong at full size.
+    pub divide_by: u32,
+}
+
+/// Using the top card when the card under it is the other half of a combo uses both kinds of card,
+/// in either order.
+pub struct CardCombo {
+    pub cards: (AbilityCardTypes, AbilityCardTypes),
+    pub name: &'static str,
+    // what it does, short enough to fit on screen under the name.
+    pub description: &'static str,
+    pub effects: [ComboEffect; 2],
+}
+
+pub const CARD_COMBOS: [CardCombo; 4] = [
+    CardCombo {
+        cards: (AbilityCardTypes::Lizard, AbilityCardTypes::Bird),
+        name: "sky warp",
+        description: "fly + warp",
+        effects: [
+            ComboEffect { card_type: AbilityCardTypes::Bird, cards: ComboCards::One, divide_by: 1 },
+            ComboEffect { card_type: AbilityCardTypes::Lizard, cards: ComboCards::TimesUsed(1), divide_by: 1 },
+        ],
+    },
+    CardCombo {
+        cards: (AbilityCardTypes::Kitty, AbilityCardTypes::Magnet),
+        name: "kitty radar",
+        description: "2x magnet + time",
+        effects: [
+            ComboEffect { card_type: AbilityCardTypes::Magnet, cards: ComboCards::TimesUsed(2), divide_by: 1 },
+            ComboEffect { card_type: AbilityCardTypes::Kitty, cards: ComboCards::TimesUsed(1), divide_by: 1 },
+        ],
+    },
+    CardCombo {
+        cards: (AbilityCardTypes::Piggy, AbilityCardTypes::Freeze),
+        name: "cold storage",
+        description: "2x freeze + time",
+        effects: [
+            ComboEffect { card_type: AbilityCardTypes::Freeze, cards: ComboCards::TimesUsed(2), divide_by: 1 },
+            ComboEffect { card_type: AbilityCardTypes::Piggy, cards: ComboCards::TimesUsed(1), divide_by: 2 },
+        ],
+    },
+    CardCombo {
+        cards: (AbilityCardTypes::Dash, AbilityCardTypes::DoubleJump),
+        name: "zoomies",
+        description: "dash + air jumps",
+        effects: [
+            ComboEffect { card_type: AbilityCardTypes::Dash, cards: ComboCards::TimesUsed(1), divide_by: 1 },
+            ComboEffect { card_type: AbilityCardTypes::DoubleJump, cards: ComboCards::TimesUsed(1), divide_by: 1 },
+        ],
+    },
+];
+
+impl CardCombo {
+    pub fn find(a: AbilityCardTypes, b: AbilityCardTypes) -> Option<&'static CardCombo> {
+        CARD_COMBOS.iter().find(|combo| combo.cards == (a, b) || combo.cards == (b, a))
+    }
+
+    /// What the combo does, given how many of each of its cards were used. Amounts come from
+    /// the card table, so rebalancing a card rebalances its combos too.
+    pub fn results(self: &Self, n_consumed: [u32; 2]) -> [AbilityCardUsageResult; 2] {
+        let n = n_consumed[0] + n_consumed[1];
+        self.effects.each_ref().map(|effect| {
+            let n_cards = match effect.cards {
+                ComboCards::One => 1,
+                ComboCards::TimesUsed(times) => n * times,
+            };
+            let def = CardDef::get(effect.card_type);
+            def.result_of_amount(def.formula.amount(n_cards) / effect.divide_by)
+        })
+    }
+}
+
+/// Which card finding each kind of NPC gives. NPCs not listed give kitty cards.
+pub const NPC_CARDS: [(PresetSprites, AbilityCardTypes); 7] = [
+    (PresetSprites::Kitty1, AbilityCardTypes::Kitty),
+    (PresetSprites::Kitty2, AbilityCardTypes::Kitty),
+    (PresetSprites::Kitty3, AbilityCardTypes::Kitty),
+    (PresetSprites::Kitty4, AbilityCardTypes::Kitty),
+    (PresetSprites::Pig, AbilityCardTypes::Piggy),
+    (PresetSprites::Lizard, AbilityCardTypes::Lizard),
+    (PresetSprites::BirdIsntReal, AbilityCardTypes::Bird),
+];
+
+pub fn card_for_npc(sprite_type: PresetSprites) -> AbilityCardTypes {
+    match NPC_CARDS.iter().find(|(npc_sprite, _)| *npc_sprite == sprite_type) {
+        Some((_, card_type)) => *card_type,
+        None => AbilityCardTypes::Kitty,
+    }
+}
+
+pub struct DeckRules {
+    // cards found past this many are lost.
+    pub stack_capacity: usize,
+}
+
+pub const DECK_RULES: DeckRules = DeckRules { stack_capacity: 5 };
@@ -0,0 +1,195 @@
+use super::mapchunk::MapChunk;
+
+// The chunk index is a grid of cells over the whole map, each CHUNK_INDEX_CELL_TILES x CHUNK_INDEX_CELL_TILES
+// tiles, listing which chunks touch each cell. Most cells only touch one or two chunks, so looking up what's
+// at a point is a couple of bound checks instead of one per chunk.
+const CHUNK_INDEX_CELL_TILES: i32 = 8;
+
+// chunk ids are stored as u16s, and so are the offsets into the id list.
+const CHUNK_INDEX_MAX_IDS: usize = u16::MAX as usize;
+
+pub struct ChunkIndex {
+    // top left of the grid, in tiles.
+    origin_x: i32,
+    origin_y: i32,
+    // size of the grid, in cells. Zero if there's no index for this map.
+    width: i32,
+    height: i32,
+    // how many chunks the map had when the index was built. If it has changed since, the index is stale.
+    n_chunks: usize,
+    // cell i lists ids[cell_starts[i]..cell_starts[i + 1]], in increasing order.
+    cell_starts: Vec<u16>,
+    ids: Vec<u16>,
+}
+
+impl ChunkIndex {
+    pub fn new() -> ChunkIndex {
+        ChunkIndex {
+            origin_x: 0,
+            origin_y: 0,
+            width: 0,
+            height: 0,
+            n_chunks: 0,
+            cell_starts: Vec::new(),
+            ids: Vec::new(),
+        }
+    }
+
+    /// Drop the index and give its memory back, so there's room to generate the next map.
+    pub fn clear(self: &mut Self) {
+        *self = ChunkIndex::new();
+    }
+
+    /// Is there an index, and is it for these chunks?
+    pub fn is_built_for(self: &Self, chunks: &[MapChunk]) -> bool {
+        self.width > 0 && self.n_chunks == chunks.len()
+    }
+
+    // the range of cells covering this range of tiles, clamped to the grid. Empty if it misses the grid.
+    fn cells_covering(self: &Self, tile_x0: i32, tile_y0: i32, tile_x1: i32, tile_y1: i32) -> (i32, i32, i32, i32) {
+        (
+            ((tile_x0 - self.origin_x).div_euclid(CHUNK_INDEX_CELL_TILES)).max(0),
+            ((tile_y0 - self.origin_y).div_euclid(CHUNK_INDEX_CELL_TILES)).max(0),
+            ((tile_x1 - self.origin_x).div_euclid(CHUNK_INDEX_CELL_TILES)).min(self.width - 1),
+            ((tile_y1 - self.origin_y).div_euclid(CHUNK_INDEX_CELL_TILES)).min(self.height - 1),
+        )
+    }
+
+    // each chunk goes in every cell its tiles touch, plus the row and column of tiles before it:
+    // MapChunk::get_tile_abs rounds toward zero, so its first row and column reach a few pixels further out.
+    fn chunk_tiles(chunk: &MapChunk) -> (i32, i32, i32, i32) {
+        (
+            chunk.bound.x - 1,
+            chunk.bound.y - 1,
+            chunk.bound.x + chunk.bound.width as i32 - 1,
+            chunk.bound.y + chunk.bound.height as i32 - 1,
+        )
+    }
+
+    /// Build the index for a freshly generated map. If the map is too big, or there isn't enough
+    /// memory, there's no index and lookups check every chunk.
+    pub fn rebuild(self: &mut Self, chunks: &[MapChunk]) {
+        self.clear();
+        if chunks.is_empty() || chunks.len() > CHUNK_INDEX_MAX_IDS {
+            return;
+        }
+
+        let mut min_x = i32::MAX;
+        let mut min_y = i32::MAX;
+        let mut max_x = i32::MIN;
+        let mut max_y = i32::MIN;
+        for chunk in chunks.iter() {
+            let (x0, y0, x1, y1) = ChunkIndex::chunk_tiles(chunk);
+            min_x = min_x.min(x0);
+            min_y = min_y.min(y0);
+            max_x = max_x.max(x1);
+            max_y = max_y.max(y1);
+        }
+        self.origin_x = min_x;
+        self.origin_y = min_y;
+        self.width = (max_x - min_x) / CHUNK_INDEX_CELL_TILES + 1;
+        self.height = (max_y - min_y) / CHUNK_INDEX_CELL_TILES + 1;
+        let n_cells = (self.width * self.height) as usize;
+
+        // count how many chunks land in each cell, then lay the lists out back to back.
+        let mut counts: Vec<u16> = Vec::new();
+        if counts.try_reserve_exact(n_cells + 1).is_err() {
+            self.clear();
+            return;
+        }
+        counts.resize(n_cells + 1, 0);
+        let mut n_ids: usize = 0;
+        for chunk in chunks.iter() {
+            let (x0, y0, x1, y1) = ChunkIndex::chunk_tiles(chunk);
+            let (cx0, cy0, cx1, cy1) = self.cells_covering(x0, y0, x1, y1);
+            for cy in cy0..=cy1 {
+                for cx in cx0..=cx1 {
+                    counts[(cy * self.width + cx) as usize] += 1;
+                    n_ids += 1;
+                }
+            }
+        }
+        if n_ids > CHUNK_INDEX_MAX_IDS
+            || self.cell_starts.try_reserve_exact(n_cells + 1).is_err()
+            || self.ids.try_reserve_exact(n_ids).is_err()
+        {
+            self.clear();
+            return;
+        }
+        let mut start: u16 = 0;
+        for count in counts.iter_mut() {
+            self.cell_starts.push(start);
+            start += *count;
+            // from here on, count is where the next id in this cell goes.
+            *count = self.cell_starts[self.cell_starts.len() - 1];
+        }
+        self.ids.resize(n_ids, 0);
+        for (id, chunk) in chunks.iter().enumerate() {
+            let (x0, y0, x1, y1) = ChunkIndex::chunk_tiles(chunk);
+            let (cx0, cy0, cx1, cy1) = self.cells_covering(x0, y0, x1, y1);
+            for cy in cy0..=cy1 {
+                for cx in cx0..=cx1 {
+                    let next = &mut counts[(cy * self.width + cx) as usize];
+                    self.ids[*next as usize] = id as u16;
+                    *next += 1;
+                }
+            }
+        }
+        self.n_chunks = chunks.len();
+    }
+
+    /// Ids of the chunks that might cover some of these tiles, in increasing order, each once.
+    /// Without an index, that's every chunk.
+    pub fn candidates(self: &Self, n_chunks: usize, tile_x0: i32, tile_y0: i32, tile_x1: i32, tile_y1: i32) -> ChunkCandidates<'_> {
+        let cells = match self.width > 0 && self.n_chunks == n_chunks {
+            true => Some(self.cells_covering(tile_x0, tile_y0, tile_x1, tile_y1)),
+            false => None,
+        };
+        ChunkCandidates {
+            index: self,
+            n_chunks,
+            cells,
+            next_id: 0,
+        }
+    }
+}
+
+pub struct ChunkCandidates<'a> {
+    index: &'a ChunkIndex,
+    n_chunks: usize,
+    // the cells to look in, or None to go through every chunk.
+    cells: Option<(i32, i32, i32, i32)>,
+    next_id: usize,
+}
+
+impl<'a> Iterator for ChunkCandidates<'a> {
+    type Item = usize;
+
+    fn next(self: &mut Self) -> Option<usize> {
+        let id = match self.cells {
+            None => match self.next_id < self.n_chunks {
+                true => self.next_id,
+                false => return None,
+            },
+            Some((cx0, cy0, cx1, cy1)) => {
+                // a chunk can be in several of our cells, so take the lowest id we haven't handed out yet
+                // across all of them. Queries only ever cover a handful of cells.
+                let mut lowest: Option<usize> = None;
+                for cy in cy0..=cy1 {
+                    for cx in cx0..=cx1 {
+                        let cell = (cy * self.index.width + cx) as usize;
+                        let lo = self.index.cell_starts[cell] as usize;
+                        let hi = self.index.cell_starts[cell + 1] as usize;
+                        let found = self.index.ids[lo..hi].iter().map(|&id| id as usize).find(|&id| id >= self.next_id);
+                        if let Some(id) = found {
+                            lowest = Some(lowest.map_or(id, |l| l.min(id)));
+                        }
+                    }
+                }
+                lowest?
+            }
+        };
+        self.next_id = id + 1;
+        Some(id)
+    }
+}
@@ -1,16 +1,25 @@
 
 use crate::{
-    game::{ability_cards::{AbilityCardStack, AbilityCardTypes}, entities::{Player, WarpAbility, WarpState}, popup_text::{PopTextRingbuffer, PopupIcon}}, multiplatform_defs::{BUTTON_1, BUTTON_DOWN, BUTTON_LEFT, BUTTON_RIGHT}, spritesheet
+    game::{card_content::card_for_npc, entities::{WarpAbility, WarpState}, popup_text::{PopTextRingbuffer, PopupIcon}}, multiplatform_defs::{BUTTON_1, BUTTON_DOWN, BUTTON_LEFT, BUTTON_RIGHT, BUTTON_UP}
 };
 
 use super::{
+    achievements::AchievementEvent,
     entities::{Character, KittyStates, MovingEntity, OptionallyEnabledPlayer},
     game_constants::{
         TILE_HEIGHT_PX, TILE_WIDTH_PX, X_LEFT_BOUND, X_RIGHT_BOUND, Y_LOWER_BOUND, Y_UPPER_BOUND,
     },
-    game_map::GameMap,
+    dynamic_solids::DynamicSolid,
+    fixed::Fixed,
+    game_map::{GameMap, TileProperty},
     game_state::GameState,
+    contacts::{Contact, EntityRef},
+    hazards::{HAZARD_HIT_PENALTY, HAZARD_INVULNERABLE_FRAMES},
+    menus::{GameMode, NormalPlayModes},
+    music::SoundEffect,
+    pickups::{PickupKind, CLOCK_PICKUP_TIME, YARN_PICKUP_SCORE},
     mapchunk::{MapChunk, TileAlignedBoundingBox}, cloud::Cloud,
+    warp::{WarpDestinations, WARP_CHARGE_FRAMES},
 };
 
 // use crate::wasm4::*;
@@ -39,6 +48,7 @@ pub fn check_absolute_point_inside_tile_aligned_bound(
     false
 }
 
+#[derive(Hash)]
 pub struct AbsoluteBoundingBox<P, W> {
     pub x: P,
     pub y: P,
@@ -100,51 +110,36 @@ pub fn check_absolute_bounding_box_partially_inside_another(
 pub fn get_bound_of_character(character: &Character) -> AbsoluteBoundingBox<i32, u32> {
     let char_positioning = character.sprite.frames[character.current_sprite_i as usize];
     AbsoluteBoundingBox {
-        x: character.x_pos as i32,
-        y: character.y_pos as i32,
+        x: character.x_pos.to_i32(),
+        y: character.y_pos.to_i32(),
         width: char_positioning.width as u32,
         height: char_positioning.height as u32,
     }
 }
 
+// is there a solid tile right up against the side the character is facing?
+fn check_wall_beside_character(map: &GameMap, character: &Character) -> bool {
+    let bound = get_bound_of_character(character);
+    let xs = match character.is_facing_right {
+        true => [bound.x + bound.width as i32 - 1, bound.x + bound.width as i32, bound.x + bound.width as i32 + 1],
+        false => [bound.x, bound.x - 1, bound.x - 2],
+    };
+    xs.iter().any(|&x| {
+        map.is_solid_abs(x, bound.y + 1) || map.is_solid_abs(x, bound.y + bound.height as i32 - 2)
+    })
+}
+
 pub fn check_entity_collisions(game_state: &mut GameState) {
     // player -> npc
-    const N_PLAYER_NPC_COLLISIONS_TO_CHECK_AT_MOST: usize = 10;
-    let mut npc_hitlist: [(u8, u8); N_PLAYER_NPC_COLLISIONS_TO_CHECK_AT_MOST] =
-        [(0, 0); N_PLAYER_NPC_COLLISIONS_TO_CHECK_AT_MOST];
-    let mut hitlist_i: u8 = 0;
-    for (i, opt_p) in game_state.players.iter().enumerate() {
-        if let OptionallyEnabledPlayer::Enabled(p) = opt_p {
-            for (j, npc2) in game_state.npcs.iter().enumerate() {
-                let did_hit: bool;
-                {
-                    let player_bound = get_bound_of_character(&p.character);
-
-                    let npc2_bound = get_bound_of_character(npc2);
-                    did_hit = check_absolute_bounding_box_partially_inside_another(
-                        &player_bound,
-                        &npc2_bound,
-                    );
-                }
-                match did_hit {
-                    true => {
-                        // npcs hit
-                        if hitlist_i < npc_hitlist.len() as u8 {
-                            npc_hitlist[hitlist_i as usize] = (i as u8, j as u8);
-                            hitlist_i += 1;
-                        }
-                    }
-                    _ => {}
-                }
-            }
-        }
-    }
-
-    for (hit_p_i, hit_npc_i) in &npc_hitlist[..hitlist_i as usize] {
-        let opt_p = &mut game_state.players[*hit_p_i as usize];
+    for contact_i in 0..game_state.contacts.len() {
+        let (hit_p_i, hit_npc_i) = match game_state.contacts[contact_i] {
+            Contact { a: EntityRef::Player(p_i), b: EntityRef::Npc(npc_i) } => (p_i, npc_i),
+            _ => continue,
+        };
+        let opt_p = &mut game_state.players[hit_p_i];
 
         if let OptionallyEnabledPlayer::Enabled(p) = opt_p {
-            let npc = &mut game_state.npcs[*hit_npc_i as usize];
+            let npc = &mut game_state.npcs[hit_npc_i];
 
             let pop_x = npc.x_pos;
             let pop_y = npc.y_pos;
@@ -157,16 +152,11 @@ pub fn check_entity_collisions(game_state: &mut GameState) {
 
                     let gained_amount = 1 * 60;
 
-                    popup_texts_rb.add_new_popup(pop_x - 7.0, pop_y, format![" +{}", gained_amount/60].to_string(), PopupIcon::CatHead);
+                    popup_texts_rb.add_new_popup(pop_x - Fixed::from_int(7), pop_y, format![" +{}", gained_amount/60].to_string(), PopupIcon::CatHead);
 
                     // add card
-                    let abil_card_type = match npc.sprite_type {
-                        spritesheet::PresetSprites::Kitty1 | spritesheet::PresetSprites::Kitty2 | spritesheet::PresetSprites::Kitty3 | spritesheet::PresetSprites::Kitty4 => AbilityCardTypes::Kitty,
-                        spritesheet::PresetSprites::Pig => AbilityCardTypes::Piggy,
-                        spritesheet::PresetSprites::Lizard => AbilityCardTypes::Lizard,
-                        spritesheet::PresetSprites::BirdIsntReal => AbilityCardTypes::Bird,
-                        _ => AbilityCardTypes::Kitty,
-                    };
+                    let abil_card_type = card_for_npc(npc.sprite_type);
+                    p.warp.last_found_npc = Some((npc.x_pos, npc.y_pos));
 
                     // spawn some clouds
                     for dir in [(1.0, 0.0), (0.5, 0.86), (-0.5, 0.86), (-1.0, 0.0), (-0.5, -0.86), (0.5, -0.86)] {
@@ -174,7 +164,7 @@ pub fn check_entity_collisions(game_state: &mut GameState) {
 
                         let vx = CARD_CLOUD_SPEED * dir.0;
                         let vy = CARD_CLOUD_SPEED * dir.1;
-                        Cloud::try_push_cloud(&mut game_state.clouds, npc.x_pos + 2.0, npc.y_pos + 3.0, vx, vy);
+                        Cloud::try_push_cloud(&mut game_state.clouds, npc.x_pos.to_f32() + 2.0, npc.y_pos.to_f32() + 3.0, vx, vy);
 
                     }
 
@@ -186,179 +176,338 @@ pub fn check_entity_collisions(game_state: &mut GameState) {
                     game_state.countdown_timer_msec += gained_amount;
                     game_state.countdown_timer_msec = game_state.countdown_timer_msec.min(100 * 60 - 1);
                     game_state.score += gained_amount;
+                    // the title and select screens play themselves, and nothing there counts.
+                    if let GameMode::NormalPlay(NormalPlayModes::MainGameplay) = game_state.game_mode {
+                        game_state.achievement_events.push(AchievementEvent::FoundNpc(npc.sprite_type));
+                        game_state.sound_effects.push(SoundEffect::FoundNpc);
+                        game_state.run_stats.npc_found(npc.sprite_type);
+                    }
                 }
                 Some(_) => {}
             }
 
             // p.y_pos -= 2.0;
-            npc.following_i = Some(*hit_p_i);
+            npc.following_i = Some(hit_p_i as u8);
         }
     }
 }
 
-pub struct CollisionResult {
-    allowable_displacement: i32,
-    collided: bool,
-    backed_up: bool,
+pub fn check_hazard_collisions(game_state: &mut GameState) {
+    for (i, opt_p) in game_state.players.iter_mut().enumerate() {
+        if let OptionallyEnabledPlayer::Enabled(p) = opt_p {
+            if p.invulnerable_frames > 0 {
+                p.invulnerable_frames -= 1;
+                continue;
+            }
+            if game_state.godmode {
+                continue;
+            }
+            // spiky tiles hurt just as much as hazards do.
+            let did_hit = p.character.touching_damaging_tile || game_state.contacts.iter().any(|contact| match contact {
+                Contact { a: EntityRef::Player(p_i), b: EntityRef::Hazard(hazard_i) } => {
+                    *p_i == i && game_state.hazards[*hazard_i].is_dangerous()
+                }
+                _ => false,
+            });
+            if did_hit {
+                // ouch! lose some time, but never drop straight to zero here,
+                // so the countdown itself still ends the game.
+                let lost_amount = HAZARD_HIT_PENALTY.min(game_state.countdown_timer_msec.saturating_sub(1));
+                game_state.countdown_timer_msec -= lost_amount;
+                game_state.popup_text_ringbuffer.add_new_popup(p.character.x_pos - Fixed::from_int(7), p.character.y_pos, format![" -{}", HAZARD_HIT_PENALTY / 60].to_string(), PopupIcon::Clock);
+                p.invulnerable_frames = HAZARD_INVULNERABLE_FRAMES;
+                game_state.sound_effects.push(SoundEffect::HazardHit);
+
+                for dir in [(1.0, 0.0), (0.5, 0.86), (-0.5, 0.86), (-1.0, 0.0), (-0.5, -0.86), (0.5, -0.86)] {
+                    const HIT_CLOUD_SPEED: f32 = 3.0;
+                    Cloud::try_push_cloud(&mut game_state.clouds, p.character.x_pos.to_f32() + 2.0, p.character.y_pos.to_f32() + 3.0, HIT_CLOUD_SPEED * dir.0, HIT_CLOUD_SPEED * dir.1);
+                }
+            }
+        }
+    }
 }
 
-pub fn raycast_axis_aligned(
-    horizontal: bool,
-    positive: bool,
-    abs_start_pt: (i32, i32),
-    ray_displacement: i32,
-    chunk: &MapChunk,
-) -> CollisionResult {
-    const DIST_PER_ITER: i32 = 1;
-
-    let mut collision_result = CollisionResult {
-        allowable_displacement: ray_displacement,
-        collided: true,
-        backed_up: false,
-    };
-
-    let ray_x_dist_per_iter: i32;
-    let ray_y_dist_per_iter: i32;
+pub fn check_pickup_collisions(game_state: &mut GameState) {
+    // each pickup goes to the first player touching it.
+    let mut taken = core::mem::take(&mut game_state.taken_pickups);
+    taken.clear();
+    for contact in game_state.contacts.iter() {
+        if let Contact { a: EntityRef::Player(p_i), b: EntityRef::Pickup(pickup_i) } = contact {
+            if !taken.iter().any(|(taken_i, _)| taken_i == pickup_i) {
+                taken.push((*pickup_i, *p_i));
+            }
+        }
+    }
+    // take them from the back, so removing one doesn't move any we still have to take.
+    taken.sort_unstable_by(|a, b| b.0.cmp(&a.0));
 
-    // has the opposite sign of the ray displacement, for N iters.
+    for &(pickup_i, p_i) in taken.iter() {
+        let p = match &mut game_state.players[p_i] {
+            OptionallyEnabledPlayer::Enabled(p) => p,
+            OptionallyEnabledPlayer::Disabled => continue,
+        };
 
-    let mut vertical_ray: i32 = 0;
-    let mut horizontal_ray: i32 = 0;
+        let pickup = game_state.pickups.swap_remove(pickup_i);
+        // pickups sit on whole pixels.
+        let (pickup_x, pickup_y) = (Fixed::from_int(pickup.x_pos as i32), Fixed::from_int(pickup.y_pos as i32));
+        game_state.pickups_collected += 1;
+        game_state.sound_effects.push(SoundEffect::Pickup);
+        let popup_texts_rb: &mut PopTextRingbuffer = &mut game_state.popup_text_ringbuffer;
+        match pickup.kind {
+            PickupKind::Clock => {
+                game_state.countdown_timer_msec += CLOCK_PICKUP_TIME;
+                game_state.countdown_timer_msec = game_state.countdown_timer_msec.min(100 * 60 - 1);
+                popup_texts_rb.add_new_popup(pickup_x - Fixed::from_int(7), pickup_y, format![" +{}", CLOCK_PICKUP_TIME / 60].to_string(), PopupIcon::Clock);
+            }
+            PickupKind::Yarn => {
+                game_state.score += YARN_PICKUP_SCORE;
+                popup_texts_rb.add_new_popup(pickup_x - Fixed::from_int(7), pickup_y, format!["+{}p", YARN_PICKUP_SCORE].to_string(), PopupIcon::None);
+            }
+            PickupKind::Card(card_type) => {
+                let card_p = game_state.camera.cvt_world_to_screen_coords(pickup_x, pickup_y);
+                p.card_stack.try_push_card(card_type, card_p.0, card_p.1);
+                popup_texts_rb.add_new_popup(pickup_x - Fixed::from_int(10), pickup_y, "card!".to_string(), PopupIcon::None);
+            }
+        }
 
-    let start_pt;
+        // spawn some clouds
+        for dir in [(1.0, 0.0), (0.5, 0.86), (-0.5, 0.86), (-1.0, 0.0), (-0.5, -0.86), (0.5, -0.86)] {
+            const PICKUP_CLOUD_SPEED: f32 = 3.0;
 
-    if horizontal {
-        start_pt = (abs_start_pt.0, abs_start_pt.1 - 1);
-        if positive {
-            ray_x_dist_per_iter = DIST_PER_ITER;
-            ray_y_dist_per_iter = 0;
-        } else {
-            ray_x_dist_per_iter = -DIST_PER_ITER;
-            ray_y_dist_per_iter = 0;
-        }
-    } else {
-        start_pt = (abs_start_pt.0, abs_start_pt.1);
-        if positive {
-            ray_x_dist_per_iter = 0;
-            ray_y_dist_per_iter = DIST_PER_ITER;
-        } else {
-            ray_x_dist_per_iter = 0;
-            ray_y_dist_per_iter = -DIST_PER_ITER;
+            let vx = PICKUP_CLOUD_SPEED * dir.0;
+            let vy = PICKUP_CLOUD_SPEED * dir.1;
+            Cloud::try_push_cloud(&mut game_state.clouds, pickup.x_pos, pickup.y_pos + 1.0, vx, vy);
         }
     }
+    game_state.taken_pickups = taken;
+}
 
-    let mut should_clamp_x = false;
-    let mut should_clamp_y = false;
+pub struct SweepResult {
+    collided: bool,
+    // the edge started out already overlapping something.
+    backed_up: bool,
+    // what we ran into. If several things at once, the most interesting one.
+    tile: TileProperty,
+    touched_damaging: bool,
+    // the dynamic solid we ran into, if any.
+    solid_i: Option<usize>,
+}
 
-    // travel along, see if it's colliding with things
+/// Springs beat ice, ice beats spikes, and anything beats open air.
+fn more_interesting_tile(a: TileProperty, b: TileProperty) -> TileProperty {
+    fn rank(tile: TileProperty) -> u8 {
+        match tile {
+            TileProperty::Empty => 0,
+            TileProperty::OneWayUp => 1,
+            TileProperty::Solid => 2,
+            TileProperty::Damaging => 3,
+            TileProperty::LowFriction => 4,
+            TileProperty::Bouncy => 5,
+        }
+    }
+    match rank(b) > rank(a) {
+        true => b,
+        false => a,
+    }
+}
 
-    let mut on_first_iter: bool = true;
+/// Sweep one edge of a bounding box along an axis, through a chunk's tiles and the map's dynamic solids.
+///
+/// The edge sits at `start` along the axis and covers `span` (inclusive) across it. Each column of
+/// tiles under the edge is swept on its own, from `start` to one pixel past `displacement`, reading
+/// each tile once and stopping at the first thing in the way or at the edge of the chunk.
+/// A column that already overlaps something backs up until it's clear, and counts as `backed_up`.
+/// If it runs out of chunk before then, it's inside a wall that isn't ours to deal with, and is ignored.
+pub fn sweep_axis_aligned(
+    horizontal: bool,
+    positive: bool,
+    start: i32,
+    span: (i32, i32),
+    displacement: i32,
+    chunk: &MapChunk,
+    tile_properties: &[TileProperty; 16],
+    dynamic_solids: &[DynamicSolid],
+) -> SweepResult {
+    let mut result = SweepResult {
+        collided: false,
+        backed_up: false,
+        tile: TileProperty::Empty,
+        touched_damaging: false,
+        solid_i: None,
+    };
 
-    loop {
-        // make sure this ray even is in the chunk in the first place
-        match chunk.get_tile_abs(
-            start_pt.0 + horizontal_ray as i32,
-            start_pt.1 + vertical_ray as i32,
-        ) {
-            Ok(tile) => {
-                if tile != 0 {
-                    collision_result.collided = true;
-                    // if we hit a tile first thing, we need to back up until we DON'T hit anything.
-                    if on_first_iter {
-                        collision_result.backed_up = true;
-                        on_first_iter = false;
-                    }
-                    // if we're not backing up and we hit something, we're done
-                    else if !collision_result.backed_up {
-                        // text(format!["hit tile {lowerleft_vertical_ray}"], 10, 50);
-                        if horizontal {
-                            should_clamp_x = true;
-                        } else {
-                            should_clamp_y = true;
-                        }
+    let dir = match positive {
+        true => 1,
+        false => -1,
+    };
+    // lay the chunk out along our axis
+    let (tile_len, cross_tile_len, along_origin, along_len, cross_origin, cross_len) = match horizontal {
+        true => (
+            TILE_WIDTH_PX as i32,
+            TILE_HEIGHT_PX as i32,
+            chunk.bound.x * TILE_WIDTH_PX as i32,
+            chunk.bound.width as i32,
+            chunk.bound.y * TILE_HEIGHT_PX as i32,
+            chunk.bound.height as i32,
+        ),
+        false => (
+            TILE_HEIGHT_PX as i32,
+            TILE_WIDTH_PX as i32,
+            chunk.bound.y * TILE_HEIGHT_PX as i32,
+            chunk.bound.height as i32,
+            chunk.bound.x * TILE_WIDTH_PX as i32,
+            chunk.bound.width as i32,
+        ),
+    };
+    // rounds toward zero, the same way MapChunk::get_tile_abs does. So the first row and column
+    // of a chunk reach a few pixels further out than the rest.
+    let to_tile = |px: i32, origin: i32, len: i32| (px - origin) / len;
+    let first_cross_tile = to_tile(span.0, cross_origin, cross_tile_len).max(0);
+    let last_cross_tile = to_tile(span.1, cross_origin, cross_tile_len).min(cross_len - 1);
+
+    // the dynamic solids across our edge, as (index, along range, cross range).
+    let solids_across = || {
+        dynamic_solids
+            .iter()
+            .enumerate()
+            .filter(|(_, solid)| solid.is_solid())
+            .filter_map(move |(i, solid)| {
+                let bound = solid.get_bound();
+                let (along, along_w, cross, cross_w) = match horizontal {
+                    true => (bound.x, bound.width as i32, bound.y, bound.height as i32),
+                    false => (bound.y, bound.height as i32, bound.x, bound.width as i32),
+                };
+                match cross <= span.1 && cross + cross_w > span.0 {
+                    true => Some((i, (along, along + along_w - 1), (cross, cross + cross_w - 1))),
+                    false => None,
+                }
+            })
+    };
+    // usually there are none.
+    let any_solids_across = solids_across().next().is_some();
+
+    for cross_tile in first_cross_tile..=last_cross_tile {
+        // the part of our edge in this column
+        let mut lane_lo = cross_origin + cross_tile * cross_tile_len;
+        if cross_tile == 0 {
+            lane_lo -= cross_tile_len - 1;
+        }
+        let lane_lo = lane_lo.max(span.0);
+        let lane_hi = (cross_origin + (cross_tile + 1) * cross_tile_len - 1).min(span.1);
+
+        // what's in the way at this pixel along the axis? None if we've left the chunk.
+        let mut last_along_tile = i32::MIN;
+        let mut last_tile = TileProperty::Empty;
+        let mut blocker_at = |along: i32| -> Option<(TileProperty, Option<usize>)> {
+            let along_tile = to_tile(along, along_origin, tile_len);
+            if along_tile < 0 || along_tile >= along_len {
+                return None;
+            }
+            // only read each tile once
+            if along_tile != last_along_tile {
+                let (col, row) = match horizontal {
+                    true => (along_tile, cross_tile),
+                    false => (cross_tile, along_tile),
+                };
+                last_along_tile = along_tile;
+                last_tile = tile_properties[chunk.get_tile(col as usize, row as usize) as usize & 0xf];
+            }
+            let blocks = match last_tile {
+                TileProperty::Empty => false,
+                // one-way tiles only stop an edge falling onto their top.
+                TileProperty::OneWayUp => !horizontal && positive && along.rem_euclid(TILE_HEIGHT_PX as i32) == 0,
+                _ => true,
+            };
+            if blocks {
+                return Some((last_tile, None));
+            }
+            // platforms and crumbling blocks block like plain rock.
+            let in_the_way = solids_across().find(|(_, (lo, hi), (cross_lo, cross_hi))| {
+                along >= *lo && along <= *hi && *cross_lo <= lane_hi && *cross_hi >= lane_lo
+            });
+            match in_the_way {
+                Some((i, _, _)) => Some((TileProperty::Solid, Some(i))),
+                None => Some((TileProperty::Empty, None)),
+            }
+        };
 
+        let mut hit: Option<(TileProperty, Option<usize>)> = None;
+        let mut lane_backed_up = false;
+        match blocker_at(start) {
+            None => continue,
+            Some((TileProperty::Empty, _)) => {
+                // look one pixel past where we're going, so we notice floors we're resting on.
+                let reach = displacement.abs() + 1;
+                let mut k = 0;
+                loop {
+                    // with only tiles in the way, nothing changes until the next tile starts
+                    // (which is also where one-way tops are), so jump straight there.
+                    let along = start + k * dir;
+                    k += match (any_solids_across, positive) {
+                        (false, true) => tile_len - along.rem_euclid(tile_len),
+                        (false, false) => along.rem_euclid(tile_len) + 1,
+                        (true, _) => 1,
+                    };
+                    if k > reach {
                         break;
                     }
-                } else {
-                    // if we don't hit a tile first thing, we should cast forward until we DO hit something.
-                    if on_first_iter {
-                        collision_result.backed_up = false;
-                        on_first_iter = false;
-                    }
-                    // if we're backing up and we're not hitting something, we're done backing up
-                    else if collision_result.backed_up {
-                        if horizontal {
-                            should_clamp_x = true;
-                        } else {
-                            should_clamp_y = true;
+                    match blocker_at(start + k * dir) {
+                        None => break,
+                        Some((TileProperty::Empty, _)) => {}
+                        blocker => {
+                            hit = blocker;
+                            break;
                         }
-
-                        break;
                     }
                 }
             }
-            Err(_) => {
-                break collision_result.collided = false;
+            Some(first_blocker) => {
+                // already inside something: back up until we're out of it.
+                lane_backed_up = true;
+                let mut along = start;
+                let mut last_blocker = first_blocker;
+                loop {
+                    along -= dir;
+                    match blocker_at(along) {
+                        None => break,
+                        Some((TileProperty::Empty, _)) => {
+                            hit = Some(last_blocker);
+                            break;
+                        }
+                        Some(blocker) => last_blocker = blocker,
+                    }
+                }
             }
         }
 
-        if !on_first_iter && !collision_result.backed_up {
-            if horizontal {
-                if horizontal_ray.abs() > ray_displacement.abs() {
-                    collision_result.collided = false;
-                    break;
-                }
-            } else {
-                if vertical_ray.abs() > ray_displacement.abs() {
-                    collision_result.collided = false;
-                    break;
-                }
+        if let Some((tile, solid_i)) = hit {
+            result.collided = true;
+            result.backed_up |= lane_backed_up;
+            result.touched_damaging |= tile == TileProperty::Damaging;
+            result.tile = more_interesting_tile(result.tile, tile);
+            if solid_i.is_some() {
+                result.solid_i = solid_i;
             }
         }
-
-        let dir = match collision_result.backed_up {
-            true => -1,
-            false => 1,
-        };
-        vertical_ray += ray_y_dist_per_iter * dir;
-        horizontal_ray += ray_x_dist_per_iter * dir;
-    }
-    if should_clamp_x {
-        collision_result.allowable_displacement = vertical_ray;
-    } else if should_clamp_y {
-        collision_result.allowable_displacement = horizontal_ray;
     }
-    collision_result
+    result
 }
 
 // handle inputs of players and other characters.
 pub fn update_pos(map: &GameMap, moving_entity: MovingEntity, input: u8, godmode: bool, clouds:&mut Vec<Cloud>) {
     let character: &mut Character;
+    // NPCs don't warp.
+    let mut warp_destinations: Option<&mut WarpDestinations> = None;
 
     match moving_entity {
         MovingEntity::OptionalPlayer(optionally_enabled_player) => {
             match optionally_enabled_player {
                 OptionallyEnabledPlayer::Enabled(ch) => {
                     character = &mut ch.character;
+                    warp_destinations = Some(&mut ch.warp);
                 }
-                OptionallyEnabledPlayer::Disabled => {
-                    if input != 0 {
-                        *optionally_enabled_player = OptionallyEnabledPlayer::Enabled(Player {
-                            character: Character::new(spritesheet::PresetSprites::MainCat),
-                            card_stack: AbilityCardStack {
-                                cards: Vec::new()
-                            },
-                        });
-                        match optionally_enabled_player {
-                            OptionallyEnabledPlayer::Enabled(ch) => {
-                                character = &mut ch.character;
-                            }
-                            _ => return,
-                        }
-                    } else {
-                        return;
-                    }
-                }
+                // players join before this, as whoever they picked.
+                OptionallyEnabledPlayer::Disabled => return,
             }
         }
         MovingEntity::NPC(npc) => {
@@ -366,9 +515,18 @@ pub fn update_pos(map: &GameMap, moving_entity: MovingEntity, input: u8, godmode
         }
     }
 
-    const BTN_ACCEL: f32 = 0.85;
-    const HOP_V: f32 = -5.0;
-    const H_DECAY: f32 = 0.92;
+    // while holding down to warp, left and right pick where to, instead of walking.
+    let warp_input = input;
+    let input = match (&warp_destinations, &character.warp_ability) {
+        (Some(_), WarpAbility::CanWarp(_)) if input & BUTTON_DOWN != 0 => input & !(BUTTON_LEFT | BUTTON_RIGHT),
+        _ => input,
+    };
+
+    // ride along with whatever platform we were standing on.
+    if let Some(solid) = character.riding_solid.and_then(|i| map.dynamic_solids.get(i)) {
+        character.x_pos += Fixed::from_int(solid.moved_x);
+        character.y_pos += Fixed::from_int(solid.moved_y);
+    }
 
     enum HorizontalMovementOutcome {
         ChangedDirection,
@@ -381,17 +539,25 @@ pub fn update_pos(map: &GameMap, moving_entity: MovingEntity, input: u8, godmode
         let ret;
         let previous_direction = the_char.is_facing_right;
 
+        // slippery tiles are hard to get going on, and hard to stop on.
+        const ICE_ACCEL_MULT: Fixed = Fixed::from_f32(0.2);
+        const ICE_H_DECAY: Fixed = Fixed::from_f32(0.99);
+        let (btn_accel, h_decay) = match the_char.ground_tile {
+            TileProperty::LowFriction => (the_char.species.physics.btn_accel * ICE_ACCEL_MULT, ICE_H_DECAY),
+            _ => (the_char.species.physics.btn_accel, the_char.species.physics.h_decay),
+        };
+
         let mut moving_now = false;
         if input & BUTTON_LEFT != 0 {
-            the_char.x_vel -= BTN_ACCEL;
+            the_char.x_vel -= btn_accel;
             the_char.is_facing_right = false;
             moving_now = true;
         } else if input & BUTTON_RIGHT != 0 {
-            the_char.x_vel += BTN_ACCEL;
+            the_char.x_vel += btn_accel;
             the_char.is_facing_right = true;
             moving_now = true;
         } else {
-            the_char.x_vel *= H_DECAY;
+            the_char.x_vel *= h_decay;
             the_char.current_sprite_i = 0;
         }
 
@@ -412,12 +578,13 @@ pub fn update_pos(map: &GameMap, moving_entity: MovingEntity, input: u8, godmode
         match the_char.state { 
             KittyStates::JumpingUp(t) => match t {
                 0 => {}
+                1 if !the_char.species.physics.kicks_up_clouds => {}
                 1 => {
                     
                     const CLOUD_VX: f32 = 2.0;
                     const CLOUD_VY: f32 = 1.0;
-                    let y = the_char.y_pos + (the_char.sprite.frames[the_char.current_sprite_i as usize].height as f32) * 1.2;
-                    let x = the_char.x_pos + (the_char.sprite.frames[the_char.current_sprite_i as usize].width as f32) * 0.5;
+                    let y = the_char.y_pos.to_f32() + (the_char.sprite.frames[the_char.current_sprite_i as usize].height as f32) * 1.2;
+                    let x = the_char.x_pos.to_f32() + (the_char.sprite.frames[the_char.current_sprite_i as usize].width as f32) * 0.5;
                     Cloud::try_push_cloud(clouds, x, y, CLOUD_VX, CLOUD_VY);
                     Cloud::try_push_cloud(clouds, x, y, -CLOUD_VX, CLOUD_VY);
 
@@ -437,7 +604,7 @@ pub fn update_pos(map: &GameMap, moving_entity: MovingEntity, input: u8, godmode
         if allow_jump {
             if input & BUTTON_1 != 0 {
                 the_char.state = KittyStates::JumpingUp(0);
-                the_char.y_vel = HOP_V;
+                the_char.y_vel = the_char.species.physics.hop_v;
                 return true;
             }
         }
@@ -449,11 +616,10 @@ pub fn update_pos(map: &GameMap, moving_entity: MovingEntity, input: u8, godmode
         handle_jumping(character, input, clouds);
     }
 
-    const GRAVITY: f32 = 0.3;
     // const HUGGING_WALL_SLIDE_MULT: f32 = 0.2;
     match character.state {
         KittyStates::HuggingWall(_) | KittyStates::OnCeiling(_) => {
-            character.y_vel = 0.0;
+            character.y_vel = Fixed::ZERO;
             // if character.y_vel < 0.0 {
             //     character.y_vel = 0.0;
             // }
@@ -462,21 +628,28 @@ pub fn update_pos(map: &GameMap, moving_entity: MovingEntity, input: u8, godmode
             // }
         }
         _ => {
-            character.y_vel += GRAVITY;
+            character.y_vel += character.species.physics.gravity;
         }
     }
 
     match character.state {
         KittyStates::JumpingUp(t) => {
             handle_horizontal_input(character, input);
-            handle_jumping(character, input, clouds);
-            character.state = KittyStates::JumpingUp((t as u32 + 1).min(255) as u8);
+            let jumped = handle_jumping(character, input, clouds);
+            // once the jump can't go any higher, pressing jump again uses up an air jump and starts a new one.
+            if !jumped && character.banked_air_jumps > 0 && input & BUTTON_1 != 0 && !character.jump_held {
+                character.banked_air_jumps -= 1;
+                character.y_vel = character.species.physics.hop_v;
+                character.state = KittyStates::JumpingUp(0);
+            } else {
+                character.state = KittyStates::JumpingUp((t as u32 + 1).min(255) as u8);
+            }
         }
         KittyStates::HuggingWall(firstframe) => {
             if firstframe {
                 if character.is_facing_right {
-                    character.x_pos += character.sprite.frames[3].width as f32
-                        - character.sprite.frames[4].width as f32;
+                    character.x_pos += Fixed::from_int(character.sprite.frames[3].width as i32
+                        - character.sprite.frames[4].width as i32);
                 }
             }
             character.state = KittyStates::HuggingWall(false);
@@ -486,9 +659,17 @@ pub fn update_pos(map: &GameMap, moving_entity: MovingEntity, input: u8, godmode
                     character.state = KittyStates::JumpingUp(0);
                 }
                 _ => {
+                    // clingy critters can crawl along the wall
+                    if character.species.physics.clings {
+                        if input & BUTTON_UP != 0 {
+                            character.y_vel = -character.species.physics.climb_v;
+                        } else if input & BUTTON_DOWN != 0 {
+                            character.y_vel = character.species.physics.climb_v;
+                        }
+                    }
                     if handle_jumping(character, input, clouds) {
                         character.is_facing_right = !character.is_facing_right;
-                        const WALLJUMP_VX: f32 = 3.0;
+                        const WALLJUMP_VX: Fixed = Fixed::from_int(3);
                         let new_x_vel = match character.is_facing_right {
                             true => WALLJUMP_VX,
                             false => -WALLJUMP_VX,
@@ -496,8 +677,8 @@ pub fn update_pos(map: &GameMap, moving_entity: MovingEntity, input: u8, godmode
                         character.x_vel = new_x_vel;
                         // #TODO find better spacing fix for walljump on right.
                         if !character.is_facing_right {
-                            character.x_pos -= character.sprite.frames[3].width as f32
-                                - character.sprite.frames[4].width as f32;
+                            character.x_pos -= Fixed::from_int(character.sprite.frames[3].width as i32
+                                - character.sprite.frames[4].width as i32);
                         }
                     }
                 }
@@ -543,7 +724,10 @@ pub fn update_pos(map: &GameMap, moving_entity: MovingEntity, input: u8, godmode
                     character.state = KittyStates::OnCeiling(0);
                 }
                 HorizontalMovementOutcome::StoppedMoving => {
-                    character.state = KittyStates::Sleeping;
+                    // clingy critters stay put instead of letting go
+                    if !character.species.physics.clings {
+                        character.state = KittyStates::Sleeping;
+                    }
                 }
                 _ => {
                     // character.state = KittyStates::Sleeping;
@@ -588,15 +772,29 @@ pub fn update_pos(map: &GameMap, moving_entity: MovingEntity, input: u8, godmode
         }
     }
 
-    character.x_vel = num::clamp(character.x_vel, -character.x_vel_cap, character.x_vel_cap);
+    // dashing shoots us forward at a steady speed, ignoring gravity and the usual speed cap.
+    const DASH_V: Fixed = Fixed::from_int(4);
+    let x_vel_cap = match character.dash_frames {
+        0 => character.x_vel_cap,
+        _ => {
+            character.dash_frames -= 1;
+            character.x_vel = match character.is_facing_right {
+                true => DASH_V,
+                false => -DASH_V,
+            };
+            character.y_vel = Fixed::ZERO;
+            DASH_V.max(character.x_vel_cap)
+        }
+    };
+    character.x_vel = num::clamp(character.x_vel, -x_vel_cap, x_vel_cap);
     character.y_vel = num::clamp(character.y_vel, -character.y_vel_cap, character.y_vel_cap);
 
     // now, we need to check if moving in the current direction would collide with anything.
     // Since before moving we can assume we are in a valid location, as long as this collision
     // logic places us in another valid location, we'll be okay.
 
-    let mut discretized_y_displacement_this_frame = character.y_vel as i32;
-    let mut discretized_x_displacement_this_frame = character.x_vel as i32;
+    let mut discretized_y_displacement_this_frame = character.y_vel.to_i32();
+    let mut discretized_x_displacement_this_frame = character.x_vel.to_i32();
 
     // hotfix: if our y displacement is exactly zero, set it to 1, just so we
     // can properly check if we're colliding with the ground.
@@ -615,6 +813,9 @@ pub fn update_pos(map: &GameMap, moving_entity: MovingEntity, input: u8, godmode
     }
 
     let mut touching_some_ground: bool = false;
+    let mut ground_tile = TileProperty::Empty;
+    let mut touching_damaging_tile = false;
+    let mut riding_solid = None;
 
     if !godmode {
         // trace("will check--------------------");
@@ -623,282 +824,143 @@ pub fn update_pos(map: &GameMap, moving_entity: MovingEntity, input: u8, godmode
             get_sprite_i_from_anim_state(&character.state, discretized_y_displacement_this_frame);
         let char_bound = get_bound_of_character(&character);
         let mut inside_at_least_one_chunk = false;
-        for chunk in map.chunks.iter() {
+        for chunk in map.chunks_overlapping(&char_bound) {
             // trace("checking chn");
 
-            // if the sprite is inside this chunk, we now need to check to see if moving along our velocity
-            if check_absolue_bound_partially_inside_tile_aligned_bound(&char_bound, &chunk.bound) {
-                inside_at_least_one_chunk = true;
-                // text(format!["Player in ch {i}"], 10, 10);
-
-                // VERTICAL COLLISION
-                // take the y velocity, and start at the edge of the player's bounding box, and project the line outward, to check for collisions.
-
-                // text(format!["moving up"], 10, 20);
-                // CHECK TOP LEFT CORNER
-                // create the decreasing vertical vector, and incrementally travel until it is touching something
-
-                // let lowerleft_checker_location = (char_bound.x, char_bound.y);
-                // let lowerright_checker_location = (char_bound.x + char_bound.width as i32 - 1, char_bound.y);
-                // let upperleft_checker_location = (char_bound.x, char_bound.y + char_bound.height as i32 - 1);
-                // let upperright_checker_location = (char_bound.x + char_bound.width as i32 - 1, char_bound.y + char_bound.height as i32 - 1);
-
-                // fn check_collision_group_on_same_dir(
-                //     points: &Vec<(i32, i32)>,
-                //     character: &mut Character,
-                //     displacement_to_set: &mut i32,
-                //     horizontal: bool,
-                //     positive: bool,
-                // ) {
-
-                // }
-
-                let h_col_res_lower;
-                let h_col_res_upper;
-                let v_col_res_left;
-                let v_col_res_right;
-
-                let upper_y = char_bound.y + char_bound.height as i32 - 2;
-                let lower_y = char_bound.y + 1;
-                let left_x: i32 = char_bound.x + 1;
-                let right_x: i32 = char_bound.x + char_bound.width as i32 - 2;
-
-                let vert_y;
-                let positive_y;
+            // the sprite is inside this chunk, we now need to check to see if moving along our velocity
+            // would collide with anything in it.
+            inside_at_least_one_chunk = true;
+
+            // the probe edges sit one pixel inside the bounding box.
+            let upper_y = char_bound.y + char_bound.height as i32 - 2;
+            let lower_y = char_bound.y + 1;
+            let left_x: i32 = char_bound.x + 1;
+            let right_x: i32 = char_bound.x + char_bound.width as i32 - 2;
+
+            // VERTICAL SWEEP: push the bottom edge down (or the top edge up).
+            let positive_y = discretized_y_displacement_this_frame > 0;
+            let vert_y = match positive_y {
+                true => upper_y,
+                false => lower_y,
+            };
+            let v_sweep = sweep_axis_aligned(
+                false,
+                positive_y,
+                vert_y,
+                (left_x, right_x),
+                discretized_y_displacement_this_frame,
+                chunk,
+                map.tile_properties,
+                &map.dynamic_solids,
+            );
+
+            // HORIZONTAL SWEEP: push the right edge right (or the left edge left).
+            let positive_x = discretized_x_displacement_this_frame > 0;
+            let horizontal_x = match positive_x {
+                true => right_x,
+                false => left_x,
+            };
+            let h_span = (lower_y - 1, upper_y - 1);
+            let h_sweep = sweep_axis_aligned(
+                true,
+                positive_x,
+                horizontal_x,
+                h_span,
+                discretized_x_displacement_this_frame,
+                chunk,
+                map.tile_properties,
+                &map.dynamic_solids,
+            );
+
+            if v_sweep.touched_damaging || h_sweep.touched_damaging {
+                touching_damaging_tile = true;
+            }
 
-                // VERTICAL RAYCAST
-                if discretized_y_displacement_this_frame > 0 {
-                    // GOING DOWNWARD
-                    positive_y = true;
-                    vert_y = upper_y;
-                } else {
-                    positive_y = false;
-                    vert_y = lower_y;
-                }
-                v_col_res_left = raycast_axis_aligned(
-                    false,
-                    positive_y,
-                    (left_x, vert_y),
-                    discretized_y_displacement_this_frame,
-                    chunk,
-                );
-                v_col_res_right = raycast_axis_aligned(
-                    false,
-                    positive_y,
-                    (right_x, vert_y),
-                    discretized_y_displacement_this_frame,
-                    chunk,
-                );
+            if v_sweep.collided {
+                touching_some_ground = true;
+                // remember what we're standing on. Springs win over ice, ice over plain ground.
+                if positive_y {
+                    ground_tile = more_interesting_tile(ground_tile, v_sweep.tile);
+                    if v_sweep.solid_i.is_some() {
+                        riding_solid = v_sweep.solid_i;
+                    }
+                }
+                // if we collided against the top, automatically hang
+                if positive_y == false {
+                    character.state = match character.state {
+                        KittyStates::OnCeiling(t) => KittyStates::OnCeiling(t+1),
+                        KittyStates::HuggingWall(t) => KittyStates::HuggingWall(t),
+                        _ => KittyStates::OnCeiling(0),
+                    };
+                }
 
-                let horizontal_x;
-                let positive_x;
+                character.y_vel = Fixed::ZERO;
+                discretized_y_displacement_this_frame = 0;
+            }
 
-                // HORIZONTAL RAYCAST
-                if discretized_x_displacement_this_frame > 0 {
-                    // GOING DOWNWARD
-                    positive_x = true;
-                    horizontal_x = right_x;
-                } else {
-                    positive_x = false;
-                    horizontal_x = left_x;
-                }
-                h_col_res_lower = raycast_axis_aligned(
+            if h_sweep.collided {
+                // did the top of the edge run into something, or just the bottom?
+                let head_hit = sweep_axis_aligned(
                     true,
                     positive_x,
-                    (horizontal_x, lower_y),
+                    horizontal_x,
+                    (h_span.0, h_span.0),
                     discretized_x_displacement_this_frame,
                     chunk,
-                );
-                h_col_res_upper = raycast_axis_aligned(
-                    true,
-                    positive_x,
-                    (horizontal_x, upper_y),
-                    discretized_x_displacement_this_frame,
-                    chunk,
-                );
-
-                if v_col_res_left.collided || v_col_res_right.collided {
-                    touching_some_ground = true;
-                    // if we collided against the top, automatically hang
-                    if positive_y == false {
-                        character.state = match character.state {
-                            KittyStates::OnCeiling(t) => KittyStates::OnCeiling(t+1),
-                            KittyStates::HuggingWall(t) => KittyStates::HuggingWall(t),
-                            _ => KittyStates::OnCeiling(0),
-                        };
-                    }
+                    map.tile_properties,
+                    &map.dynamic_solids,
+                ).collided;
 
-                    character.y_vel = 0.0;
-                    if v_col_res_left.backed_up {
-                        discretized_y_displacement_this_frame =
-                            v_col_res_left.allowable_displacement;
-                    } else if v_col_res_right.backed_up {
-                        discretized_y_displacement_this_frame =
-                            v_col_res_right.allowable_displacement;
-                    } else {
-                        discretized_y_displacement_this_frame =
-                            v_col_res_left.allowable_displacement.signum()
-                                * v_col_res_left
-                                    .allowable_displacement
-                                    .abs_diff(v_col_res_left.allowable_displacement)
-                                    as i32;
-                    }
+                if !head_hit && positive_y {
+                    // if we are touching floor (pos y), and only our feet hit something,
+                    // allow us to hop up the ledge.
 
-                    // if positive_y {
-                    //     // && !h_col_res_lower.collided && !h_col_res_upper.collided {
-                    //     touching_some_ground = true;
-                    // }
-                }
-
-                if h_col_res_lower.collided || h_col_res_upper.collided {
-                    if !h_col_res_lower.collided && positive_y {
-                        // if we are touching floor (pos y), and the bottom horizontal hit but
-                        // not the top, allow us to hop up the ledge.
-
-                        discretized_y_displacement_this_frame -= TILE_HEIGHT_PX as i32;
-                    } else {
-                        // if the above special case isn't true, we hit a wall
-                        character.x_vel = 0.0;
-
-                        // if in free fall (after beginning of jump), allow hugging wall
-                        match character.state {
-                            KittyStates::JumpingUp(t) => match t {
-                                0..=15 => {}
-                                _ => {
-                                    character.state = KittyStates::HuggingWall(true);
-                                }
-                            },
-                            KittyStates::OnCeiling(_) => {
+                    discretized_y_displacement_this_frame -= TILE_HEIGHT_PX as i32;
+                } else {
+                    // if the above special case isn't true, we hit a wall
+                    character.x_vel = Fixed::ZERO;
+                    character.dash_frames = 0;
+
+                    // if in free fall (after beginning of jump), allow hugging wall
+                    // (clingy critters grab on whenever they bump into one)
+                    match character.state {
+                        KittyStates::JumpingUp(t) => match t {
+                            0..=15 if !character.species.physics.clings => {}
+                            _ => {
                                 character.state = KittyStates::HuggingWall(true);
                             }
-                            _ => {}
+                        },
+                        KittyStates::OnCeiling(_) => {
+                            character.state = KittyStates::HuggingWall(true);
                         }
-                    }
-
-                    if h_col_res_lower.backed_up {
-                        discretized_x_displacement_this_frame =
-                            h_col_res_lower.allowable_displacement;
-                    } else if h_col_res_upper.backed_up {
-                        discretized_x_displacement_this_frame =
-                            h_col_res_upper.allowable_displacement;
-                    } else {
-                        discretized_x_displacement_this_frame =
-                            h_col_res_lower.allowable_displacement.signum()
-                                * h_col_res_upper
-                                    .allowable_displacement
-                                    .abs_diff(h_col_res_lower.allowable_displacement)
-                                    as i32;
+                        KittyStates::Walking(_) | KittyStates::Sleeping => {
+                            if character.species.physics.clings {
+                                character.state = KittyStates::HuggingWall(true);
+                            }
+                        }
+                        _ => {}
                     }
                 }
-                // // upward collision
-                // if character.y_vel < 0.0 {
-                //     let collision_res = raycast_axis_aligned(false, false, lowerleft_checker_location, discretized_y_displacement_this_frame, chunk);
-                //     discretized_y_displacement_this_frame = collision_res.allowable_displacement;
-                //     if collision_res.collided {
-                //         character.y_vel = 0.0;
-                //     }
-                //     if !collision_res.backed_up {
-                //         let second_collision_res = raycast_axis_aligned(false, false, lowerright_checker_location, discretized_y_displacement_this_frame, chunk);
-                //         discretized_y_displacement_this_frame = second_collision_res.allowable_displacement;
-                //         if second_collision_res.collided {
-                //             character.y_vel = 0.0;
-                //         }
-                //     }
-                // }
-
-                // // downward collision
-                // else {
-                //     let collision_res = raycast_axis_aligned(false, true, upperleft_checker_location, discretized_y_displacement_this_frame, chunk);
-                //     discretized_y_displacement_this_frame = collision_res.allowable_displacement;
-                //     if collision_res.collided {
-                //         character.y_vel = 0.0;
-                //         touching_some_ground = true;
-
-                //     } else {
-
-                //     }
-                //     if !collision_res.backed_up {
-                //         let second_collision_res = raycast_axis_aligned(false, true, upperright_checker_location, discretized_y_displacement_this_frame, chunk);
-                //         discretized_y_displacement_this_frame = second_collision_res.allowable_displacement;
-                //         if second_collision_res.collided {
-                //             touching_some_ground = true;
-                //             character.y_vel = 0.0;
-                //         }
-                //     }
-                // }
-
-                // // left collision
-                // if character.x_vel < 0.0 {
-                //     let collision_res = raycast_axis_aligned(true, false, lowerleft_checker_location, discretized_x_displacement_this_frame, chunk);
-                //     discretized_x_displacement_this_frame = collision_res.allowable_displacement;
-                //     if collision_res.collided {
-                //         // if in free fall (after beginning of jump), allow hugging wall
-                //         match character.state {
-                //             KittyStates::JumpingUp(t) => {
-                //                 match t {
-                //                     0..=15 => {
-
-                //                     },
-                //                     _ => {
-                //                         character.state = KittyStates::HuggingWall(true);
-                //                     }
-                //                 }
-                //             }
-                //             _ => {}
-                //         }
-                //         character.x_vel = 0.0;
-                //     }
-                //     if !collision_res.backed_up {
-                //         let second_collision_res = raycast_axis_aligned(true, false, upperleft_checker_location, discretized_x_displacement_this_frame, chunk);
-                //         discretized_x_displacement_this_frame = second_collision_res.allowable_displacement;
-                //         if second_collision_res.collided {
-                //             character.x_vel = 0.0;
-                //         }
-                //     }
-                // }
 
-                // // right collision
-                // else {
-
-                //     let collision_res = raycast_axis_aligned(true, true, lowerright_checker_location, discretized_x_displacement_this_frame, chunk);
-                //     discretized_x_displacement_this_frame = collision_res.allowable_displacement;
-                //     if collision_res.collided {
-                //         // if in free fall (after beginning of jump), allow hugging wall
-                //         match character.state {
-                //             KittyStates::JumpingUp(t) => {
-                //                 match t {
-                //                     0..=15 => {
-
-                //                     },
-                //                     _ => {
-                //                         character.state = KittyStates::HuggingWall(true);
-                //                     }
-                //                 }
-                //             }
-                //             _ => {}
-                //         }
-
-                //         character.x_vel = 0.0;
-                //     }
-                //     if !collision_res.backed_up {
-                //         let second_collision_res = raycast_axis_aligned(true, true, upperright_checker_location, discretized_x_displacement_this_frame, chunk);
-                //         discretized_x_displacement_this_frame = second_collision_res.allowable_displacement;
-                //         if second_collision_res.collided {
-                //             character.x_vel = 0.0;
-                //         }
-                //     }
-                // }
+                // only our feet clipping something lets us keep going.
+                if h_sweep.backed_up || head_hit {
+                    discretized_x_displacement_this_frame = 0;
+                }
             }
         }
 
         // if anyone makes it out of bounds, drop them in the center of the map
         // (or if they use the lizard warp)
         if !inside_at_least_one_chunk {
-            character.x_pos = 10.0;
-            character.y_pos = 10.0;
+            character.x_pos = Fixed::from_int(10);
+            character.y_pos = Fixed::from_int(10);
         }
     }
 
+    character.ground_tile = ground_tile;
+    character.touching_damaging_tile = touching_damaging_tile;
+    character.riding_solid = riding_solid;
+
     if !touching_some_ground {
         // if we were walking and we fall off, change state
         match character.state {
@@ -928,7 +990,22 @@ pub fn update_pos(map: &GameMap, moving_entity: MovingEntity, input: u8, godmode
         }
     }
 
-    // handle warping. If down is held, warp.
+    // springs launch whoever lands on them.
+    if character.ground_tile == TileProperty::Bouncy {
+        const SPRING_HOP_V: Fixed = Fixed::from_f32(-6.5);
+        character.y_vel = SPRING_HOP_V;
+        character.state = KittyStates::JumpingUp(1);
+    }
+
+    // remember the chunks we've been in, to warp back to.
+    if let Some(warp) = &mut warp_destinations {
+        let bound = get_bound_of_character(&character);
+        if let Some(chunk_id) = map.chunk_id_at_abs(bound.x + bound.width as i32 / 2, bound.y + bound.height as i32 / 2) {
+            warp.discover_chunk(chunk_id);
+        }
+    }
+
+    // handle warping. Hold down to charge, picking a destination with left and right, and let go to warp.
     if input & BUTTON_DOWN != 0 {
         match &mut character.warp_ability {
             WarpAbility::CannotWarp => {},
@@ -936,20 +1013,32 @@ pub fn update_pos(map: &GameMap, moving_entity: MovingEntity, input: u8, godmode
                 match warp_state {
                     WarpState::Charging(t) => {
                         *t += 1;
-                        if *t >= 25 {
+                        if *t >= WARP_CHARGE_FRAMES {
                             character.warp_ability = WarpAbility::CanWarp(WarpState::Ready);
                         }
                     },
-                    WarpState::Ready => {
-                        character.x_pos = 10.0;
-                        character.y_pos = 10.0;
-                        character.warp_ability = WarpAbility::CanWarp(WarpState::Charging(0));
-                    }
+                    WarpState::Ready => {}
+                }
+                if let Some(warp) = &mut warp_destinations {
+                    warp.cycle(warp_input);
                 }
             }
         }
     } else {
         match character.warp_ability {
+            WarpAbility::CanWarp(WarpState::Ready) => {
+                let (x, y) = match &warp_destinations {
+                    Some(warp) => warp.position(map),
+                    None => (Fixed::from_int(10), Fixed::from_int(10)),
+                };
+                character.x_pos = x;
+                character.y_pos = y;
+                character.x_vel = Fixed::ZERO;
+                character.y_vel = Fixed::ZERO;
+                discretized_x_displacement_this_frame = 0;
+                discretized_y_displacement_this_frame = 0;
+                character.warp_ability = WarpAbility::CanWarp(WarpState::Charging(0));
+            },
             WarpAbility::CanWarp(_) => {
                 character.warp_ability = WarpAbility::CanWarp(WarpState::Charging(0));
             },
@@ -959,11 +1048,21 @@ pub fn update_pos(map: &GameMap, moving_entity: MovingEntity, input: u8, godmode
 
     // character.current_sprite_i = get_sprite_i_from_anim_state(&character.state, discretized_y_displacement_this_frame);
 
-    character.x_pos += discretized_x_displacement_this_frame as f32;
-    character.y_pos += discretized_y_displacement_this_frame as f32;
+    character.x_pos += Fixed::from_int(discretized_x_displacement_this_frame);
+    character.y_pos += Fixed::from_int(discretized_y_displacement_this_frame);
 
-    character.x_pos = num::clamp(character.x_pos, X_LEFT_BOUND as f32, X_RIGHT_BOUND as f32);
-    character.y_pos = num::clamp(character.y_pos, Y_LOWER_BOUND as f32, Y_UPPER_BOUND as f32);
+    character.x_pos = num::clamp(character.x_pos, Fixed::from_int(X_LEFT_BOUND), Fixed::from_int(X_RIGHT_BOUND));
+    character.y_pos = num::clamp(character.y_pos, Fixed::from_int(Y_LOWER_BOUND), Fixed::from_int(Y_UPPER_BOUND));
+
+    // if a clingy critter crawls off the end of its wall, let go.
+    if character.species.physics.clings && !godmode {
+        if let KittyStates::HuggingWall(false) = character.state {
+            if !check_wall_beside_character(map, character) {
+                character.state = KittyStates::JumpingUp(30);
+            }
+        }
+    }
 
+    character.jump_held = input & BUTTON_1 != 0;
     character.count += 1;
 }
@@ -0,0 +1,80 @@
+use super::{
+    collision::{check_absolute_bounding_box_partially_inside_another, get_bound_of_character, AbsoluteBoundingBox},
+    entities::OptionallyEnabledPlayer,
+    game_state::GameState,
+};
+
+/// Something that can touch something else, by its index in GameState.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum EntityRef {
+    Player(usize),
+    Npc(usize),
+    Hazard(usize),
+    Pickup(usize),
+}
+
+impl EntityRef {
+    fn is_character(self: &Self) -> bool {
+        match self {
+            EntityRef::Player(_) | EntityRef::Npc(_) => true,
+            EntityRef::Hazard(_) | EntityRef::Pickup(_) => false,
+        }
+    }
+}
+
+/// Two entities whose bounds overlap this frame. `a` always sorts before `b`,
+/// so a player touching an NPC is always (Player, Npc).
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Contact {
+    pub a: EntityRef,
+    pub b: EntityRef,
+}
+
+/// Find every pair of overlapping entities that involves at least one player or NPC.
+/// Hazards and pickups never care about each other.
+///
+/// Sort and sweep: sort everything by its left edge, then each entity only needs checking against
+/// the ones that start before its right edge. The contacts come out sorted, so whoever handles
+/// them does so in the same order every time.
+/// entities is only scratch space, kept between frames so it isn't allocated every time.
+pub fn find_contacts(game_state: &GameState, entities: &mut Vec<(AbsoluteBoundingBox<i32, u32>, EntityRef)>, contacts: &mut Vec<Contact>) {
+    contacts.clear();
+    entities.clear();
+
+    for (i, opt_p) in game_state.players.iter().enumerate() {
+        if let OptionallyEnabledPlayer::Enabled(p) = opt_p {
+            entities.push((get_bound_of_character(&p.character), EntityRef::Player(i)));
+        }
+    }
+    for (i, npc) in game_state.npcs.iter().enumerate() {
+        entities.push((get_bound_of_character(npc), EntityRef::Npc(i)));
+    }
+    for (i, hazard) in game_state.hazards.iter().enumerate() {
+        entities.push((hazard.get_bound(), EntityRef::Hazard(i)));
+    }
+    for (i, pickup) in game_state.pickups.iter().enumerate() {
+        entities.push((pickup.get_bound(), EntityRef::Pickup(i)));
+    }
+
+    entities.sort_unstable_by_key(|(bound, entity)| (bound.x, *entity));
+
+    for (i, (bound, entity)) in entities.iter().enumerate() {
+        let right_x = bound.x + bound.width as i32;
+        for (other_bound, other_entity) in entities[i + 1..].iter() {
+            if other_bound.x >= right_x {
+                break;
+            }
+            if !entity.is_character() && !other_entity.is_character() {
+                continue;
+            }
+            if check_absolute_bounding_box_partially_inside_another(bound, other_bound) {
+                contacts.push(Contact {
+                    a: (*entity).min(*other_entity),
+                    b: (*entity).max(*other_entity),
+                });
+            }
+        }
+    }
+
+    contacts.sort_unstable();
+}
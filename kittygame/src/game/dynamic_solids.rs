@@ -0,0 +1,164 @@
+use super::{
+    collision::AbsoluteBoundingBox,
+    game_constants::{TILE_HEIGHT_PX, TILE_WIDTH_PX},
+};
+
+// platforms move one pixel every this many frames.
+const PLATFORM_FRAMES_PER_PX: u32 = 2;
+// and wait this long at each end of their track.
+const PLATFORM_PAUSE_FRAMES: u16 = 40;
+
+// how long a crumbling block holds up once stood on, and how long until it comes back.
+const CRUMBLE_SHAKE_FRAMES: u16 = 40;
+const CRUMBLE_RESPAWN_FRAMES: u16 = 240;
+
+#[derive(Clone, Copy, Hash)]
+pub enum DynamicSolidKind {
+    // slides between its home and `range_px` to the right.
+    MovingHorizontal,
+    // slides between its home and `range_px` below.
+    MovingVertical,
+    // shakes for a bit once stood on, then falls away and grows back later.
+    Crumbling,
+}
+
+#[derive(Hash)]
+pub enum DynamicSolidState {
+    Moving,
+    Paused(u16),
+    Intact,
+    Shaking(u16),
+    Gone(u16),
+}
+
+/// A solid that isn't part of the tile grid. The collision sweeps in update_pos treat it like plain rock.
+#[derive(Hash)]
+pub struct DynamicSolid {
+    pub kind: DynamicSolidKind,
+    pub state: DynamicSolidState,
+    pub x_pos: i32,
+    pub y_pos: i32,
+    pub width: u32,
+    pub height: u32,
+    pub home_x: i32,
+    pub home_y: i32,
+    pub range_px: i32,
+    // how far along the track we are, and which way we're headed.
+    pub offset: i32,
+    pub forward: bool,
+    // how far we moved this frame, so whoever is standing on us can move along.
+    pub moved_x: i32,
+    pub moved_y: i32,
+    pub count: u32,
+}
+
+impl DynamicSolid {
+    /// A platform `width_tiles` wide and one tile tall, moving `range_tiles` away from (tile_x, tile_y) and back.
+    pub fn new_platform(kind: DynamicSolidKind, tile_x: i32, tile_y: i32, width_tiles: u32, range_tiles: u32) -> DynamicSolid {
+        let x = tile_x * TILE_WIDTH_PX as i32;
+        let y = tile_y * TILE_HEIGHT_PX as i32;
+        let range_px = match kind {
+            DynamicSolidKind::MovingVertical => range_tiles * TILE_HEIGHT_PX as u32,
+            _ => range_tiles * TILE_WIDTH_PX as u32,
+        };
+        let state = match kind {
+            DynamicSolidKind::Crumbling => DynamicSolidState::Intact,
+            _ => DynamicSolidState::Moving,
+        };
+        DynamicSolid {
+            kind,
+            state,
+            x_pos: x,
+            y_pos: y,
+            width: width_tiles * TILE_WIDTH_PX as u32,
+            height: TILE_HEIGHT_PX as u32,
+            home_x: x,
+            home_y: y,
+            range_px: range_px as i32,
+            offset: 0,
+            forward: true,
+            moved_x: 0,
+            moved_y: 0,
+            count: 0,
+        }
+    }
+
+    pub fn new_crumbling(tile_x: i32, tile_y: i32) -> DynamicSolid {
+        DynamicSolid::new_platform(DynamicSolidKind::Crumbling, tile_x, tile_y, 1, 0)
+    }
+
+    pub fn get_bound(self: &Self) -> AbsoluteBoundingBox<i32, u32> {
+        AbsoluteBoundingBox {
+            x: self.x_pos,
+            y: self.y_pos,
+            width: self.width,
+            height: self.height,
+        }
+    }
+
+    // crumbled blocks let everything through until they grow back.
+    pub fn is_solid(self: &Self) -> bool {
+        match self.state {
+            DynamicSolidState::Gone(_) => false,
+            _ => true,
+        }
+    }
+
+    pub fn blocks_pt(self: &Self, x: i32, y: i32) -> bool {
+        self.is_solid()
+            && x >= self.x_pos
+            && x < self.x_pos + self.width as i32
+            && y >= self.y_pos
+            && y < self.y_pos + self.height as i32
+    }
+
+    /// Move along the track, or crumble. `stood_on` is whether anyone rode us last frame,
+    /// `occupied` whether anyone overlaps us (crumbled blocks won't grow back into someone).
+    pub fn update(self: &mut Self, stood_on: bool, occupied: bool) {
+        self.count += 1;
+        self.moved_x = 0;
+        self.moved_y = 0;
+        self.state = match self.state {
+            DynamicSolidState::Moving => {
+                if self.count % PLATFORM_FRAMES_PER_PX != 0 {
+                    DynamicSolidState::Moving
+                } else {
+                    let step = match self.forward {
+                        true => 1,
+                        false => -1,
+                    };
+                    self.offset += step;
+                    match self.kind {
+                        DynamicSolidKind::MovingVertical => self.moved_y = step,
+                        _ => self.moved_x = step,
+                    }
+                    self.x_pos += self.moved_x;
+                    self.y_pos += self.moved_y;
+                    if self.offset <= 0 || self.offset >= self.range_px {
+                        self.forward = !self.forward;
+                        DynamicSolidState::Paused(PLATFORM_PAUSE_FRAMES)
+                    } else {
+                        DynamicSolidState::Moving
+                    }
+                }
+            }
+            DynamicSolidState::Paused(t) => match t {
+                0 => DynamicSolidState::Moving,
+                _ => DynamicSolidState::Paused(t - 1),
+            },
+            DynamicSolidState::Intact => match stood_on {
+                true => DynamicSolidState::Shaking(CRUMBLE_SHAKE_FRAMES),
+                false => DynamicSolidState::Intact,
+            },
+            DynamicSolidState::Shaking(t) => match t {
+                0 => DynamicSolidState::Gone(CRUMBLE_RESPAWN_FRAMES),
+                _ => DynamicSolidState::Shaking(t - 1),
+            },
+            DynamicSolidState::Gone(t) => match (t, occupied) {
+                (0, false) => DynamicSolidState::Intact,
+                (0, true) => DynamicSolidState::Gone(0),
+                _ => DynamicSolidState::Gone(t - 1),
+            },
+        };
+    }
+}
@@ -1,12 +1,43 @@
+use core::hash::{Hash, Hasher};
+
 use crate::spritesheet::{self, PresetSprites};
 
-use super::ability_cards::AbilityCardStack;
+use super::{ability_cards::AbilityCardStack, fixed::Fixed, game_map::TileProperty, navigation::NavFollower, species::{PlayableCharacter, SpeciesProfile}, warp::WarpDestinations};
 
+#[derive(Hash)]
 pub struct Player {
     pub character: Character,
     pub card_stack: AbilityCardStack,
+    // after getting hit by a hazard, the player blinks and can't be hit again for a bit.
+    pub invulnerable_frames: u8,
+    // while this is counting down, the HUD points at the nearest kitty nobody has found yet.
+    pub magnet_frames: u32,
+    // where a warp can take us this level.
+    pub warp: WarpDestinations,
+    // picked on the select screen. Decides the sprite, physics and starting card.
+    pub playable: PlayableCharacter,
+}
+
+impl Player {
+    pub fn new(playable: PlayableCharacter) -> Player {
+        let profile = playable.profile();
+        let mut card_stack = AbilityCardStack { cards: Vec::new() };
+        if let Some(card) = profile.starting_card {
+            // it slides into place from the corner.
+            card_stack.try_push_card(card, Fixed::ZERO, Fixed::ZERO);
+        }
+        Player {
+            character: Character::new_with_species(profile.sprite, &profile.species),
+            card_stack,
+            invulnerable_frames: 0,
+            magnet_frames: 0,
+            warp: WarpDestinations::new(),
+            playable,
+        }
+    }
 }
 
+#[derive(Hash)]
 pub enum OptionallyEnabledPlayer {
     Enabled(Player),
     Disabled
@@ -33,12 +64,12 @@ pub enum WarpAbility {
 }
 
 pub struct Character {
-    pub x_pos: f32,
-    pub y_pos: f32,
-    pub x_vel: f32,
-    pub y_vel: f32,
-    pub x_vel_cap: f32,
-    pub y_vel_cap: f32,
+    pub x_pos: Fixed,
+    pub y_pos: Fixed,
+    pub x_vel: Fixed,
+    pub y_vel: Fixed,
+    pub x_vel_cap: Fixed,
+    pub y_vel_cap: Fixed,
     pub count: i32,
     pub is_facing_right: bool,
     pub state: KittyStates,
@@ -48,6 +79,19 @@ pub struct Character {
     pub can_fly: bool,
     pub sprite_type: PresetSprites,
     pub warp_ability: WarpAbility,
+    pub nav: NavFollower,
+    pub species: &'static SpeciesProfile,
+    // what we were standing on last frame, and whether we touched anything that hurts.
+    pub ground_tile: TileProperty,
+    pub touching_damaging_tile: bool,
+    // index into map.dynamic_solids of the platform we stood on last frame.
+    pub riding_solid: Option<usize>,
+    // from dash and double jump cards: frames of dash left, and mid-air jumps saved up.
+    // Landing doesn't use the jumps up: they last, even into the next level, until they're jumped.
+    pub dash_frames: u32,
+    pub banked_air_jumps: u32,
+    // air jumps need a fresh press, so remember whether jump was held last frame.
+    pub jump_held: bool,
 }
 
 #[derive(PartialEq, Eq, Hash)]
@@ -65,22 +109,62 @@ pub enum KittyStates {
 
 impl Character {
     pub fn new(sprite_type: PresetSprites) -> Character {
+        Character::new_with_species(sprite_type, SpeciesProfile::from_preset(&sprite_type))
+    }
+
+    /// Players can move differently from NPCs that look the same.
+    pub fn new_with_species(sprite_type: PresetSprites, species: &'static SpeciesProfile) -> Character {
         Character {
-            x_pos: 10 as f32,
-            y_pos: 10.0,
-            x_vel: 0.0,
-            y_vel: 0.0,
-            x_vel_cap: 2.0,
-            y_vel_cap: 7.0,
+            x_pos: Fixed::from_int(10),
+            y_pos: Fixed::from_int(10),
+            x_vel: Fixed::ZERO,
+            y_vel: Fixed::ZERO,
+            x_vel_cap: species.physics.x_vel_cap,
+            y_vel_cap: species.physics.y_vel_cap,
             count: 0,
             is_facing_right: true,
             state: KittyStates::JumpingUp(200),
             current_sprite_i: 0,
             sprite: &spritesheet::Sprite::from_preset(&sprite_type),
             following_i: None,
-            can_fly: false,
+            can_fly: species.physics.can_fly,
             sprite_type,
-            warp_ability: WarpAbility::CannotWarp
+            warp_ability: WarpAbility::CannotWarp,
+            nav: NavFollower::new(),
+            species,
+            ground_tile: TileProperty::Empty,
+            touching_damaging_tile: false,
+            riding_solid: None,
+            dash_frames: 0,
+            banked_air_jumps: 0,
+            jump_held: false,
         }
     }
-}
\ No newline at end of file
+}
+
+// the sprite and species come from sprite_type, so there's no need to hash them separately.
+impl Hash for Character {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.x_pos.hash(state);
+        self.y_pos.hash(state);
+        self.x_vel.hash(state);
+        self.y_vel.hash(state);
+        self.x_vel_cap.hash(state);
+        self.y_vel_cap.hash(state);
+        self.count.hash(state);
+        self.is_facing_right.hash(state);
+        self.state.hash(state);
+        self.current_sprite_i.hash(state);
+        self.following_i.hash(state);
+        self.can_fly.hash(state);
+        self.sprite_type.hash(state);
+        self.warp_ability.hash(state);
+        self.nav.hash(state);
+        self.ground_tile.hash(state);
+        self.touching_damaging_tile.hash(state);
+        self.riding_solid.hash(state);
+        self.dash_frames.hash(state);
+        self.banked_air_jumps.hash(state);
+        self.jump_held.hash(state);
+    }
+}
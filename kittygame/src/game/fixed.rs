@@ -0,0 +1,112 @@
+use core::ops::{Add, AddAssign, Div, Mul, MulAssign, Neg, Sub, SubAssign};
+
+// 16 bits of fraction leaves room for positions a few tens of thousands of pixels out,
+// and is fine enough that decay constants like 0.99 don't round away.
+const FRAC_BITS: u32 = 16;
+
+/// A 16.16 fixed-point number, for anything that has to come out bit-for-bit the same on every
+/// platform: character physics, the camera, and card and popup easing. Floats are only used for
+/// literals (converted at compile time) and for drawing.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub struct Fixed(i32);
+
+impl Fixed {
+    pub const ZERO: Fixed = Fixed(0);
+
+    pub const fn from_int(n: i32) -> Fixed {
+        Fixed(n << FRAC_BITS)
+    }
+
+    /// Only meant for constants, which get converted at compile time. Rounds to the nearest step.
+    pub const fn from_f32(v: f32) -> Fixed {
+        let scaled = v * (1 << FRAC_BITS) as f32;
+        match scaled < 0.0 {
+            true => Fixed((scaled - 0.5) as i32),
+            false => Fixed((scaled + 0.5) as i32),
+        }
+    }
+
+    pub const fn from_raw(raw: i32) -> Fixed {
+        Fixed(raw)
+    }
+
+    pub const fn to_raw(self: Self) -> i32 {
+        self.0
+    }
+
+    /// Rounds toward zero, the same as `as i32` does for floats.
+    pub const fn to_i32(self: Self) -> i32 {
+        self.0 / (1 << FRAC_BITS)
+    }
+
+    /// For drawing, and anything else that doesn't feed back into the simulation.
+    pub fn to_f32(self: Self) -> f32 {
+        self.0 as f32 / (1 << FRAC_BITS) as f32
+    }
+
+    pub fn abs(self: Self) -> Fixed {
+        Fixed(self.0.wrapping_abs())
+    }
+}
+
+impl Add for Fixed {
+    type Output = Fixed;
+    fn add(self, rhs: Fixed) -> Fixed {
+        Fixed(self.0.wrapping_add(rhs.0))
+    }
+}
+
+impl Sub for Fixed {
+    type Output = Fixed;
+    fn sub(self, rhs: Fixed) -> Fixed {
+        Fixed(self.0.wrapping_sub(rhs.0))
+    }
+}
+
+impl Neg for Fixed {
+    type Output = Fixed;
+    fn neg(self) -> Fixed {
+        Fixed(self.0.wrapping_neg())
+    }
+}
+
+// products round toward negative infinity.
+impl Mul for Fixed {
+    type Output = Fixed;
+    fn mul(self, rhs: Fixed) -> Fixed {
+        Fixed(((self.0 as i64 * rhs.0 as i64) >> FRAC_BITS) as i32)
+    }
+}
+
+impl Mul<i32> for Fixed {
+    type Output = Fixed;
+    fn mul(self, rhs: i32) -> Fixed {
+        Fixed(self.0.wrapping_mul(rhs))
+    }
+}
+
+// quotients round toward zero.
+impl Div<i32> for Fixed {
+    type Output = Fixed;
+    fn div(self, rhs: i32) -> Fixed {
+        Fixed(self.0 / rhs)
+    }
+}
+
+impl AddAssign for Fixed {
+    fn add_assign(&mut self, rhs: Fixed) {
+        *self = *self + rhs;
+    }
+}
+
+impl SubAssign for Fixed {
+    fn sub_assign(&mut self, rhs: Fixed) {
+        *self = *self - rhs;
+    }
+}
+
+impl MulAssign for Fixed {
+    fn mul_assign(&mut self, rhs: Fixed) {
+        *self = *self * rhs;
+    }
+}
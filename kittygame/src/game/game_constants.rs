@@ -1,3 +1,5 @@
+use super::map_generators::MapGenAlgorithm;
+
 // BUILDING PROFILE #1: baseline
 // const BUILDING_SUGGESTED_MIN_WIDTH: usize = 8; // 3;
 // const BUILDING_SUGGESTED_MAX_WIDTH: usize = 11; // 14;
@@ -13,6 +15,9 @@ pub struct MapGenSetting {
     pub chunk_max_side_len: usize,
     pub max_n_tiles_per_chunk: usize,
     pub linear_mapsize_mult: f32,
+    pub algorithm: MapGenAlgorithm,
+    // percent chance that a large chunk gets moving platforms or crumbling blocks.
+    pub dynamic_solid_chance: u32,
 }
 
 pub const MAP_GEN_SETTINGS: [MapGenSetting; 9] = [
@@ -21,57 +26,85 @@ pub const MAP_GEN_SETTINGS: [MapGenSetting; 9] = [
         chunk_max_side_len: 50,
         max_n_tiles_per_chunk: 400,
         linear_mapsize_mult: 1.0,
+        algorithm: MapGenAlgorithm::RandomRects,
+        dynamic_solid_chance: 0,
     },
     MapGenSetting{ // oblong
         chunk_min_side_len: 6,
         chunk_max_side_len: 25,
         max_n_tiles_per_chunk: 2000,
         linear_mapsize_mult: 1.0,
+        algorithm: MapGenAlgorithm::RandomRects,
+        dynamic_solid_chance: 0,
     },
     MapGenSetting{ // teeny tunnels
         chunk_min_side_len: 6,
         chunk_max_side_len: 12,
         max_n_tiles_per_chunk: 800,
         linear_mapsize_mult: 0.7,
+        algorithm: MapGenAlgorithm::RandomRects,
+        dynamic_solid_chance: 0,
     },
     MapGenSetting{ // large normal
         chunk_min_side_len: 20,
         chunk_max_side_len: 40,
         max_n_tiles_per_chunk: 1400,
         linear_mapsize_mult: 1.0,
+        algorithm: MapGenAlgorithm::BspRooms,
+        dynamic_solid_chance: 30,
     },
     MapGenSetting{ // small
         chunk_min_side_len: 6,
         chunk_max_side_len: 30,
         max_n_tiles_per_chunk: 500,
         linear_mapsize_mult: 1.0,
+        algorithm: MapGenAlgorithm::RandomRects,
+        dynamic_solid_chance: 0,
     },
     MapGenSetting{ // ridiculous tunnel
         chunk_min_side_len: 8,
         chunk_max_side_len: 10,
         max_n_tiles_per_chunk: 500,
         linear_mapsize_mult: 0.4,
+        algorithm: MapGenAlgorithm::Tower,
+        dynamic_solid_chance: 0,
     },
     MapGenSetting{ // chaotic
         chunk_min_side_len: 5,
         chunk_max_side_len: 60,
         max_n_tiles_per_chunk: 500,
         linear_mapsize_mult: 1.0,
+        algorithm: MapGenAlgorithm::Cave,
+        dynamic_solid_chance: 0,
     },
     MapGenSetting{ // spacey
         chunk_min_side_len: 12,
         chunk_max_side_len: 40,
         max_n_tiles_per_chunk: 2000,
         linear_mapsize_mult: 1.0,
+        algorithm: MapGenAlgorithm::RandomRects,
+        dynamic_solid_chance: 80,
     },
     MapGenSetting{ // underworld
         chunk_min_side_len: 5,
         chunk_max_side_len: 80,
         max_n_tiles_per_chunk: 5000,
         linear_mapsize_mult: 1.0,
+        algorithm: MapGenAlgorithm::RandomRects,
+        dynamic_solid_chance: 40,
     },
 ];
 
+// what the tutorial's map is built with. It's laid out by hand, so only the algorithm matters.
+pub const TUTORIAL_MAP_GEN_SETTING: MapGenSetting = MapGenSetting {
+    chunk_min_side_len: 16,
+    chunk_max_side_len: 60,
+    max_n_tiles_per_chunk: 1200,
+    linear_mapsize_mult: 0.0,
+    algorithm: MapGenAlgorithm::Tutorial,
+    dynamic_solid_chance: 0,
+};
+
 // pub const MAP_CHUNK_MIN_SIDE_LEN: usize = 6;
 // pub const MAP_CHUNK_MAX_SIDE_LEN: usize = 50;
 
@@ -81,6 +114,9 @@ pub const MAP_GEN_SETTINGS: [MapGenSetting; 9] = [
 pub const MAX_N_TILES_IN_WHOLE_MAP: usize = 25 * 2048;
 
 pub const MAX_N_NPCS: usize = 20;
+pub const MAX_N_HAZARDS: usize = 12;
+pub const MAX_N_PICKUPS: usize = 10;
+pub const MAX_N_DYNAMIC_SOLIDS: usize = 24;
   
 pub const TILE_WIDTH_PX: usize = 5;
 pub const TILE_HEIGHT_PX: usize = 5;
@@ -92,6 +128,48 @@ pub const Y_UPPER_BOUND: i32 = 5000;
 
 pub const COUNTDOWN_TIMER_START: u32 = 60 * 60;
 
+/// How a difficulty changes the game, relative to medium.
+pub struct DifficultySetting {
+    pub name: &'static str,
+    pub countdown_timer_start: u32,
+    // percent of the usual time and score each level adds.
+    pub countdown_bonus_percent: u32,
+    // percent of the usual number of NPCs to find each level.
+    pub npcs_to_find_percent: u32,
+    // multiplies how much bigger each level's map gets.
+    pub mapsize_mult: f32,
+    // percent of how often unfound NPCs usually move around.
+    pub npc_wander_percent: u32,
+}
+
+// in the order of the Difficulty enum.
+pub const DIFFICULTY_SETTINGS: [DifficultySetting; 3] = [
+    DifficultySetting { // easy
+        name: "Easy",
+        countdown_timer_start: 90 * 60,
+        countdown_bonus_percent: 150,
+        npcs_to_find_percent: 75,
+        mapsize_mult: 0.75,
+        npc_wander_percent: 60,
+    },
+    DifficultySetting { // medium
+        name: "Medium",
+        countdown_timer_start: COUNTDOWN_TIMER_START,
+        countdown_bonus_percent: 100,
+        npcs_to_find_percent: 100,
+        mapsize_mult: 1.0,
+        npc_wander_percent: 100,
+    },
+    DifficultySetting { // hard
+        name: "Hard",
+        countdown_timer_start: 45 * 60,
+        countdown_bonus_percent: 75,
+        npcs_to_find_percent: 125,
+        mapsize_mult: 1.25,
+        npc_wander_percent: 150,
+    },
+];
+
 pub const START_DIFFICULTY_LEVEL: u32 = 1;
 pub const FINAL_LEVEL: u32 = 45;
 pub const LEVELS_PER_MOOD: usize = 5;
@@ -1,8 +1,21 @@
-use super::{mapchunk::MapChunk, game_constants::MAX_N_TILES_IN_WHOLE_MAP};
+use super::{
+    chunk_index::ChunkIndex,
+    collision::{check_absolue_bound_partially_inside_tile_aligned_bound, AbsoluteBoundingBox},
+    dynamic_solids::DynamicSolid,
+    game_constants::{MAX_N_TILES_IN_WHOLE_MAP, TILE_HEIGHT_PX, TILE_WIDTH_PX},
+    mapchunk::{MapChunk, TileAlignedBoundingBox},
+    rng::GameRng,
+};
 
 pub struct GameMap {
     pub chunks: Vec<MapChunk>,
-    pub num_tiles: usize
+    pub num_tiles: usize,
+    // how each tile id behaves in the current tileset.
+    pub tile_properties: &'static [TileProperty; 16],
+    // moving platforms and crumbling blocks, which collide like tiles but live outside the grid.
+    pub dynamic_solids: Vec<DynamicSolid>,
+    // which chunks are where, so lookups don't have to check every chunk.
+    pub chunk_index: ChunkIndex,
 }
 
 // pub struct MapTileSet {
@@ -22,6 +35,7 @@ pub struct GameMap {
 // bottomright bottom   bottomleft left
 // topleft     middle   corrupt1   c2
 // c3          c4       c5         c6  
+// (or oneway, spikes, ice and spring in tilesets that have sprites for them, see MAP_TILE_PROPERTIES)
 pub const MAP_TILESETS: [[u8; 16]; 9] = [
     [ // normal
         0, 20, 0, 17,
@@ -50,8 +64,8 @@ pub const MAP_TILESETS: [[u8; 16]; 9] = [
     [ // small (jungle vibes)
         0, 12, 27, 14,
         29, 12, 28, 14,
-        25, 12, 9, 31,
-        32, 32, 31, 32
+        25, 12, 9, 12,
+        18, 31, 32, 15
     ],
     // for clouds stage use pillars and platforms! :D
     [ // rediculous tunnels
@@ -63,8 +77,8 @@ pub const MAP_TILESETS: [[u8; 16]; 9] = [
     [ // chaotic
         0, 20, 0, 30,
         0, 18, 0, 30,
-        0, 30, 31, 27,
-        29, 25, 28, 32
+        0, 30, 30, 27,
+        18, 31, 32, 14
     ],
     [ // spacey
         0, 20, 10, 15,
@@ -76,10 +90,70 @@ pub const MAP_TILESETS: [[u8; 16]; 9] = [
         26, 32, 26, 32,
         26, 32, 26, 32,
         0, 26, 25, 27,
-        29, 31, 28, 28
+        18, 31, 12, 14
     ],
 ];
 
+/// How a tile id behaves, independent of how it's drawn.
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TileProperty {
+    Empty,
+    Solid,
+    // can be jumped up through, and stood on from above.
+    OneWayUp,
+    // solid, and costs the player time like a hazard.
+    Damaging,
+    // solid and slippery.
+    LowFriction,
+    // solid, and launches whoever lands on it.
+    Bouncy,
+}
+
+impl TileProperty {
+    /// Does this tile block movement from every side?
+    pub fn is_solid(self: &Self) -> bool {
+        match self {
+            TileProperty::Empty | TileProperty::OneWayUp => false,
+            _ => true,
+        }
+    }
+}
+
+// tile ids the map generators place for special tiles.
+pub const ONE_WAY_TILE: u8 = 12;
+pub const SPIKE_TILE: u8 = 13;
+pub const ICE_TILE: u8 = 14;
+pub const SPRING_TILE: u8 = 15;
+
+// every tile is rock, for tilesets without sprites for the special tiles.
+const PLAIN_TILE_PROPERTIES: [TileProperty; 16] = [
+    TileProperty::Empty, TileProperty::Solid, TileProperty::Solid, TileProperty::Solid,
+    TileProperty::Solid, TileProperty::Solid, TileProperty::Solid, TileProperty::Solid,
+    TileProperty::Solid, TileProperty::Solid, TileProperty::Solid, TileProperty::Solid,
+    TileProperty::Solid, TileProperty::Solid, TileProperty::Solid, TileProperty::Solid,
+];
+
+pub const SPECIAL_TILE_PROPERTIES: [TileProperty; 16] = [
+    TileProperty::Empty, TileProperty::Solid, TileProperty::Solid, TileProperty::Solid,
+    TileProperty::Solid, TileProperty::Solid, TileProperty::Solid, TileProperty::Solid,
+    TileProperty::Solid, TileProperty::Solid, TileProperty::Solid, TileProperty::Solid,
+    TileProperty::OneWayUp, TileProperty::Damaging, TileProperty::LowFriction, TileProperty::Bouncy,
+];
+
+// one table per tileset in MAP_TILESETS. Only small, chaotic and underworld draw the special tiles,
+// the rest use those ids for more rubble.
+pub const MAP_TILE_PROPERTIES: [&[TileProperty; 16]; 9] = [
+    &PLAIN_TILE_PROPERTIES, // normal
+    &PLAIN_TILE_PROPERTIES, // oblong
+    &PLAIN_TILE_PROPERTIES, // teeny tunnels
+    &PLAIN_TILE_PROPERTIES, // large normal
+    &SPECIAL_TILE_PROPERTIES, // small
+    &PLAIN_TILE_PROPERTIES, // rediculous tunnels
+    &SPECIAL_TILE_PROPERTIES, // chaotic
+    &PLAIN_TILE_PROPERTIES, // spacey
+    &SPECIAL_TILE_PROPERTIES, // underworld
+];
+
 
 
 impl GameMap {
@@ -167,6 +241,111 @@ impl GameMap {
         }
     }
 
+    /// The smallest tile-aligned bound that covers every chunk in the map.
+    pub fn get_extents(self: &Self) -> TileAlignedBoundingBox {
+        if self.chunks.is_empty() {
+            return TileAlignedBoundingBox::init(0, 0, 0, 0);
+        }
+        let mut min_x = i32::MAX;
+        let mut min_y = i32::MAX;
+        let mut max_x = i32::MIN;
+        let mut max_y = i32::MIN;
+        for chunk in self.chunks.iter() {
+            min_x = min_x.min(chunk.bound.x);
+            min_y = min_y.min(chunk.bound.y);
+            max_x = max_x.max(chunk.bound.x + chunk.bound.width as i32);
+            max_y = max_y.max(chunk.bound.y + chunk.bound.height as i32);
+        }
+        TileAlignedBoundingBox::init(min_x, min_y, (max_x - min_x) as usize, (max_y - min_y) as usize)
+    }
+
+    pub fn get_tile_property(self: &Self, tile: u8) -> TileProperty {
+        self.tile_properties[tile as usize & 0xf]
+    }
+
+    /// Can the current tileset draw one-way, spike, ice and spring tiles?
+    pub fn has_special_tiles(self: &Self) -> bool {
+        self.tile_properties[ONE_WAY_TILE as usize] != TileProperty::Solid
+    }
+
+    /// (Re)build the chunk index, once the chunks are all laid out.
+    pub fn rebuild_chunk_index(self: &mut Self) {
+        self.chunk_index.rebuild(&self.chunks);
+    }
+
+    // chunks that might have a tile at this absolute pixel position.
+    fn chunks_at_abs(self: &Self, x: i32, y: i32) -> impl Iterator<Item = &MapChunk> {
+        let tile_x = x.div_euclid(TILE_WIDTH_PX as i32);
+        let tile_y = y.div_euclid(TILE_HEIGHT_PX as i32);
+        self.chunk_index
+            .candidates(self.chunks.len(), tile_x, tile_y, tile_x, tile_y)
+            .map(move |id| &self.chunks[id])
+    }
+
+    /// Which chunk this absolute pixel position is in, if any.
+    pub fn chunk_id_at_abs(self: &Self, x: i32, y: i32) -> Option<usize> {
+        let tile_x = x.div_euclid(TILE_WIDTH_PX as i32);
+        let tile_y = y.div_euclid(TILE_HEIGHT_PX as i32);
+        self.chunk_index
+            .candidates(self.chunks.len(), tile_x, tile_y, tile_x, tile_y)
+            .find(|id| self.chunks[*id].get_tile_abs(x, y).is_ok())
+    }
+
+    /// The tile at this absolute pixel position, or None if it isn't inside any chunk.
+    pub fn tile_at_abs(self: &Self, x: i32, y: i32) -> Option<u8> {
+        self.chunks_at_abs(x, y).find_map(|chunk| chunk.get_tile_abs(x, y).ok())
+    }
+
+    /// The chunks this bound is partially inside, in the order they were added to the map.
+    pub fn chunks_overlapping<'a>(self: &'a Self, bound: &'a AbsoluteBoundingBox<i32, u32>) -> impl Iterator<Item = &'a MapChunk> {
+        // the bound's corners are checked one pixel past its right and bottom edges.
+        let tile_x0 = bound.x.div_euclid(TILE_WIDTH_PX as i32);
+        let tile_y0 = bound.y.div_euclid(TILE_HEIGHT_PX as i32);
+        let tile_x1 = (bound.x + bound.width as i32).div_euclid(TILE_WIDTH_PX as i32);
+        let tile_y1 = (bound.y + bound.height as i32).div_euclid(TILE_HEIGHT_PX as i32);
+        self.chunk_index
+            .candidates(self.chunks.len(), tile_x0, tile_y0, tile_x1, tile_y1)
+            .map(move |id| &self.chunks[id])
+            .filter(move |chunk| check_absolue_bound_partially_inside_tile_aligned_bound(bound, &chunk.bound))
+    }
+
+    /// Is there a solid tile at this absolute pixel position?
+    pub fn is_solid_abs(self: &Self, x: i32, y: i32) -> bool {
+        for chunk in self.chunks_at_abs(x, y) {
+            if let Ok(tile) = chunk.get_tile_abs(x, y) {
+                if self.get_tile_property(tile).is_solid() {
+                    return true;
+                }
+            }
+        }
+        false
+    }
+
+    /// Try random tiles outside the origin chunk, where the players start, and give back the
+    /// absolute pixel position of the first one that fits, if any of n_tries did.
+    pub fn try_find_spot(self: &Self, n_tries: usize, rng: &mut GameRng, fits: impl Fn(i32, i32) -> bool) -> Option<(i32, i32)> {
+        if self.chunks.len() < 2 {
+            return None;
+        }
+        for _ in 0..n_tries {
+            let rand_chunk_i = (rng.next_for_worldgen() % (self.chunks.len() - 1) as u64) as usize + 1;
+            let chunk: &MapChunk = &self.chunks[rand_chunk_i];
+            let tile_x = chunk.bound.x + (rng.next_for_worldgen() % chunk.bound.width as u64) as i32;
+            let tile_y = chunk.bound.y + (rng.next_for_worldgen() % chunk.bound.height as u64) as i32;
+            let x = tile_x * TILE_WIDTH_PX as i32;
+            let y = tile_y * TILE_HEIGHT_PX as i32;
+            if fits(x, y) {
+                return Some((x, y));
+            }
+        }
+        None
+    }
+
+    /// Is this absolute pixel position inside any chunk at all?
+    pub fn is_inside_abs(self: &Self, x: i32, y: i32) -> bool {
+        self.tile_at_abs(x, y).is_some()
+    }
+
     pub fn add_chunk(self: & mut Self, mut chunk: MapChunk) {
         self.link_chunk_to_touching_chunks(&mut chunk);
         self.chunks.push(chunk);
@@ -178,6 +357,9 @@ impl GameMap {
         let map = GameMap { 
             chunks: chunks,
             num_tiles: 0,
+            tile_properties: MAP_TILE_PROPERTIES[0],
+            dynamic_solids: Vec::new(),
+            chunk_index: ChunkIndex::new(),
         };
     
     
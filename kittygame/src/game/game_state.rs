@@ -1,10 +1,30 @@
 
+use core::hash::{Hash, Hasher};
+
 use super::cloud::Cloud;
-use super::entities::{Player, WarpAbility};
-use super::game_constants::{COUNTDOWN_TIMER_START, START_DIFFICULTY_LEVEL, LEVELS_PER_MOOD, MAP_GEN_SETTINGS};
+use super::collision::{check_absolute_bounding_box_partially_inside_another, get_bound_of_character, AbsoluteBoundingBox};
+use super::contacts::{find_contacts, Contact, EntityRef};
+use super::fixed::Fixed;
+use super::entities::{Player, WarpAbility, WarpState};
+use super::ability_cards::AbilityCardTypes;
+use super::species::PlayableCharacter;
+use super::pickups::{get_n_pickups_for_difficulty, get_random_pickup_kind, try_place_pickup, Pickup};
+use super::hazards::{get_n_hazards_for_difficulty, try_place_hazard, Hazard, HazardKind};
+use super::game_constants::{DifficultySetting, DIFFICULTY_SETTINGS, START_DIFFICULTY_LEVEL, LEVELS_PER_MOOD, MAP_GEN_SETTINGS, TUTORIAL_MAP_GEN_SETTING};
 use super::menus::GameMode;
+use super::map_generators::{place_dynamic_solids, place_special_tiles};
+use super::navigation::NavGraph;
+use super::options::GameOptions;
+use super::storage::SaveData;
+use super::achievements::{Achievement, AchievementEvent, Achievements};
+use super::tutorial::Tutorial;
+use super::run_stats::RunStats;
+use super::high_scores::HighScores;
+use super::music::SoundEffect;
 use super::popup_text::PopTextRingbuffer;
+use super::replay::Replay;
 use super::rng::GameRng;
+use super::state_hash::StateHasher;
 use super::{
     camera::Camera,
     entities::{Character, OptionallyEnabledPlayer},
@@ -14,52 +34,113 @@ use super::{
         TILE_HEIGHT_PX, TILE_WIDTH_PX,
     },
     game_map::GameMap,
-    mapchunk::{MapChunk, TileAlignedBoundingBox},
+    mapchunk::MapChunk,
     rng::Rng,
 };
-use crate::game::ability_cards::AbilityCardStack;
-use crate::game::game_map::MAP_TILESETS;
+use crate::game::card_content::CardCombo;
+use crate::game::game_map::{MAP_TILESETS, MAP_TILE_PROPERTIES};
 use crate::game::music::SONGS;
 // use crate::kitty_ss;
 use crate::spritesheet::{self, KITTY_SPRITESHEET_PALETTES};
 
-// Games can either be fixed-seed and timed for speedrunning, or random.
-type RunSeed = u32;
+// Games can either be fixed-seed and timed for speedrunning, random, or the tutorial level.
+pub type RunSeed = u32;
+#[derive(Clone, Copy, Hash)]
 pub enum RunType {
     Random,
-    Speedrun(RunSeed)
+    Speedrun(RunSeed),
+    Tutorial,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Difficulty {
+    Easy,
+    Medium,
+    Hard
+}
+
+impl Difficulty {
+    // easiest first.
+    pub const ALL: [Difficulty; 3] = [Difficulty::Easy, Difficulty::Medium, Difficulty::Hard];
+
+    pub fn setting(self: &Self) -> &'static DifficultySetting {
+        match self {
+            Difficulty::Easy => &DIFFICULTY_SETTINGS[0],
+            Difficulty::Medium => &DIFFICULTY_SETTINGS[1],
+            Difficulty::Hard => &DIFFICULTY_SETTINGS[2],
+        }
+    }
+
+    pub fn name(self: &Self) -> &'static str {
+        self.setting().name
+    }
+
+    pub fn from_name(name: &str) -> Option<Difficulty> {
+        Difficulty::ALL.into_iter().find(|d| d.name().eq_ignore_ascii_case(name))
+    }
 }
 
-// pub enum Difficulty {
-//     Easy,
-//     Medium,
-//     Hard
-// }
+/// How things stood just before the current level was generated, so the pause menu can restart it.
+pub struct LevelStart {
+    rng: GameRng,
+    countdown_timer_msec: u32,
+    countdown_frozen_frames: u32,
+    score: u32,
+    // each player's cards, whether they could warp, and what was left of their card effects.
+    // Anything picked up or used during the level is given back.
+    decks: [Vec<AbilityCardTypes>; 4],
+    could_warp: [bool; 4],
+    banked_air_jumps: [u32; 4],
+    dash_frames: [u32; 4],
+    magnet_frames: [u32; 4],
+}
 
 pub struct GameSettings {
     pub run_type: RunType,
-    // pub difficulty: Difficulty
+    pub difficulty: Difficulty,
+    // who each player picked. Players who join partway through play as who they picked too.
+    pub characters: [PlayableCharacter; 4],
 }
 
 
 pub struct GameState {
     pub players: [OptionallyEnabledPlayer; 4],
     pub npcs: Vec<Character>,
+    pub hazards: Vec<Hazard>,
+    pub pickups: Vec<Pickup>,
+    pub pickups_collected: u32,
+    // everything touching everything else this frame.
+    pub contacts: Vec<Contact>,
+    // scratch space, cleared and filled again each frame so it's only allocated once: everyone's
+    // bounds to find the contacts with, the characters' bounds and what they're riding for the
+    // dynamic solids, and which pickups go to which player.
+    pub contact_entities: Vec<(AbsoluteBoundingBox<i32, u32>, EntityRef)>,
+    pub solid_riders: Vec<(AbsoluteBoundingBox<i32, u32>, Option<usize>)>,
+    pub taken_pickups: Vec<(usize, usize)>,
     pub background_tiles: &'static Vec<spritesheet::Sprite>,
     pub map: GameMap,
+    pub nav_graph: NavGraph,
     pub camera: Camera,
     pub rng: GameRng,
     pub game_mode: GameMode,
     pub countdown_timer_msec: u32,
     pub countdown_paused: bool,
+    // from freeze cards: the countdown stays put until this runs out.
+    pub countdown_frozen_frames: u32,
     pub godmode: bool,
     pub pallette_idx: usize,
     pub song_idx: usize,
     pub song_timer: u32,
     pub difficulty_level: u32,
     pub total_npcs_to_find: u32,
+    // where we are in the tutorial, on a tutorial run.
+    pub tutorial: Option<Tutorial>,
+    // how this run's going, for the summary at the end.
+    pub run_stats: RunStats,
     pub score: u32,
     pub popup_text_ringbuffer: PopTextRingbuffer,
+    // the last card combo someone pulled off, and how much longer to show it.
+    pub combo_banner: Option<(&'static CardCombo, u32)>,
     pub tileset_idx: usize,
     pub map_gen_settings_idx: usize,
     pub tutorial_text_counter: u8,
@@ -67,15 +148,31 @@ pub struct GameState {
     pub countdown_and_score_bonus: u32,
     pub settings: GameSettings,
     pub speedrun_timer_msec: u32,
+    // frames since the game started.
+    pub frame_count: u32,
+    // everything played so far, if we're recording.
+    pub replay: Option<Replay>,
+    pub options: GameOptions,
+    // the options changed since the platform last saved them.
+    pub save_dirty: bool,
+    pub achievements: Achievements,
+    pub high_scores: HighScores,
+    // sounds to play once this frame's over.
+    pub sound_effects: Vec<SoundEffect>,
+    // what happened this frame, for the achievements to look at once it's over.
+    pub achievement_events: Vec<AchievementEvent>,
+    // just unlocked, to be shown one at a time, and how long the first has been up.
+    pub achievement_toasts: Vec<Achievement>,
+    pub achievement_toast_timer: u32,
+    pub level_start: Option<LevelStart>,
+    // the frame the game was paused on, while it's paused. Everything in the level stays put until then.
+    pub paused_since_frame: Option<u32>,
 }
 
 impl GameState {
     pub fn new() -> GameState {
         let characters = [
-            OptionallyEnabledPlayer::Enabled(Player {
-                character: Character::new(spritesheet::PresetSprites::MainCat),
-                card_stack: AbilityCardStack { cards: Vec::new() },
-            }),
+            OptionallyEnabledPlayer::Enabled(Player::new(PlayableCharacter::MainCat)),
             OptionallyEnabledPlayer::Disabled,
             OptionallyEnabledPlayer::Disabled,
             OptionallyEnabledPlayer::Disabled,
@@ -86,29 +183,41 @@ impl GameState {
         GameState {
             players: characters,
             npcs: Vec::new(),
+            hazards: Vec::new(),
+            pickups: Vec::new(),
+            pickups_collected: 0,
+            contacts: Vec::new(),
+            contact_entities: Vec::new(),
+            solid_riders: Vec::new(),
+            taken_pickups: Vec::new(),
             background_tiles: spritesheet::Sprite::get_spritesheet(),
             map: GameMap::create_map(),
+            nav_graph: NavGraph::new(),
             camera: Camera {
-                current_viewing_x_offset: 0.0,
-                current_viewing_y_offset: 0.0,
-                current_viewing_x_target: 0.0,
-                current_viewing_y_target: 0.0,
+                current_viewing_x_offset: Fixed::ZERO,
+                current_viewing_y_offset: Fixed::ZERO,
+                current_viewing_x_target: Fixed::ZERO,
+                current_viewing_y_target: Fixed::ZERO,
             },
             rng,
             game_mode: GameMode::StartScreen,
             countdown_timer_msec: 60 * 3,
             countdown_paused: false,
+            countdown_frozen_frames: 0,
             godmode: false,
             pallette_idx: 0,
             song_idx: 0,
             song_timer: 0,
             difficulty_level: START_DIFFICULTY_LEVEL,
             total_npcs_to_find: 3,
+            tutorial: None,
+            run_stats: RunStats::new(),
             score: 0,
             popup_text_ringbuffer: PopTextRingbuffer {
                 texts: [None, None, None, None, None, None, None, None, None, None],
                 next_avail_idx: 0,
             },
+            combo_banner: None,
             tileset_idx: 0,
             map_gen_settings_idx: 0,
             tutorial_text_counter: 0,
@@ -116,13 +225,63 @@ impl GameState {
             countdown_and_score_bonus: 0,
             settings: GameSettings{
                 run_type: RunType::Random,
-                // difficulty: Difficulty::Medium
+                difficulty: Difficulty::Medium,
+                characters: [PlayableCharacter::MainCat; 4],
             },
             speedrun_timer_msec: 0,
+            frame_count: 0,
+            replay: None,
+            options: GameOptions::new(),
+            save_dirty: false,
+            achievements: Achievements::new(),
+            high_scores: HighScores::new(),
+            sound_effects: Vec::new(),
+            achievement_events: Vec::new(),
+            achievement_toasts: Vec::new(),
+            achievement_toast_timer: 0,
+            level_start: None,
+            paused_since_frame: None,
+        }
+    }
+
+    /// Generate the map that a seed mode run plays on some level. Every level draws from
+    /// the same worldgen stream, so all the levels before it need to be generated too.
+    pub fn regenerate_seeded_map(self: &mut Self, seed: RunSeed, level: u32) {
+        self.settings.run_type = RunType::Speedrun(seed);
+        self.rng = GameRng::FixedSeed(Rng::new_from_seed(seed), Rng::new_from_seed(seed));
+        for l in START_DIFFICULTY_LEVEL..=level {
+            self.difficulty_level = l;
+            self.regenerate_map();
         }
     }
 
     pub fn regenerate_map(self: &mut Self) {
+        self.level_start = Some(LevelStart {
+            rng: self.rng.clone(),
+            countdown_timer_msec: self.countdown_timer_msec,
+            countdown_frozen_frames: self.countdown_frozen_frames,
+            score: self.score,
+            decks: self.players.each_ref().map(|optional_player| match optional_player {
+                OptionallyEnabledPlayer::Enabled(p) => p.card_stack.cards.iter().flatten().map(|card| card.card_type).collect(),
+                OptionallyEnabledPlayer::Disabled => Vec::new(),
+            }),
+            could_warp: self.players.each_ref().map(|optional_player| match optional_player {
+                OptionallyEnabledPlayer::Enabled(p) => p.character.warp_ability != WarpAbility::CannotWarp,
+                OptionallyEnabledPlayer::Disabled => false,
+            }),
+            banked_air_jumps: self.players.each_ref().map(|optional_player| match optional_player {
+                OptionallyEnabledPlayer::Enabled(p) => p.character.banked_air_jumps,
+                OptionallyEnabledPlayer::Disabled => 0,
+            }),
+            dash_frames: self.players.each_ref().map(|optional_player| match optional_player {
+                OptionallyEnabledPlayer::Enabled(p) => p.character.dash_frames,
+                OptionallyEnabledPlayer::Disabled => 0,
+            }),
+            magnet_frames: self.players.each_ref().map(|optional_player| match optional_player {
+                OptionallyEnabledPlayer::Enabled(p) => p.magnet_frames,
+                OptionallyEnabledPlayer::Disabled => 0,
+            }),
+        });
         self.godmode = false;
 
 
@@ -141,6 +300,7 @@ impl GameState {
         // set the tileset
         {
             self.tileset_idx = ((self.difficulty_level as usize - 1) / LEVELS_PER_MOOD) % MAP_TILESETS.len();
+            self.map.tile_properties = MAP_TILE_PROPERTIES[self.tileset_idx];
         }
         
 
@@ -151,28 +311,36 @@ impl GameState {
 
 
 
-        let map_gen_setting = &MAP_GEN_SETTINGS[self.map_gen_settings_idx];
-        let map_chunk_min_side_len = map_gen_setting.chunk_min_side_len;
-        let map_chunk_max_side_len = map_gen_setting.chunk_max_side_len;
-        let max_n_tiles_in_chunk = map_gen_setting.max_n_tiles_per_chunk;
-        
+        let tutorial_run = matches!(self.settings.run_type, RunType::Tutorial);
+        self.tutorial = match tutorial_run {
+            true => Some(Tutorial::new()),
+            false => None,
+        };
+        let map_gen_setting = match tutorial_run {
+            true => &TUTORIAL_MAP_GEN_SETTING,
+            false => &MAP_GEN_SETTINGS[self.map_gen_settings_idx],
+        };
         // an average-sized map is ~ 30x30 = 900 blocks. Anything smaller is more twisty and denser. Make those
         // twistier maps smaller by a linear factor.
 
-        let max_n_tiles_in_map: u32 = (0.7 * 2048.0) as u32 + (map_gen_setting.linear_mapsize_mult * 0.25 * 2048.0) as u32 * self.difficulty_level;
+        let difficulty = self.settings.difficulty.setting();
+        let max_n_tiles_in_map: u32 = (0.7 * 2048.0) as u32 + (map_gen_setting.linear_mapsize_mult * difficulty.mapsize_mult * 0.25 * 2048.0) as u32 * self.difficulty_level;
 
 
         let map = &mut self.map;
         map.num_tiles = 0;
         map.chunks.clear();
+        map.chunk_index.clear();
+        self.nav_graph.clear();
         let rng = &mut self.rng;
 
         for optional_player in self.players.iter_mut() {
             match optional_player {
                 OptionallyEnabledPlayer::Enabled(p) => {
-                    p.character.x_pos = 10.0;
-                    p.character.y_pos = 10.0;
-                    p.character.can_fly = false;
+                    p.character.x_pos = Fixed::from_int(10);
+                    p.character.y_pos = Fixed::from_int(10);
+                    p.character.can_fly = p.character.species.physics.can_fly;
+                    p.warp.reset_for_level();
                 }
                 OptionallyEnabledPlayer::Disabled => {}
             }
@@ -183,9 +351,9 @@ impl GameState {
         npcs.clear();
 
         self.total_npcs_to_find =
-            (1 + (self.difficulty_level / 3) + rng.next_for_worldgen() as u32 % 3).min(MAX_N_NPCS as u32);
+            ((1 + (self.difficulty_level / 3) + rng.next_for_worldgen() as u32 % 3) * difficulty.npcs_to_find_percent / 100).clamp(1, MAX_N_NPCS as u32);
 
-        self.countdown_and_score_bonus = (4 + self.difficulty_level.min(20) / 3) * 60;
+        self.countdown_and_score_bonus = (4 + self.difficulty_level.min(20) / 3) * 60 * difficulty.countdown_bonus_percent / 100;
 
         self.countdown_timer_msec += self.countdown_and_score_bonus;
         self.countdown_timer_msec = self.countdown_timer_msec.min(100 * 60 - 1);
@@ -193,7 +361,8 @@ impl GameState {
 
         match self.difficulty_level {
             START_DIFFICULTY_LEVEL => {
-                self.countdown_timer_msec = COUNTDOWN_TIMER_START;
+                self.countdown_timer_msec = difficulty.countdown_timer_start;
+                self.countdown_frozen_frames = 0;
                 self.score = 0;
                 self.tutorial_text_counter = 0;
 
@@ -224,232 +393,227 @@ impl GameState {
             };
             npcs.push(Character::new(preset));
         }
+        // the tutorial only has the one kitty to find, and it's always a lizard.
+        if tutorial_run {
+            npcs.clear();
+            npcs.push(Character::new(spritesheet::PresetSprites::Lizard));
+            self.total_npcs_to_find = 1;
+        }
 
-        let mut current_chunk_locations: Vec<TileAlignedBoundingBox> = Vec::new();
+        // lay out and fill in the chunks.
+        map_gen_setting.algorithm.generator().generate(map, map_gen_setting, max_n_tiles_in_map, rng);
+        if !tutorial_run {
+            place_special_tiles(map, rng);
+            place_dynamic_solids(map, map_gen_setting, rng);
+        }
 
-        match current_chunk_locations.try_reserve(1) {
-            Ok(_) => {
-                current_chunk_locations.push(TileAlignedBoundingBox::init(0, 0, 32, 32));
-            }
-            Err(_) => {
-                return;
-            }
+        // spawn npcs (disallow spawning in origin chunk)
+        for i in 0..npcs.len() {
+            let rand_chunk_i = (rng.next_for_worldgen() % (map.chunks.len() - 1) as u64) as usize + 1;
+            let chunk: &MapChunk = &map.chunks[rand_chunk_i];
+            npcs[i].x_pos = Fixed::from_int(chunk.bound.x * TILE_WIDTH_PX as i32 + 10);
+            npcs[i].y_pos = Fixed::from_int(chunk.bound.y * TILE_HEIGHT_PX as i32 + 10);
         }
-        // place the chunks randomly.
-        let mut tile_count = 0;
 
-        'generate_chunks: loop {
-            if tile_count >= max_n_tiles_in_map {
-                break 'generate_chunks;
+        // reset NPCs
+        for npc in npcs.iter_mut() {
+            npc.following_i = None;
+        }
+
+        // spawn hazards, more of them the further in we are.
+        self.hazards.clear();
+        let n_hazards = match tutorial_run {
+            true => 0,
+            false => get_n_hazards_for_difficulty(self.difficulty_level),
+        };
+        for _ in 0..n_hazards {
+            let kind = match rng.next_for_worldgen() % 2 {
+                0 => HazardKind::Spikeball,
+                _ => HazardKind::Icicle,
+            };
+            if let Some(hazard) = try_place_hazard(map, kind, rng) {
+                self.hazards.push(hazard);
             }
-            // attempt to place a new chunk
-            // if in viable location, place this chunk
-            'generate_one_chunk: loop {
-                // choose a new viable chunk size
-
-                let mut chunk_wid: usize;
-                let mut chunk_hei: usize;
-                'find_place_for_chunk: loop {
-                    chunk_wid = map_chunk_min_side_len
-                        + (rng.next_for_worldgen() as usize % (map_chunk_max_side_len - map_chunk_min_side_len));
-                    chunk_hei = map_chunk_min_side_len
-                        + (rng.next_for_worldgen() as usize % (map_chunk_max_side_len - map_chunk_min_side_len));
-                    if chunk_hei * chunk_wid <= max_n_tiles_in_chunk {
-                        if map.try_fit_chunk_into(chunk_wid, chunk_hei) {
-                            break 'find_place_for_chunk;
-                        } else {
-                            break 'generate_chunks;
-                        }
-                    }
-                }
+        }
 
-                let r_offs_1: i32 = rng.next_for_worldgen() as i32 % map_chunk_min_side_len as i32
-                    - (map_chunk_min_side_len as f32 / 2.0) as i32;
-
-                let random_chunk_from_list_i =
-                    (rng.next_for_worldgen() % current_chunk_locations.len() as u64) as usize;
-                let vertical_stack = rng.next_for_worldgen() % 2 == 1;
-                let positive_stack = rng.next_for_worldgen() % 2 == 1;
-                let rand_bound = &current_chunk_locations[random_chunk_from_list_i];
-                let new_chunk_location: TileAlignedBoundingBox;
-
-                if vertical_stack {
-                    if positive_stack {
-                        new_chunk_location = TileAlignedBoundingBox::init(
-                            rand_bound.x + r_offs_1,
-                            rand_bound.y + rand_bound.height as i32,
-                            chunk_wid,
-                            chunk_hei,
-                        );
-                    } else {
-                        new_chunk_location = TileAlignedBoundingBox::init(
-                            rand_bound.x + r_offs_1,
-                            rand_bound.y - chunk_hei as i32,
-                            chunk_wid,
-                            chunk_hei,
-                        );
-                    }
-                } else {
-                    if positive_stack {
-                        new_chunk_location = TileAlignedBoundingBox::init(
-                            rand_bound.x + rand_bound.width as i32,
-                            rand_bound.y + r_offs_1,
-                            chunk_wid,
-                            chunk_hei,
-                        );
-                    } else {
-                        new_chunk_location = TileAlignedBoundingBox::init(
-                            rand_bound.x - chunk_wid as i32,
-                            rand_bound.y + r_offs_1,
-                            chunk_wid,
-                            chunk_hei,
-                        );
-                    }
-                }
-                let mut is_viable_spot = true;
-
-                fn shares_enough_axes_with_other_bounds(
-                    potential_bound: &TileAlignedBoundingBox,
-                    source_bound: &TileAlignedBoundingBox,
-                    side_len: usize,
-                ) -> bool {
-                    let b1: &TileAlignedBoundingBox = potential_bound;
-                    let b2: &TileAlignedBoundingBox = source_bound;
-
-                    fn do_for_one_side(
-                        b1: &TileAlignedBoundingBox,
-                        b2: &TileAlignedBoundingBox,
-                        side_len: usize,
-                    ) -> bool {
-                        if b1.y + b1.height as i32 == b2.y {
-                            if (b1.x + b1.width as i32 - b2.x).min(b2.x + b2.width as i32 - b1.x)
-                                >= side_len as i32
-                            {
-                                return true;
-                            } else {
-                                return false;
-                            }
-                        }
+        // scatter some pickups around.
+        self.pickups.clear();
+        self.pickups_collected = 0;
+        let n_pickups = match tutorial_run {
+            true => 0,
+            false => get_n_pickups_for_difficulty(self.difficulty_level),
+        };
+        for _ in 0..n_pickups {
+            let kind = get_random_pickup_kind(rng);
+            if let Some(pickup) = try_place_pickup(map, kind, rng) {
+                self.pickups.push(pickup);
+            }
+        }
 
-                        if b1.x + b1.width as i32 == b2.x {
-                            if (b1.y + b1.height as i32 - b2.y).min(b2.y + b2.height as i32 - b1.y)
-                                >= side_len as i32
-                            {
-                                return true;
-                            } else {
-                                return false;
-                            }
-                        }
-                        true
-                    }
+        // so followers can find their way around the new map.
+        self.nav_graph.rebuild(&self.map);
 
-                    do_for_one_side(&b1, &b2, side_len) && do_for_one_side(&b2, b1, side_len)
-                }
+        // so collision doesn't have to look through every chunk.
+        self.map.rebuild_chunk_index();
 
-                // ensure it shares enough adjacency with source chunk
-                if !shares_enough_axes_with_other_bounds(&rand_bound, &new_chunk_location, map_chunk_min_side_len) {
-                    is_viable_spot = false;
-                }
+        self.run_stats.start_level(self.difficulty_level, self.countdown_timer_msec);
+    }
 
-                for other_bound in &current_chunk_locations {
-                    // if it collides with existing chunk, disallow
-                    if new_chunk_location.y + new_chunk_location.height as i32 > other_bound.y {
-                        if new_chunk_location.y < other_bound.y + other_bound.height as i32 {
-                            if new_chunk_location.x + new_chunk_location.width as i32
-                                > other_bound.x
-                            {
-                                if new_chunk_location.x < other_bound.x + other_bound.width as i32 {
-                                    is_viable_spot = false;
-                                }
-                            }
-                        }
-                    }
-                    // if it doesn't collide, but it share too little with any adjacent chunks, it's also invalid
-                    if !shares_enough_axes_with_other_bounds(&other_bound, &new_chunk_location, map_chunk_min_side_len) {
-                        is_viable_spot = false;
-                    }
-                }
+    /// A hash of everything that decides how the game plays out from here: the map, everyone on it,
+    /// the RNG, the timers, the cards and which screen we're on. Two builds that hash the same after
+    /// every frame are playing the same game. Things that are only drawn, like the camera, clouds
+    /// and popup text, are left out.
+    pub fn state_hash(self: &Self) -> u64 {
+        let mut hasher = StateHasher::new();
+        let h = &mut hasher;
+
+        self.map.chunks.hash(h);
+        self.map.dynamic_solids.hash(h);
+        self.players.hash(h);
+        self.npcs.hash(h);
+        self.hazards.hash(h);
+        self.pickups.hash(h);
+        self.pickups_collected.hash(h);
+        self.rng.hash(h);
+
+        self.game_mode.hash(h);
+        self.settings.run_type.hash(h);
+        self.settings.difficulty.hash(h);
+        self.settings.characters.hash(h);
+        self.difficulty_level.hash(h);
+        self.total_npcs_to_find.hash(h);
+        self.tutorial.hash(h);
+        self.score.hash(h);
+        self.countdown_timer_msec.hash(h);
+        self.countdown_paused.hash(h);
+        self.countdown_frozen_frames.hash(h);
+        self.countdown_and_score_bonus.hash(h);
+        self.speedrun_timer_msec.hash(h);
+        self.song_timer.hash(h);
+        self.tutorial_text_counter.hash(h);
+        self.godmode.hash(h);
+        // the controls and tutorial change how the game plays out, and the rest come along for free.
+        self.options.hash(h);
+        self.paused_since_frame.hash(h);
+
+        hasher.finish()
+    }
 
-                if is_viable_spot {
-                    // trace(format!("pushing chunk {new_chunk_location:?}"));
-                    match current_chunk_locations.try_reserve(1) {
-                        Ok(_) => {
-                            current_chunk_locations.push(new_chunk_location);
-                            tile_count += (chunk_hei * chunk_wid) as u32;
-                            map.num_tiles += chunk_hei * chunk_wid;
-                            break 'generate_one_chunk;
-                        }
-                        Err(_) => {
-                            break 'generate_chunks;
-                        }
-                    }
+    /// Put everything back how it was when this level started, and generate the same map again.
+    ///
+    /// The run stats aren't rewound: the attempt that was given up on goes in as a level that's over,
+    /// like one that ran out of time, and the jumps, cards and so on from it still count.
+    pub fn restart_level(self: &mut Self) {
+        let Some(level_start) = self.level_start.take() else {
+            return;
+        };
+        self.run_stats.end_level();
+        self.rng = level_start.rng;
+        self.countdown_timer_msec = level_start.countdown_timer_msec;
+        self.countdown_frozen_frames = level_start.countdown_frozen_frames;
+        self.score = level_start.score;
+        for (i, optional_player) in self.players.iter_mut().enumerate() {
+            if let OptionallyEnabledPlayer::Enabled(p) = optional_player {
+                p.card_stack.cards.clear();
+                for card_type in level_start.decks[i].iter() {
+                    p.card_stack.try_push_card(*card_type, Fixed::ZERO, Fixed::ZERO);
                 }
+                p.character.warp_ability = match level_start.could_warp[i] {
+                    true => WarpAbility::CanWarp(WarpState::Charging(0)),
+                    false => WarpAbility::CannotWarp,
+                };
+                p.character.banked_air_jumps = level_start.banked_air_jumps[i];
+                p.character.dash_frames = level_start.dash_frames[i];
+                p.magnet_frames = level_start.magnet_frames[i];
             }
         }
+        self.regenerate_map();
+    }
 
-        'init_the_chunks: for current_chunk_location in current_chunk_locations.into_iter() {
-            let mut chunk = MapChunk::init();
-
-            chunk.bound = current_chunk_location;
-
-            match chunk.initialize() {
-                true => {}
-                false => {
-                    break 'init_the_chunks;
-                }
+    /// Unfreeze the level. In seed mode, how long it was paused goes in the replay, since it doesn't count toward the time.
+    pub fn end_pause(self: &mut Self) {
+        if let Some(paused_since_frame) = self.paused_since_frame.take() {
+            if let (RunType::Speedrun(_), Some(replay)) = (&self.settings.run_type, &mut self.replay) {
+                replay.record_pause(paused_since_frame, self.frame_count - paused_since_frame);
             }
+        }
+    }
 
-            let corrupt_materials: [u8; 7] = [9, 10, 11, 12, 13, 14, 15];
-            const CORRUPT_CHANCE: f32 = 0.2;
-
-            fn get_material(normal: u8, corrupt: u8, chance: f32, rng: &mut GameRng) -> u8 {
-                if (rng.next_for_worldgen() as u8 % 255) as f32 > 255.0 * chance {
-                    return normal;
-                }
-                corrupt
-            }
+    /// What gets kept between sessions.
+    pub fn save_data(self: &Self) -> SaveData {
+        SaveData {
+            options: self.options,
+            achievements: self.achievements,
+            high_scores: self.high_scores,
+        }
+    }
 
-            // left and right walls
-            for row in 1..chunk.bound.height - 1 as usize {
-                let corrupt_material: u8 =
-                    corrupt_materials[rng.next_for_worldgen() as usize % corrupt_materials.len()];
-                let left_material = get_material(7, corrupt_material, CORRUPT_CHANCE, rng);
-                let right_material = get_material(3, corrupt_material, CORRUPT_CHANCE, rng);
+    pub fn load_save_data(self: &mut Self, save: SaveData) {
+        self.options = save.options;
+        self.achievements = save.achievements;
+        self.high_scores = save.high_scores;
+    }
 
-                chunk.set_tile(0, row, left_material);
-                chunk.set_tile(chunk.bound.width as usize - 1, row, right_material);
+    /// The run's over. If it was a random run that beat the best on its difficulty, save that.
+    pub fn record_high_score(self: &mut Self, won: bool) {
+        if let RunType::Random = self.settings.run_type {
+            let won_in_frames = if won { Some(self.speedrun_timer_msec) } else { None };
+            if self.high_scores.record_run(self.settings.difficulty, self.score, won_in_frames) {
+                self.save_dirty = true;
             }
+        }
+    }
 
-            // top and bottom walls
-            for col in 1..chunk.bound.width - 1 as usize {
-                let corrupt_material: u8 =
-                    corrupt_materials[rng.next_for_worldgen() as usize % corrupt_materials.len()];
-                let top_material = get_material(1, corrupt_material, CORRUPT_CHANCE, rng);
-                let bottom_material = get_material(5, corrupt_material, CORRUPT_CHANCE, rng);
-                chunk.set_tile(col, 0, top_material);
-                chunk.set_tile(col, chunk.bound.height as usize - 1, bottom_material);
+    /// Show this frame's achievement_events to the achievements. Any they unlock get saved and shown.
+    pub fn handle_achievement_events(self: &mut Self) {
+        let events = core::mem::take(&mut self.achievement_events);
+        // the tutorial's lizard is a given, so nothing there counts.
+        if self.tutorial.is_some() {
+            return;
+        }
+        for event in events.iter() {
+            let newly_unlocked = self.achievements.handle_event(event);
+            if !newly_unlocked.is_empty() {
+                self.save_dirty = true;
+                self.achievement_toasts.extend(newly_unlocked);
             }
+        }
+    }
 
-            // corners
-            chunk.set_tile(0, 0, 8);
-            chunk.set_tile(chunk.bound.width as usize - 1, chunk.bound.height as usize - 1, 4);
-            chunk.set_tile(chunk.bound.width as usize - 1, 0, 2);
-            chunk.set_tile(0, chunk.bound.height as usize - 1, 6);
+    /// Find out who's touching whom this frame, for the entity, hazard and pickup collision checks.
+    pub fn update_contacts(self: &mut Self) {
+        let mut entities = core::mem::take(&mut self.contact_entities);
+        let mut contacts = core::mem::take(&mut self.contacts);
+        find_contacts(self, &mut entities, &mut contacts);
+        self.contact_entities = entities;
+        self.contacts = contacts;
+    }
 
-            map.add_chunk(chunk);
+    /// Move platforms along, and crumble whatever someone stood on last frame.
+    pub fn update_dynamic_solids(self: &mut Self) {
+        let riders = &mut self.solid_riders;
+        riders.clear();
+        for optional_player in self.players.iter() {
+            if let OptionallyEnabledPlayer::Enabled(p) = optional_player {
+                riders.push((get_bound_of_character(&p.character), p.character.riding_solid));
+            }
         }
-
-        // spawn npcs (disallow spawning in origin chunk)
-        for i in 0..npcs.len() {
-            let rand_chunk_i = rng.next_for_worldgen() as usize % (map.chunks.len() - 1) + 1;
-            let chunk: &MapChunk = &map.chunks[rand_chunk_i];
-            npcs[i].x_pos = chunk.bound.x as f32 * TILE_WIDTH_PX as f32 + 10.0;
-            npcs[i].y_pos = chunk.bound.y as f32 * TILE_HEIGHT_PX as f32 + 10.0;
+        for npc in self.npcs.iter() {
+            riders.push((get_bound_of_character(npc), npc.riding_solid));
         }
 
-        // reset NPCs
-        for npc in npcs.iter_mut() {
-            npc.following_i = None;
+        for (i, solid) in self.map.dynamic_solids.iter_mut().enumerate() {
+            let solid_bound = solid.get_bound();
+            let mut stood_on = false;
+            let mut occupied = false;
+            for (bound, riding_solid) in riders.iter() {
+                occupied |= check_absolute_bounding_box_partially_inside_another(bound, &solid_bound);
+                // riding_solid only remembers one of the solids under our feet, so crumble the rest too.
+                let below = AbsoluteBoundingBox { y: bound.y + 1, ..*bound };
+                stood_on |= *riding_solid == Some(i)
+                    || (riding_solid.is_some() && check_absolute_bounding_box_partially_inside_another(&below, &solid_bound));
+            }
+            solid.update(stood_on, occupied);
         }
     }
 }
@@ -0,0 +1,179 @@
+use super::{
+    cloud::Cloud,
+    entities::OptionallyEnabledPlayer,
+    fixed::Fixed,
+    game_constants::{MAX_N_HAZARDS, START_DIFFICULTY_LEVEL, TILE_HEIGHT_PX, TILE_WIDTH_PX},
+    game_map::GameMap,
+    rng::GameRng,
+};
+use crate::game::collision::AbsoluteBoundingBox;
+
+pub const HAZARD_WIDTH_PX: u32 = 5;
+pub const HAZARD_HEIGHT_PX: u32 = 5;
+
+// how much countdown time a hit costs, and how long the player blinks afterwards.
+pub const HAZARD_HIT_PENALTY: u32 = 5 * 60;
+pub const HAZARD_INVULNERABLE_FRAMES: u8 = 90;
+
+#[derive(Hash)]
+pub enum HazardKind {
+    // rolls back and forth along a floor.
+    Spikeball,
+    // hangs from a ceiling and drops on whoever walks underneath.
+    Icicle,
+}
+
+#[derive(Hash)]
+pub enum HazardState {
+    Patrolling,
+    Hanging,
+    Falling,
+    Regrowing(u16),
+}
+
+#[derive(Hash)]
+pub struct Hazard {
+    pub kind: HazardKind,
+    pub state: HazardState,
+    pub x_pos: Fixed,
+    pub y_pos: Fixed,
+    pub x_vel: Fixed,
+    pub y_vel: Fixed,
+    pub home_x: Fixed,
+    pub home_y: Fixed,
+    pub count: u32,
+}
+
+impl Hazard {
+    pub fn new(kind: HazardKind, x: Fixed, y: Fixed) -> Hazard {
+        const SPIKEBALL_SPEED: Fixed = Fixed::from_f32(0.5);
+        let (state, x_vel) = match kind {
+            HazardKind::Spikeball => (HazardState::Patrolling, SPIKEBALL_SPEED),
+            HazardKind::Icicle => (HazardState::Hanging, Fixed::ZERO),
+        };
+        Hazard {
+            kind,
+            state,
+            x_pos: x,
+            y_pos: y,
+            x_vel,
+            y_vel: Fixed::ZERO,
+            home_x: x,
+            home_y: y,
+            count: 0,
+        }
+    }
+
+    pub fn get_bound(self: &Self) -> AbsoluteBoundingBox<i32, u32> {
+        AbsoluteBoundingBox {
+            x: self.x_pos.to_i32(),
+            y: self.y_pos.to_i32(),
+            width: HAZARD_WIDTH_PX,
+            height: HAZARD_HEIGHT_PX,
+        }
+    }
+
+    // regrowing icicles can't hurt anyone.
+    pub fn is_dangerous(self: &Self) -> bool {
+        match self.state {
+            HazardState::Regrowing(_) => false,
+            _ => true,
+        }
+    }
+
+    pub fn update(self: &mut Self, map: &GameMap, players: &[OptionallyEnabledPlayer; 4], clouds: &mut Vec<Cloud>) {
+        self.count += 1;
+        let w = HAZARD_WIDTH_PX as i32;
+        let h = HAZARD_HEIGHT_PX as i32;
+        match self.state {
+            HazardState::Patrolling => {
+                // turn around at walls and ledges.
+                let next_x = self.x_pos + self.x_vel;
+                let leading_x = match self.x_vel > Fixed::ZERO {
+                    true => next_x.to_i32() + w - 1,
+                    false => next_x.to_i32(),
+                };
+                let floor_y = self.y_pos.to_i32() + h;
+                if map.is_solid_abs(leading_x, self.y_pos.to_i32() + h / 2)
+                    || !map.is_solid_abs(leading_x, floor_y)
+                    || !map.is_inside_abs(leading_x, floor_y)
+                {
+                    self.x_vel = -self.x_vel;
+                } else {
+                    self.x_pos = next_x;
+                }
+            }
+            HazardState::Hanging => {
+                const DROP_X_DIST: Fixed = Fixed::from_int(8);
+                const DROP_Y_DIST: Fixed = Fixed::from_int(60);
+                for opt_p in players.iter() {
+                    if let OptionallyEnabledPlayer::Enabled(p) = opt_p {
+                        let dx = p.character.x_pos - self.x_pos;
+                        let dy = p.character.y_pos - self.y_pos;
+                        if dx.abs() < DROP_X_DIST && dy > Fixed::ZERO && dy < DROP_Y_DIST {
+                            self.state = HazardState::Falling;
+                            self.y_vel = Fixed::ZERO;
+                        }
+                    }
+                }
+            }
+            HazardState::Falling => {
+                const FALL_GRAVITY: Fixed = Fixed::from_f32(0.25);
+                const FALL_VEL_CAP: Fixed = Fixed::from_int(4);
+                self.y_vel = (self.y_vel + FALL_GRAVITY).min(FALL_VEL_CAP);
+                self.y_pos += self.y_vel;
+                let bottom_y = self.y_pos.to_i32() + h;
+                let mid_x = self.x_pos.to_i32() + w / 2;
+                if map.is_solid_abs(mid_x, bottom_y) || !map.is_inside_abs(mid_x, bottom_y) {
+                    // shatter, then grow back where we started
+                    for dir in [(1.0, -0.5), (-1.0, -0.5), (0.5, -1.0), (-0.5, -1.0)] {
+                        const SHATTER_CLOUD_SPEED: f32 = 2.0;
+                        Cloud::try_push_cloud(clouds, self.x_pos.to_f32(), self.y_pos.to_f32() + 2.0, SHATTER_CLOUD_SPEED * dir.0, SHATTER_CLOUD_SPEED * dir.1);
+                    }
+                    const REGROW_FRAMES: u16 = 180;
+                    self.state = HazardState::Regrowing(REGROW_FRAMES);
+                    self.x_pos = self.home_x;
+                    self.y_pos = self.home_y;
+                    self.y_vel = Fixed::ZERO;
+                }
+            }
+            HazardState::Regrowing(t) => {
+                self.state = match t {
+                    0 => HazardState::Hanging,
+                    _ => HazardState::Regrowing(t - 1),
+                };
+            }
+        }
+    }
+}
+
+/// More hazards show up on later levels. The first level stays hazard-free.
+pub fn get_n_hazards_for_difficulty(difficulty_level: u32) -> u32 {
+    ((difficulty_level.saturating_sub(START_DIFFICULTY_LEVEL) + 1) / 2).min(MAX_N_HAZARDS as u32)
+}
+
+/// Look for a spot for a hazard: open floor for spikeballs, open ceiling for icicles.
+/// Never in the origin chunk, where the players start.
+pub fn try_place_hazard(map: &GameMap, kind: HazardKind, rng: &mut GameRng) -> Option<Hazard> {
+    const N_PLACEMENT_TRIES: usize = 20;
+    let tw = TILE_WIDTH_PX as i32;
+    let th = TILE_HEIGHT_PX as i32;
+    let (x, y) = map.try_find_spot(N_PLACEMENT_TRIES, rng, |x, y| {
+        if map.is_solid_abs(x, y) {
+            return false;
+        }
+        match kind {
+            HazardKind::Spikeball => {
+                map.is_solid_abs(x, y + th)
+                    && !map.is_solid_abs(x - tw, y)
+                    && !map.is_solid_abs(x + tw, y)
+            }
+            HazardKind::Icicle => {
+                map.is_solid_abs(x, y - th)
+                    && !map.is_solid_abs(x, y + th)
+                    && !map.is_solid_abs(x, y + 2 * th)
+            }
+        }
+    })?;
+    Some(Hazard::new(kind, Fixed::from_int(x), Fixed::from_int(y)))
+}
@@ -0,0 +1,68 @@
+// The best random run on each difficulty, kept in the save. Seeded runs are left out: their
+// times only compare against the same seed, and there are too many seeds to keep one for each.
+
+use super::game_state::Difficulty;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct HighScore {
+    pub score: u32,
+    // the fastest win, in frames. 0 until there's been one.
+    pub fastest_win_frames: u32,
+}
+
+impl HighScore {
+    const N_BYTES: usize = 8;
+
+    const NONE: HighScore = HighScore { score: 0, fastest_win_frames: 0 };
+}
+
+/// One HighScore per difficulty, in the order of Difficulty::ALL.
+#[derive(Clone, Copy)]
+pub struct HighScores {
+    best: [HighScore; 3],
+}
+
+impl HighScores {
+    pub const N_BYTES: usize = HighScore::N_BYTES * 3;
+
+    pub fn new() -> HighScores {
+        HighScores { best: [HighScore::NONE; 3] }
+    }
+
+    pub fn get(self: &Self, difficulty: Difficulty) -> HighScore {
+        self.best[difficulty as usize]
+    }
+
+    /// Keep the run's score, and its time if it was won, where they beat the best so far.
+    /// True if either did.
+    pub fn record_run(self: &mut Self, difficulty: Difficulty, score: u32, won_in_frames: Option<u32>) -> bool {
+        let best = &mut self.best[difficulty as usize];
+        let before = *best;
+        best.score = best.score.max(score);
+        if let Some(frames) = won_in_frames {
+            if best.fastest_win_frames == 0 || frames < best.fastest_win_frames {
+                best.fastest_win_frames = frames;
+            }
+        }
+        *best != before
+    }
+
+    pub fn to_bytes(self: &Self) -> [u8; HighScores::N_BYTES] {
+        let mut bytes = [0; HighScores::N_BYTES];
+        for (chunk, best) in bytes.chunks_exact_mut(HighScore::N_BYTES).zip(self.best.iter()) {
+            chunk[..4].copy_from_slice(&best.score.to_le_bytes());
+            chunk[4..].copy_from_slice(&best.fastest_win_frames.to_le_bytes());
+        }
+        bytes
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Option<HighScores> {
+        let bytes = bytes.get(..HighScores::N_BYTES)?;
+        let mut high_scores = HighScores::new();
+        for (chunk, best) in bytes.chunks_exact(HighScore::N_BYTES).zip(high_scores.best.iter_mut()) {
+            best.score = u32::from_le_bytes(chunk[..4].try_into().ok()?);
+            best.fastest_win_frames = u32::from_le_bytes(chunk[4..].try_into().ok()?);
+        }
+        Some(high_scores)
+    }
+}
@@ -0,0 +1,772 @@
+use super::{
+    dynamic_solids::{DynamicSolid, DynamicSolidKind},
+    game_constants::{MapGenSetting, MAX_N_DYNAMIC_SOLIDS, MAX_N_TILES_IN_WHOLE_MAP},
+    tutorial::{TUTORIAL_CHUNKS, TUTORIAL_SOLIDS},
+    game_map::{GameMap, TileProperty, ICE_TILE, ONE_WAY_TILE, SPIKE_TILE, SPRING_TILE},
+    mapchunk::{MapChunk, TileAlignedBoundingBox},
+    rng::GameRng,
+};
+
+/// Which layout algorithm a `MapGenSetting` builds its levels with.
+pub enum MapGenAlgorithm {
+    RandomRects,
+    Cave,
+    Tower,
+    BspRooms,
+    // the same hand-built map every time.
+    Tutorial,
+}
+
+impl MapGenAlgorithm {
+    pub fn generator(self: &Self) -> &'static dyn MapGenerator {
+        match self {
+            MapGenAlgorithm::RandomRects => &RandomRectsGenerator,
+            MapGenAlgorithm::Cave => &CaveGenerator,
+            MapGenAlgorithm::Tower => &TowerGenerator,
+            MapGenAlgorithm::BspRooms => &BspRoomsGenerator,
+            MapGenAlgorithm::Tutorial => &TutorialGenerator,
+        }
+    }
+}
+
+/// Lays out and fills the chunks of a level.
+///
+/// Every generator has to uphold what the rest of the game assumes about a map:
+/// - the first chunk sits at tile (0, 0), since players spawn and warp to (10, 10) px.
+/// - there are at least 2 chunks, since NPCs never spawn in the first one.
+/// - tiles (1..=4, 1..=4) of every chunk are open, since that's where NPCs spawn.
+/// - chunks only ever touch edge-to-edge, so `GameMap::add_chunk` can fuse them.
+/// - `map.num_tiles` is kept up to date and never exceeds `MAX_N_TILES_IN_WHOLE_MAP`.
+pub trait MapGenerator {
+    fn generate(self: &Self, map: &mut GameMap, setting: &MapGenSetting, max_n_tiles_in_map: u32, rng: &mut GameRng);
+}
+
+// middle tile in the tilesets, used for solid rock inside of chunks.
+const SOLID_TILE: u8 = 9;
+
+/// Make a chunk at this location with its outer walls drawn, or None if we're out of memory.
+fn build_walled_chunk(bound: TileAlignedBoundingBox, tile_properties: &[TileProperty; 16], rng: &mut GameRng) -> Option<MapChunk> {
+    let mut chunk = MapChunk::init();
+
+    chunk.bound = bound;
+
+    match chunk.initialize() {
+        true => {}
+        false => {
+            return None;
+        }
+    }
+
+    let corrupt_materials: [u8; 7] = [9, 10, 11, 12, 13, 14, 15];
+    const CORRUPT_CHANCE: f32 = 0.2;
+
+    fn get_material(normal: u8, corrupt: u8, chance: f32, rng: &mut GameRng) -> u8 {
+        if (rng.next_for_worldgen() as u8 % 255) as f32 > 255.0 * chance {
+            return normal;
+        }
+        corrupt
+    }
+    // tilesets with special tiles keep them out of the walls.
+    let plain_rock = |tile: u8| match tile_properties[tile as usize] {
+        TileProperty::Solid => tile,
+        _ => SOLID_TILE,
+    };
+
+    // left and right walls
+    for row in 1..chunk.bound.height - 1 as usize {
+        let corrupt_material: u8 =
+            plain_rock(corrupt_materials[(rng.next_for_worldgen() % corrupt_materials.len() as u64) as usize]);
+        let left_material = get_material(7, corrupt_material, CORRUPT_CHANCE, rng);
+        let right_material = get_material(3, corrupt_material, CORRUPT_CHANCE, rng);
+
+        chunk.set_tile(0, row, left_material);
+        chunk.set_tile(chunk.bound.width as usize - 1, row, right_material);
+    }
+
+    // top and bottom walls
+    for col in 1..chunk.bound.width - 1 as usize {
+        let corrupt_material: u8 =
+            plain_rock(corrupt_materials[(rng.next_for_worldgen() % corrupt_materials.len() as u64) as usize]);
+        let top_material = get_material(1, corrupt_material, CORRUPT_CHANCE, rng);
+        let bottom_material = get_material(5, corrupt_material, CORRUPT_CHANCE, rng);
+        chunk.set_tile(col, 0, top_material);
+        chunk.set_tile(col, chunk.bound.height as usize - 1, bottom_material);
+    }
+
+    // corners
+    chunk.set_tile(0, 0, 8);
+    chunk.set_tile(chunk.bound.width as usize - 1, chunk.bound.height as usize - 1, 4);
+    chunk.set_tile(chunk.bound.width as usize - 1, 0, 2);
+    chunk.set_tile(0, chunk.bound.height as usize - 1, 6);
+
+    Some(chunk)
+}
+
+/// Clear a rectangle of tiles inside the walls of a chunk.
+fn carve(chunk: &mut MapChunk, x: usize, y: usize, w: usize, h: usize) {
+    let x_end = (x + w).min(chunk.bound.width - 1);
+    let y_end = (y + h).min(chunk.bound.height - 1);
+    for row in y.max(1)..y_end {
+        for col in x.max(1)..x_end {
+            chunk.set_tile(col, row, 0);
+        }
+    }
+}
+
+/// n copies of value, or None if we're out of memory.
+fn try_filled_vec<T: Clone>(n: usize, value: T) -> Option<Vec<T>> {
+    let mut v = Vec::new();
+    v.try_reserve_exact(n).ok()?;
+    v.resize(n, value);
+    Some(v)
+}
+
+/// Sprinkle special tiles over a finished map: one-way platforms across open space,
+/// and spikes, ice and springs set into floors. The starting chunk is left alone, and so is the
+/// whole map if its tileset can't draw them.
+pub fn place_special_tiles(map: &mut GameMap, rng: &mut GameRng) {
+    const N_TRIES_PER_CHUNK: usize = 10;
+
+    if !map.has_special_tiles() {
+        return;
+    }
+
+    fn is_open(chunk: &MapChunk, col: usize, row: usize) -> bool {
+        chunk.get_tile(col, row) == 0
+    }
+    // plain solid ground with open space right above it.
+    fn is_floor(chunk: &MapChunk, col: usize, row: usize) -> bool {
+        (1..ONE_WAY_TILE).contains(&chunk.get_tile(col, row)) && is_open(chunk, col, row - 1)
+    }
+
+    for chunk in map.chunks.iter_mut().skip(1) {
+        let (w, h) = (chunk.bound.width, chunk.bound.height);
+        let (tile, run_len) = match rng.next_for_worldgen() % 100 {
+            0..=34 => (ONE_WAY_TILE, 3 + (rng.next_for_worldgen() % 4) as usize),
+            35..=59 => (SPIKE_TILE, 1 + (rng.next_for_worldgen() % 2) as usize),
+            60..=79 => (ICE_TILE, 3 + (rng.next_for_worldgen() % 5) as usize),
+            80..=94 => (SPRING_TILE, 1),
+            _ => continue,
+        };
+        if w < run_len + 4 || h < 8 {
+            continue;
+        }
+        for _ in 0..N_TRIES_PER_CHUNK {
+            let col = 1 + (rng.next_for_worldgen() % (w - 2 - run_len) as u64) as usize;
+            let row = 2 + (rng.next_for_worldgen() % (h - 3) as u64) as usize;
+            // keep the NPC spawn pocket clear
+            if col <= 5 && row <= 5 {
+                continue;
+            }
+            let fits = (col..col + run_len).all(|c| match tile {
+                ONE_WAY_TILE => is_open(chunk, c, row - 1) && is_open(chunk, c, row) && is_open(chunk, c, row + 1),
+                _ => is_floor(chunk, c, row),
+            });
+            if fits {
+                for c in col..col + run_len {
+                    chunk.set_tile(c, row, tile);
+                }
+                break;
+            }
+        }
+    }
+}
+
+/// Hang moving platforms and crumbling bridges in the open space of large chunks.
+/// How often depends on the setting; the starting chunk is left alone.
+pub fn place_dynamic_solids(map: &mut GameMap, setting: &MapGenSetting, rng: &mut GameRng) {
+    const MIN_CHUNK_SIDE_LEN: usize = 12;
+    const N_TRIES_PER_CHUNK: usize = 10;
+    const PLATFORM_WIDTH_TILES: usize = 3;
+
+    fn is_open(chunk: &MapChunk, col: usize, row: usize) -> bool {
+        chunk.get_tile(col, row) == 0
+    }
+    // the tile and the ones right above and below it are open.
+    fn is_open_column(chunk: &MapChunk, col: usize, row: usize) -> bool {
+        is_open(chunk, col, row - 1) && is_open(chunk, col, row) && is_open(chunk, col, row + 1)
+    }
+
+    map.dynamic_solids.clear();
+    for chunk in map.chunks.iter().skip(1) {
+        let (w, h) = (chunk.bound.width, chunk.bound.height);
+        if w < MIN_CHUNK_SIDE_LEN || h < MIN_CHUNK_SIDE_LEN || map.dynamic_solids.len() >= MAX_N_DYNAMIC_SOLIDS {
+            continue;
+        }
+        if rng.next_for_worldgen() % 100 >= setting.dynamic_solid_chance as u64 {
+            continue;
+        }
+        let kind = match rng.next_for_worldgen() % 3 {
+            0 => DynamicSolidKind::MovingHorizontal,
+            1 => DynamicSolidKind::MovingVertical,
+            _ => DynamicSolidKind::Crumbling,
+        };
+        for _ in 0..N_TRIES_PER_CHUNK {
+            let col = 1 + (rng.next_for_worldgen() % (w - 2 - PLATFORM_WIDTH_TILES) as u64) as usize;
+            let row = 3 + (rng.next_for_worldgen() % (h - 6) as u64) as usize;
+            // keep the NPC spawn pocket clear
+            if col <= 5 && row <= 5 {
+                continue;
+            }
+            let tile_x = chunk.bound.x + col as i32;
+            let tile_y = chunk.bound.y + row as i32;
+            match kind {
+                DynamicSolidKind::MovingHorizontal => {
+                    // slide across as much open space as there is to the right.
+                    let run_len = (col..w - 1).take_while(|&c| is_open_column(chunk, c, row)).count();
+                    if run_len < PLATFORM_WIDTH_TILES + 3 {
+                        continue;
+                    }
+                    let range = run_len - PLATFORM_WIDTH_TILES;
+                    map.dynamic_solids.push(DynamicSolid::new_platform(kind, tile_x, tile_y, PLATFORM_WIDTH_TILES as u32, range as u32));
+                }
+                DynamicSolidKind::MovingVertical => {
+                    // drop down through open space, stopping a couple tiles above the floor
+                    // so nobody gets squished underneath.
+                    let run_len = (row..h - 1)
+                        .take_while(|&r| (col..col + PLATFORM_WIDTH_TILES).all(|c| is_open(chunk, c, r) && is_open(chunk, c, r - 1)))
+                        .count();
+                    if run_len < 6 {
+                        continue;
+                    }
+                    let range = run_len - 3;
+                    map.dynamic_solids.push(DynamicSolid::new_platform(kind, tile_x, tile_y, PLATFORM_WIDTH_TILES as u32, range as u32));
+                }
+                DynamicSolidKind::Crumbling => {
+                    // a bridge of blocks, each of which crumbles on its own.
+                    let run_len = 3 + (rng.next_for_worldgen() % 3) as usize;
+                    if col + run_len >= w - 1 || !(col..col + run_len).all(|c| is_open_column(chunk, c, row)) {
+                        continue;
+                    }
+                    for i in 0..run_len {
+                        if map.dynamic_solids.len() < MAX_N_DYNAMIC_SOLIDS {
+                            map.dynamic_solids.push(DynamicSolid::new_crumbling(tile_x + i as i32, tile_y));
+                        }
+                    }
+                }
+            }
+            break;
+        }
+    }
+}
+
+/// Reserve room for a chunk in the map's tile budget. Returns false if it won't fit.
+fn try_claim_tiles(map: &mut GameMap, tile_count: &mut u32, max_n_tiles_in_map: u32, bound: &TileAlignedBoundingBox) -> bool {
+    let n_tiles = bound.width * bound.height;
+    if *tile_count + n_tiles as u32 > max_n_tiles_in_map || !map.try_fit_chunk_into(bound.width, bound.height) {
+        return false;
+    }
+    *tile_count += n_tiles as u32;
+    map.num_tiles += n_tiles;
+    true
+}
+
+/// The original generator: attach randomly sized rectangles to random existing chunks.
+pub struct RandomRectsGenerator;
+
+impl MapGenerator for RandomRectsGenerator {
+    fn generate(self: &Self, map: &mut GameMap, setting: &MapGenSetting, max_n_tiles_in_map: u32, rng: &mut GameRng) {
+        let map_chunk_min_side_len = setting.chunk_min_side_len;
+        let map_chunk_max_side_len = setting.chunk_max_side_len;
+        let max_n_tiles_in_chunk = setting.max_n_tiles_per_chunk;
+
+        let mut current_chunk_locations: Vec<TileAlignedBoundingBox> = Vec::new();
+
+        match current_chunk_locations.try_reserve(1) {
+            Ok(_) => {
+                current_chunk_locations.push(TileAlignedBoundingBox::init(0, 0, 32, 32));
+            }
+            Err(_) => {
+                return;
+            }
+        }
+        // place the chunks randomly.
+        let mut tile_count = 0;
+
+        'generate_chunks: loop {
+            if tile_count >= max_n_tiles_in_map {
+                break 'generate_chunks;
+            }
+            // attempt to place a new chunk
+            // if in viable location, place this chunk
+            'generate_one_chunk: loop {
+                // choose a new viable chunk size
+
+                let mut chunk_wid: usize;
+                let mut chunk_hei: usize;
+                'find_place_for_chunk: loop {
+                    chunk_wid = map_chunk_min_side_len
+                        + (rng.next_for_worldgen() % (map_chunk_max_side_len - map_chunk_min_side_len) as u64) as usize;
+                    chunk_hei = map_chunk_min_side_len
+                        + (rng.next_for_worldgen() % (map_chunk_max_side_len - map_chunk_min_side_len) as u64) as usize;
+                    if chunk_hei * chunk_wid <= max_n_tiles_in_chunk {
+                        if map.try_fit_chunk_into(chunk_wid, chunk_hei) {
+                            break 'find_place_for_chunk;
+                        } else {
+                            break 'generate_chunks;
+                        }
+                    }
+                }
+
+                let r_offs_1: i32 = rng.next_for_worldgen() as i32 % map_chunk_min_side_len as i32
+                    - (map_chunk_min_side_len as f32 / 2.0) as i32;
+
+                let random_chunk_from_list_i =
+                    (rng.next_for_worldgen() % current_chunk_locations.len() as u64) as usize;
+                let vertical_stack = rng.next_for_worldgen() % 2 == 1;
+                let positive_stack = rng.next_for_worldgen() % 2 == 1;
+                let rand_bound = &current_chunk_locations[random_chunk_from_list_i];
+                let new_chunk_location: TileAlignedBoundingBox;
+
+                if vertical_stack {
+                    if positive_stack {
+                        new_chunk_location = TileAlignedBoundingBox::init(
+                            rand_bound.x + r_offs_1,
+                            rand_bound.y + rand_bound.height as i32,
+                            chunk_wid,
+                            chunk_hei,
+                        );
+                    } else {
+                        new_chunk_location = TileAlignedBoundingBox::init(
+                            rand_bound.x + r_offs_1,
+                            rand_bound.y - chunk_hei as i32,
+                            chunk_wid,
+                            chunk_hei,
+                        );
+                    }
+                } else {
+                    if positive_stack {
+                        new_chunk_location = TileAlignedBoundingBox::init(
+                            rand_bound.x + rand_bound.width as i32,
+                            rand_bound.y + r_offs_1,
+                            chunk_wid,
+                            chunk_hei,
+                        );
+                    } else {
+                        new_chunk_location = TileAlignedBoundingBox::init(
+                            rand_bound.x - chunk_wid as i32,
+                            rand_bound.y + r_offs_1,
+                            chunk_wid,
+                            chunk_hei,
+                        );
+                    }
+                }
+                let mut is_viable_spot = true;
+
+                fn shares_enough_axes_with_other_bounds(
+                    potential_bound: &TileAlignedBoundingBox,
+                    source_bound: &TileAlignedBoundingBox,
+                    side_len: usize,
+                ) -> bool {
+                    let b1: &TileAlignedBoundingBox = potential_bound;
+                    let b2: &TileAlignedBoundingBox = source_bound;
+
+                    fn do_for_one_side(
+                        b1: &TileAlignedBoundingBox,
+                        b2: &TileAlignedBoundingBox,
+                        side_len: usize,
+                    ) -> bool {
+                        if b1.y + b1.height as i32 == b2.y {
+                            if (b1.x + b1.width as i32 - b2.x).min(b2.x + b2.width as i32 - b1.x)
+                                >= side_len as i32
+                            {
+                                return true;
+                            } else {
+                                return false;
+                            }
+                        }
+
+                        if b1.x + b1.width as i32 == b2.x {
+                            if (b1.y + b1.height as i32 - b2.y).min(b2.y + b2.height as i32 - b1.y)
+                                >= side_len as i32
+                            {
+                                return true;
+                            } else {
+                                return false;
+                            }
+                        }
+                        true
+                    }
+
+                    do_for_one_side(&b1, &b2, side_len) && do_for_one_side(&b2, b1, side_len)
+                }
+
+                // ensure it shares enough adjacency with source chunk
+                if !shares_enough_axes_with_other_bounds(&rand_bound, &new_chunk_location, map_chunk_min_side_len) {
+                    is_viable_spot = false;
+                }
+
+                for other_bound in &current_chunk_locations {
+                    // if it collides with existing chunk, disallow
+                    if new_chunk_location.y + new_chunk_location.height as i32 > other_bound.y {
+                        if new_chunk_location.y < other_bound.y + other_bound.height as i32 {
+                            if new_chunk_location.x + new_chunk_location.width as i32
+                                > other_bound.x
+                            {
+                                if new_chunk_location.x < other_bound.x + other_bound.width as i32 {
+                                    is_viable_spot = false;
+                                }
+                            }
+                        }
+                    }
+                    // if it doesn't collide, but it share too little with any adjacent chunks, it's also invalid
+                    if !shares_enough_axes_with_other_bounds(&other_bound, &new_chunk_location, map_chunk_min_side_len) {
+                        is_viable_spot = false;
+                    }
+                }
+
+                if is_viable_spot {
+                    // trace(format!("pushing chunk {new_chunk_location:?}"));
+                    match current_chunk_locations.try_reserve(1) {
+                        Ok(_) => {
+                            current_chunk_locations.push(new_chunk_location);
+                            tile_count += (chunk_hei * chunk_wid) as u32;
+                            map.num_tiles += chunk_hei * chunk_wid;
+                            break 'generate_one_chunk;
+                        }
+                        Err(_) => {
+                            break 'generate_chunks;
+                        }
+                    }
+                }
+            }
+        }
+
+        for current_chunk_location in current_chunk_locations.into_iter() {
+            match build_walled_chunk(current_chunk_location, map.tile_properties, rng) {
+                Some(chunk) => map.add_chunk(chunk),
+                None => break,
+            }
+        }
+    }
+}
+
+/// Open caves: a random walk of equally sized chunks on a grid, each filled with
+/// cellular automata noise. A plus-shaped tunnel through every chunk keeps the whole cave connected.
+pub struct CaveGenerator;
+
+impl MapGenerator for CaveGenerator {
+    fn generate(self: &Self, map: &mut GameMap, setting: &MapGenSetting, max_n_tiles_in_map: u32, rng: &mut GameRng) {
+        const FILL_PERCENT: u64 = 45;
+        const SMOOTHING_PASSES: usize = 4;
+        const TUNNEL_WIDTH: usize = 4;
+        const MAX_WALK_ATTEMPTS: u32 = 2000;
+
+        // every cell is the same size so that the tunnels line up across chunk seams.
+        let side_limit = ((setting.max_n_tiles_per_chunk as f32).sqrt() as usize).min(setting.chunk_max_side_len);
+        let side = ((setting.chunk_min_side_len + setting.chunk_max_side_len) / 2).min(side_limit).clamp(12, 30);
+
+        let mut tile_count = 0;
+        let mut cells: Vec<(i32, i32)> = Vec::new();
+        let mut bounds: Vec<TileAlignedBoundingBox> = Vec::new();
+
+        let mut attempts = 0;
+        let mut next_cell = (0, 0);
+        loop {
+            let bound = TileAlignedBoundingBox::init(next_cell.0 * side as i32, next_cell.1 * side as i32, side, side);
+            if !try_claim_tiles(map, &mut tile_count, max_n_tiles_in_map, &bound) {
+                break;
+            }
+            if cells.try_reserve(1).is_err() || bounds.try_reserve(1).is_err() {
+                break;
+            }
+            cells.push(next_cell);
+            bounds.push(bound);
+
+            // walk out from a random existing cell until we find a free neighbor.
+            loop {
+                attempts += 1;
+                if attempts > MAX_WALK_ATTEMPTS {
+                    break;
+                }
+                let (cx, cy) = cells[(rng.next_for_worldgen() % cells.len() as u64) as usize];
+                let candidate = match rng.next_for_worldgen() % 4 {
+                    0 => (cx + 1, cy),
+                    1 => (cx - 1, cy),
+                    2 => (cx, cy + 1),
+                    _ => (cx, cy - 1),
+                };
+                if !cells.contains(&candidate) {
+                    next_cell = candidate;
+                    break;
+                }
+            }
+            if attempts > MAX_WALK_ATTEMPTS {
+                break;
+            }
+        }
+
+        let (mut rock, mut smoothed) = match (try_filled_vec(side * side, false), try_filled_vec(side * side, false)) {
+            (Some(rock), Some(smoothed)) => (rock, smoothed),
+            _ => return,
+        };
+        for bound in bounds.into_iter() {
+            let mut chunk = match build_walled_chunk(bound, map.tile_properties, rng) {
+                Some(c) => c,
+                None => break,
+            };
+
+            // seed the inside with noise, then smooth it out into caves.
+            for row in 0..side {
+                for col in 0..side {
+                    let on_wall = row == 0 || col == 0 || row == side - 1 || col == side - 1;
+                    rock[row * side + col] = on_wall || rng.next_for_worldgen() % 100 < FILL_PERCENT;
+                }
+            }
+            for _ in 0..SMOOTHING_PASSES {
+                for row in 1..side - 1 {
+                    for col in 1..side - 1 {
+                        let mut n_rock_neighbors = 0;
+                        for dy in 0..3 {
+                            for dx in 0..3 {
+                                if (dx, dy) != (1, 1) && rock[(row + dy - 1) * side + col + dx - 1] {
+                                    n_rock_neighbors += 1;
+                                }
+                            }
+                        }
+                        smoothed[row * side + col] = n_rock_neighbors >= 5;
+                    }
+                }
+                for row in 1..side - 1 {
+                    for col in 1..side - 1 {
+                        rock[row * side + col] = smoothed[row * side + col];
+                    }
+                }
+            }
+            for row in 1..side - 1 {
+                for col in 1..side - 1 {
+                    if rock[row * side + col] {
+                        chunk.set_tile(col, row, SOLID_TILE);
+                    }
+                }
+            }
+
+            let mid = side / 2 - TUNNEL_WIDTH / 2;
+            carve(&mut chunk, 1, mid, side, TUNNEL_WIDTH);
+            carve(&mut chunk, mid, 1, TUNNEL_WIDTH, side);
+            // NPC spawn pocket, dug down to the tunnel so nobody gets walled in.
+            carve(&mut chunk, 1, 1, 5, mid);
+
+            map.add_chunk(chunk);
+        }
+    }
+}
+
+/// A vertical climb: a shaft of chunks stacked upward from the start, with ledges to jump between.
+pub struct TowerGenerator;
+
+impl MapGenerator for TowerGenerator {
+    fn generate(self: &Self, map: &mut GameMap, setting: &MapGenSetting, max_n_tiles_in_map: u32, rng: &mut GameRng) {
+        const LEDGE_SPACING: usize = 4;
+        const MIN_FLOOR_HEIGHT: usize = 10;
+
+        // narrow enough that a floor of MIN_FLOOR_HEIGHT still fits in a chunk's tile budget.
+        let width = setting.chunk_max_side_len.min(setting.max_n_tiles_per_chunk / MIN_FLOOR_HEIGHT).clamp(8, 20);
+        let max_sway = (width / 4) as u64;
+
+        let mut tile_count = 0;
+        let mut bounds: Vec<TileAlignedBoundingBox> = Vec::new();
+        let mut x = 0;
+        let mut y = 0;
+        loop {
+            let height = (setting.chunk_min_side_len
+                + (rng.next_for_worldgen() % (setting.chunk_max_side_len - setting.chunk_min_side_len + 1) as u64) as usize)
+                .min(setting.max_n_tiles_per_chunk / width)
+                .max(MIN_FLOOR_HEIGHT);
+
+            if bounds.len() > 0 {
+                // sway the shaft a bit, but keep enough overlap to climb through.
+                x += (rng.next_for_worldgen() % (2 * max_sway + 1)) as i32 - max_sway as i32;
+                y -= height as i32;
+            }
+            let bound = TileAlignedBoundingBox::init(x, y, width, height);
+            if !try_claim_tiles(map, &mut tile_count, max_n_tiles_in_map, &bound) {
+                break;
+            }
+            match bounds.try_reserve(1) {
+                Ok(_) => bounds.push(bound),
+                Err(_) => break,
+            }
+        }
+
+        for (i, bound) in bounds.into_iter().enumerate() {
+            let mut chunk = match build_walled_chunk(bound, map.tile_properties, rng) {
+                Some(c) => c,
+                None => break,
+            };
+
+            // alternate ledges off the left and right walls, from the floor up.
+            let ledge_len = (width - 2) / 2;
+            let mut from_left = (i as u64 + rng.next_for_worldgen()) % 2 == 0;
+            let mut row = chunk.bound.height - 1 - LEDGE_SPACING;
+            while row > LEDGE_SPACING {
+                for col in 0..ledge_len {
+                    let x = match from_left {
+                        true => 1 + col,
+                        false => width - 2 - col,
+                    };
+                    chunk.set_tile(x, row, SOLID_TILE);
+                }
+                from_left = !from_left;
+                row -= LEDGE_SPACING;
+            }
+
+            map.add_chunk(chunk);
+        }
+    }
+}
+
+/// Rooms and corridors: split a big rectangle into rooms with a binary space partition,
+/// leaving a gap at every split that a corridor chunk bridges.
+pub struct BspRoomsGenerator;
+
+const BSP_CORRIDOR_LEN: usize = 6;
+const BSP_CORRIDOR_THICKNESS: usize = 6;
+
+impl BspRoomsGenerator {
+    /// Split `region` into rooms, appending them to `rooms` and their connecting corridors to `corridors`.
+    /// None if we ran out of memory partway, leaving whatever was appended so far.
+    fn split(region: TileAlignedBoundingBox, depth: u32, setting: &MapGenSetting, rooms: &mut Vec<TileAlignedBoundingBox>, corridors: &mut Vec<TileAlignedBoundingBox>, rng: &mut GameRng) -> Option<()> {
+        // rooms have to be big enough that two neighbors always share room for a corridor.
+        let min_side = setting.chunk_min_side_len.max(BSP_CORRIDOR_THICKNESS + 2);
+        let max_side = setting.chunk_max_side_len.max(2 * min_side + BSP_CORRIDOR_LEN);
+
+        let can_split_x = region.width >= 2 * min_side + BSP_CORRIDOR_LEN;
+        let can_split_y = region.height >= 2 * min_side + BSP_CORRIDOR_LEN;
+        // always split at least once, since NPCs can't spawn in the starting room.
+        let small_enough = depth > 0
+            && region.width <= max_side
+            && region.height <= max_side
+            && region.width * region.height <= setting.max_n_tiles_per_chunk;
+
+        if small_enough || !(can_split_x || can_split_y) {
+            rooms.try_reserve(1).ok()?;
+            rooms.push(region);
+            return Some(());
+        }
+
+        let split_x = match (can_split_x, can_split_y) {
+            (true, false) => true,
+            (false, true) => false,
+            _ => region.width >= region.height,
+        };
+
+        let len = match split_x {
+            true => region.width,
+            false => region.height,
+        };
+        let first_len = min_side + (rng.next_for_worldgen() % (len - 2 * min_side - BSP_CORRIDOR_LEN + 1) as u64) as usize;
+        let second_start = (first_len + BSP_CORRIDOR_LEN) as i32;
+
+        let (first, second) = match split_x {
+            true => (
+                TileAlignedBoundingBox::init(region.x, region.y, first_len, region.height),
+                TileAlignedBoundingBox::init(region.x + second_start, region.y, len - first_len - BSP_CORRIDOR_LEN, region.height),
+            ),
+            false => (
+                TileAlignedBoundingBox::init(region.x, region.y, region.width, first_len),
+                TileAlignedBoundingBox::init(region.x, region.y + second_start, region.width, len - first_len - BSP_CORRIDOR_LEN),
+            ),
+        };
+
+        let first_rooms_start = rooms.len();
+        Self::split(first, depth + 1, setting, rooms, corridors, rng)?;
+        let second_rooms_start = rooms.len();
+        Self::split(second, depth + 1, setting, rooms, corridors, rng)?;
+
+        // bridge the gap between the first room that touches it on each side. Both of those start
+        // at the top (or left) of the region, so they always overlap by at least min_side.
+        let gap_start = match split_x {
+            true => region.x + first_len as i32,
+            false => region.y + first_len as i32,
+        };
+        let touches_gap = |r: &&TileAlignedBoundingBox, edge: i32, before: bool| match (split_x, before) {
+            (true, true) => r.x + r.width as i32 == edge,
+            (true, false) => r.x == edge,
+            (false, true) => r.y + r.height as i32 == edge,
+            (false, false) => r.y == edge,
+        };
+        let a = rooms[first_rooms_start..second_rooms_start].iter().find(|r| touches_gap(r, gap_start, true));
+        let b = rooms[second_rooms_start..].iter().find(|r| touches_gap(r, gap_start + BSP_CORRIDOR_LEN as i32, false));
+
+        if let (Some(a), Some(b)) = (a, b) {
+            let (lo, hi) = match split_x {
+                true => (a.y.max(b.y), (a.y + a.height as i32).min(b.y + b.height as i32)),
+                false => (a.x.max(b.x), (a.x + a.width as i32).min(b.x + b.width as i32)),
+            };
+            // keep off of the room corners, so the fused opening is the full corridor width.
+            let slack = hi - lo - BSP_CORRIDOR_THICKNESS as i32 - 2;
+            if slack >= 0 {
+                let along = lo + 1 + (rng.next_for_worldgen() % (slack as u64 + 1)) as i32;
+                corridors.try_reserve(1).ok()?;
+                corridors.push(match split_x {
+                    true => TileAlignedBoundingBox::init(gap_start, along, BSP_CORRIDOR_LEN, BSP_CORRIDOR_THICKNESS),
+                    false => TileAlignedBoundingBox::init(along, gap_start, BSP_CORRIDOR_THICKNESS, BSP_CORRIDOR_LEN),
+                });
+            }
+        }
+        Some(())
+    }
+}
+
+impl MapGenerator for BspRoomsGenerator {
+    fn generate(self: &Self, map: &mut GameMap, setting: &MapGenSetting, max_n_tiles_in_map: u32, rng: &mut GameRng) {
+        // rooms and corridors never overlap, so they all fit in the budget if the region does.
+        let side = (max_n_tiles_in_map as f32).sqrt() as usize;
+        let aspect_pct = 70 + (rng.next_for_worldgen() % 61) as usize;
+        let min_region_side = 2 * setting.chunk_min_side_len.max(BSP_CORRIDOR_THICKNESS + 2) + BSP_CORRIDOR_LEN;
+        let region = TileAlignedBoundingBox::init(
+            0,
+            0,
+            (side * aspect_pct / 100).max(min_region_side),
+            side * 100 / aspect_pct,
+        );
+
+        let mut rooms: Vec<TileAlignedBoundingBox> = Vec::new();
+        let mut corridors: Vec<TileAlignedBoundingBox> = Vec::new();
+        // out of memory partway, build what fit, as the other generators do.
+        let _ = Self::split(region, 0, setting, &mut rooms, &mut corridors, rng);
+
+        // the room in the corner at (0, 0) is always first, so the players start there.
+        let mut tile_count = 0;
+        for bound in rooms.into_iter().chain(corridors.into_iter()) {
+            if !try_claim_tiles(map, &mut tile_count, max_n_tiles_in_map, &bound) {
+                continue;
+            }
+            match build_walled_chunk(bound, map.tile_properties, rng) {
+                Some(chunk) => map.add_chunk(chunk),
+                None => break,
+            }
+        }
+    }
+}
+
+/// The tutorial's map, laid out in tutorial.rs. Only the wall materials come from the rng.
+pub struct TutorialGenerator;
+
+impl MapGenerator for TutorialGenerator {
+    fn generate(self: &Self, map: &mut GameMap, _setting: &MapGenSetting, _max_n_tiles_in_map: u32, rng: &mut GameRng) {
+        let mut tile_count = 0;
+        for (i, (x, y, w, h)) in TUTORIAL_CHUNKS.into_iter().enumerate() {
+            let bound = TileAlignedBoundingBox::init(x, y, w, h);
+            // it's the same size whatever level it's on.
+            if !try_claim_tiles(map, &mut tile_count, MAX_N_TILES_IN_WHOLE_MAP as u32, &bound) {
+                break;
+            }
+            let mut chunk = match build_walled_chunk(bound, map.tile_properties, rng) {
+                Some(c) => c,
+                None => break,
+            };
+            if i == 0 {
+                for (sx, sy, sw, sh) in TUTORIAL_SOLIDS {
+                    for row in sy..sy + sh {
+                        for col in sx..sx + sw {
+                            chunk.set_tile(col, row, SOLID_TILE);
+                        }
+                    }
+                }
+            }
+            map.add_chunk(chunk);
+        }
+    }
+}
@@ -2,6 +2,7 @@ use super::game_constants::{TILE_WIDTH_PX, TILE_HEIGHT_PX};
 
 
 
+#[derive(Hash)]
 pub struct TileAlignedBoundingBox {
     pub x: i32,
     pub y: i32,
@@ -15,6 +16,7 @@ impl TileAlignedBoundingBox {
     }
 }
 
+#[derive(Hash)]
 pub struct MapChunk {
     pub tiles: Vec<u8>,
     pub bound: TileAlignedBoundingBox
@@ -0,0 +1,223 @@
+// A small UI layer for the menus and modals. A screen is described as a list of rows, top to bottom,
+// each some text with maybe a widget after it. Rows with an id can take the focus: up and down move it
+// between them, left and right change the focused widget, and button 1 presses it.
+//
+// A screen's menu is built once, when the screen opens, and kept in its GameMode next to the focus.
+// Widgets keep their own values: update() changes them in place, and tells the screen so it can change
+// its own state (the options, the settings...) to match. Anything else on a screen that changes while
+// it's up (a row that comes and goes, who's joined) is set on the rows there and then.
+//
+// lib.rs draws them, with the same modal styles as everything else.
+
+use core::hash::{Hash, Hasher};
+
+use crate::{
+    multiplatform_defs::{BUTTON_1, BUTTON_DOWN, BUTTON_LEFT, BUTTON_RIGHT, BUTTON_UP},
+    spritesheet::PresetSprites,
+};
+
+pub enum Widget {
+    // just the text.
+    Label,
+    // does something when button 1 is pressed on it.
+    Button,
+    Toggle(bool),
+    // a number, stepped through from min to max. text is the value written out, kept to draw.
+    Spinner { value: u32, min: u32, max: u32, text: String },
+    // one of some choices, cycled through.
+    List { choices: &'static [&'static str], selected: usize },
+}
+
+impl Widget {
+    /// What's drawn after the row's text, if anything.
+    pub fn value_text(self: &Self) -> Option<&str> {
+        match self {
+            Widget::Label | Widget::Button => None,
+            Widget::Toggle(on) => Some(match on {
+                true => "on",
+                false => "off",
+            }),
+            Widget::Spinner { text, .. } => Some(text),
+            Widget::List { choices, selected } => choices.get(*selected).copied(),
+        }
+    }
+
+    /// Set a toggle (0 or 1), spinner or list (the choice's index), like a MenuEvent::Changed says.
+    pub fn set_value(self: &mut Self, new_value: u32) {
+        match self {
+            Widget::Label | Widget::Button => {},
+            Widget::Toggle(on) => *on = new_value != 0,
+            Widget::Spinner { value, text, .. } => {
+                *value = new_value;
+                *text = format!["{}", new_value];
+            }
+            Widget::List { selected, .. } => *selected = new_value as usize,
+        }
+    }
+}
+
+pub struct MenuRow<Id> {
+    // Some if the row can take the focus.
+    pub id: Option<Id>,
+    pub text: String,
+    pub widget: Widget,
+    // drawn before the text, left to right.
+    pub icons: Vec<PresetSprites>,
+    pub centered: bool,
+    // only drawn while the menu's blink is on.
+    pub blinks: bool,
+}
+
+impl<Id> MenuRow<Id> {
+    fn new(id: Option<Id>, text: &str, widget: Widget) -> MenuRow<Id> {
+        MenuRow {
+            id,
+            text: text.to_string(),
+            widget,
+            icons: Vec::new(),
+            centered: false,
+            blinks: false,
+        }
+    }
+
+    pub fn label(text: &str) -> MenuRow<Id> {
+        MenuRow::new(None, text, Widget::Label)
+    }
+
+    pub fn title(text: &str) -> MenuRow<Id> {
+        MenuRow { centered: true, ..MenuRow::label(text) }
+    }
+
+    /// A title that blinks, for news like "Clear!".
+    pub fn blinking_title(text: &str) -> MenuRow<Id> {
+        MenuRow { blinks: true, ..MenuRow::title(text) }
+    }
+
+    pub fn icon_label(icon: PresetSprites, text: &str) -> MenuRow<Id> {
+        MenuRow { icons: vec![icon], ..MenuRow::label(text) }
+    }
+
+    pub fn button(id: Id, text: &str) -> MenuRow<Id> {
+        MenuRow::new(Some(id), text, Widget::Button)
+    }
+
+    pub fn toggle(id: Id, text: &str, on: bool) -> MenuRow<Id> {
+        MenuRow::new(Some(id), text, Widget::Toggle(on))
+    }
+
+    pub fn spinner(id: Id, text: &str, value: u32, min: u32, max: u32) -> MenuRow<Id> {
+        MenuRow::new(Some(id), text, Widget::Spinner { value, min, max, text: format!["{}", value] })
+    }
+
+    pub fn list(id: Id, text: &str, choices: &'static [&'static str], selected: usize) -> MenuRow<Id> {
+        MenuRow::new(Some(id), text, Widget::List { choices, selected })
+    }
+
+    /// Swap the icons for these, if they're any different.
+    pub fn set_icons(self: &mut Self, icons: impl Iterator<Item = PresetSprites> + Clone) {
+        if !self.icons.iter().copied().eq(icons.clone()) {
+            self.icons.clear();
+            self.icons.extend(icons);
+        }
+    }
+}
+
+/// What happened to a menu this frame.
+pub enum MenuEvent<Id> {
+    Nothing,
+    // a toggle (0 or 1), spinner (its value) or list (the choice's index) was changed.
+    Changed(Id, u32),
+    // button 1 on a button.
+    Pressed(Id),
+}
+
+pub struct Menu<Id> {
+    pub rows: Vec<MenuRow<Id>>,
+    // all from the top left of wherever the menu's drawn.
+    pub margin_x: i32,
+    pub first_row_y: i32,
+    pub row_height: i32,
+    // where widget values go. Their text goes at margin_x.
+    pub value_x: i32,
+}
+
+impl<Id: Copy + PartialEq> Menu<Id> {
+    pub fn new(rows: Vec<MenuRow<Id>>) -> Menu<Id> {
+        Menu {
+            rows,
+            margin_x: 8,
+            first_row_y: 8,
+            row_height: 12,
+            value_x: 80,
+        }
+    }
+
+    fn focusable_ids(self: &Self) -> impl Iterator<Item = Id> + '_ {
+        self.rows.iter().filter_map(|row| row.id)
+    }
+
+    /// Move the focus, and change or press whatever has it, from player 1's buttons.
+    /// A focus that isn't on this menu (say, its row was hidden) moves to the first row that can take it.
+    pub fn update(self: &mut Self, focus: &mut Id, btns_pressed: u8) -> MenuEvent<Id> {
+        let n_ids = self.focusable_ids().count();
+        if n_ids == 0 {
+            return MenuEvent::Nothing;
+        }
+        let mut i = self.focusable_ids().position(|id| id == *focus).unwrap_or(0);
+        if btns_pressed & BUTTON_DOWN != 0 {
+            i = (i + 1).min(n_ids - 1);
+        }
+        if btns_pressed & BUTTON_UP != 0 {
+            i = i.saturating_sub(1);
+        }
+        let Some(id) = self.focusable_ids().nth(i) else {
+            return MenuEvent::Nothing;
+        };
+        *focus = id;
+
+        let Some(row) = self.rows.iter_mut().find(|row| row.id == Some(id)) else {
+            return MenuEvent::Nothing;
+        };
+        let left = btns_pressed & BUTTON_LEFT != 0;
+        let right = btns_pressed & BUTTON_RIGHT != 0;
+        let event = match &row.widget {
+            Widget::Label => MenuEvent::Nothing,
+            Widget::Button if btns_pressed & BUTTON_1 != 0 => MenuEvent::Pressed(*focus),
+            Widget::Button => MenuEvent::Nothing,
+            Widget::Toggle(on) if left || right => MenuEvent::Changed(*focus, !on as u32),
+            Widget::Spinner { value, min, max, .. } if left || right => {
+                let stepped = match right {
+                    true => value.saturating_add(1).min(*max),
+                    false => value.saturating_sub(1).max(*min),
+                };
+                match stepped == *value {
+                    true => MenuEvent::Nothing,
+                    false => MenuEvent::Changed(*focus, stepped),
+                }
+            }
+            Widget::List { choices, selected } if (left || right) && !choices.is_empty() => {
+                let n = choices.len();
+                let next = match right {
+                    true => (selected + 1) % n,
+                    false => (selected + n - 1) % n,
+                };
+                MenuEvent::Changed(*focus, next as u32)
+            }
+            _ => MenuEvent::Nothing,
+        };
+        if let MenuEvent::Changed(_, value) = event {
+            row.widget.set_value(value);
+        }
+        event
+    }
+
+    /// Where row i goes, from the top of the menu.
+    pub fn row_y(self: &Self, row_i: usize) -> i32 {
+        self.first_row_y + row_i as i32 * self.row_height
+    }
+}
+
+// a menu only shows state that's kept, and hashed, somewhere else.
+impl<Id> Hash for Menu<Id> {
+    fn hash<H: Hasher>(&self, _state: &mut H) {}
+}
@@ -1,55 +1,141 @@
+use core::hash::{Hash, Hasher};
+
 use super::collision::AbsoluteBoundingBox;
+use super::menu_widgets::Menu;
 
+#[derive(Hash)]
 pub enum MenuTypes {
     StartGameMessage,
+    TutorialDone,
     StartLevel,
-    // Options,
     WonLevel,
     Done,
-    WonGame
+    WonGame,
+    Paused(PauseMenuFocuses),
+}
+
+#[derive(Hash, Clone, Copy, PartialEq, Eq)]
+pub enum PauseMenuFocuses {
+    Resume,
+    RestartLevel,
+    QuitToTitle,
+    Options,
 }
 
 pub struct Modal {
     pub timer: u32,
     pub target_position: AbsoluteBoundingBox<i32, u32>,
     pub actual_position: AbsoluteBoundingBox<f32, f32>,
-    pub menu_type: MenuTypes
+    pub menu_type: MenuTypes,
+    // what's written on it, built when it comes up. Only the pause menu has anything to pick.
+    pub menu: Menu<PauseMenuFocuses>,
 }
 
 impl Modal {
-    pub fn new(target_position: AbsoluteBoundingBox<i32, u32>, menu_type: MenuTypes) -> Modal {
+    pub fn new(target_position: AbsoluteBoundingBox<i32, u32>, menu_type: MenuTypes, menu: Menu<PauseMenuFocuses>) -> Modal {
         Modal {
             timer: 0,
             target_position: target_position,
             actual_position: AbsoluteBoundingBox{
                 x: 0.0, y: 0.0, width: 1.0, height: 1.0
             },
-            menu_type
+            menu_type,
+            menu,
         }
     }
 }
 
+// actual_position only eases the box toward where it's drawn.
+impl Hash for Modal {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.timer.hash(state);
+        self.target_position.hash(state);
+        self.menu_type.hash(state);
+    }
+}
+
+#[derive(Hash)]
 pub enum NormalPlayModes {
     MainGameplay,
     // hover modal is a text, 
     HoverModal(Modal)
 }
 
+#[derive(Hash, Clone, Copy, PartialEq, Eq)]
 pub enum SelectMenuFocuses {
-    // Difficulty,
+    Difficulty,
+    CharacterSelect,
     RunType,
-    // CharacterSelect,
-    // StartGameBtn
+    // only there in seed mode.
+    Seed,
+    Start,
+    Options,
+    Achievements,
 }
 
+#[derive(Hash)]
 pub struct SelectSetup{
-    pub current_selection: SelectMenuFocuses
+    pub current_selection: SelectMenuFocuses,
+    pub menu: Menu<SelectMenuFocuses>,
+}
+
+#[derive(Hash, Clone, Copy, PartialEq, Eq)]
+pub enum OptionsMenuFocuses {
+    MusicVolume,
+    SfxVolume,
+    Palette,
+    SwapButtons,
+    ScreenScale,
+    TouchOverlay,
+    Tutorial,
+}
+
+/// Where leaving the options screen goes back to.
+#[derive(Hash)]
+pub enum OptionsReturnTo {
+    StartScreen,
+    SelectScreen,
+    // the pause menu, with the level still frozen underneath.
+    Pause,
+}
+
+#[derive(Hash)]
+pub struct OptionsSetup {
+    pub current_selection: OptionsMenuFocuses,
+    pub return_to: OptionsReturnTo,
+    pub menu: Menu<OptionsMenuFocuses>,
 }
 
+#[derive(Hash)]
 pub enum GameMode {
     StartScreen,
     NormalPlay(NormalPlayModes),
-    SelectScreen(SelectSetup)
+    SelectScreen(SelectSetup),
+    OptionsScreen(OptionsSetup),
+    AchievementsScreen(Menu<()>),
+    // the numbers from the run that just ended, a page at a time.
+    RunSummary(RunSummaryPage, Menu<()>),
+}
+
+#[derive(Hash, Clone, Copy, PartialEq, Eq)]
+pub enum RunSummaryPage {
+    Moves,
+    Cards,
+    Kitties,
+    Levels,
+}
+
+impl RunSummaryPage {
+    pub const ALL: [RunSummaryPage; 4] = [RunSummaryPage::Moves, RunSummaryPage::Cards, RunSummaryPage::Kitties, RunSummaryPage::Levels];
+
+    pub const fn name(self: &Self) -> &'static str {
+        match self {
+            RunSummaryPage::Moves => "Moves",
+            RunSummaryPage::Cards => "Cards",
+            RunSummaryPage::Kitties => "Kitties",
+            RunSummaryPage::Levels => "Levels",
+        }
+    }
 }
 
 
@@ -1,4 +1,6 @@
-// use crate::wasm4::*;
+use crate::multiplatform_defs::{ToneChannel, ToneFunc};
+
+use super::options::MAX_VOLUME;
 
 pub struct Song {
     pub name: &'static str,
@@ -117,8 +119,19 @@ pub const SONGS: [Song; 10] = [
 ];
 
 
-pub fn play_bgm(timer: u32, song: &Song) {
-        
+// how loud a tone is at full volume, out of tone()'s 100.
+const MUSIC_LOUDNESS: u32 = 20;
+const SOUND_EFFECT_LOUDNESS: u32 = 30;
+
+fn scale_volume(loudness: u32, volume: u8) -> u32 {
+    loudness * volume as u32 / MAX_VOLUME as u32
+}
+
+/// volume is out of MAX_VOLUME, from the options.
+pub fn play_bgm(timer: u32, song: &Song, volume: u8, tone: &mut ToneFunc) {
+    if volume == 0 {
+        return;
+    }
 
     let freq1: usize = (timer as usize / song.f1_pitchchange_timer as usize) % song.scale.len();
     let freq2: usize = (timer as usize / song.f2_pitchchange_timer as usize) % song.scale.len();
@@ -127,9 +140,41 @@ pub fn play_bgm(timer: u32, song: &Song) {
     let time_signature_numerator: u32 = song.time_signature.0 as u32*song.measure_length as u32;
     let time_signature_denominator: u32 = song.time_signature.1 as u32*song.measure_length as u32;
     if timer % time_signature_numerator == 0 {
-        // tone(song.scale[freq1] as u32, song.f1_note_duration as u32, 20, TONE_PULSE1);
+        tone(song.scale[freq1] as u32, song.f1_note_duration as u32, scale_volume(MUSIC_LOUDNESS, volume), &ToneChannel::Pulse1);
     }
     if timer % time_signature_denominator == 0 && (freq2 as i32).abs_diff(freq1 as i32) > 1 {
-        // tone(song.scale[freq2] as u32, song.f2_note_duration as u32, 20, TONE_PULSE2);
+        tone(song.scale[freq2] as u32, song.f2_note_duration as u32, scale_volume(MUSIC_LOUDNESS, volume), &ToneChannel::Pulse2);
     }
-}
\ No newline at end of file
+}
+
+/// Short blips for things that happen in a level, queued up over a frame and played at the end of it.
+#[derive(Clone, Copy)]
+pub enum SoundEffect {
+    FoundNpc,
+    Pickup,
+    UsedCard,
+    HazardHit,
+}
+
+impl SoundEffect {
+    // frequency, duration in frames, and voice.
+    fn tone(self: &Self) -> (u32, u32, ToneChannel) {
+        match self {
+            SoundEffect::FoundNpc => (880, 8, ToneChannel::Triangle),
+            SoundEffect::Pickup => (1320, 4, ToneChannel::Triangle),
+            SoundEffect::UsedCard => (660, 6, ToneChannel::Triangle),
+            SoundEffect::HazardHit => (150, 12, ToneChannel::Noise),
+        }
+    }
+}
+
+/// volume is out of MAX_VOLUME, from the options.
+pub fn play_sound_effects(effects: &[SoundEffect], volume: u8, tone: &mut ToneFunc) {
+    if volume == 0 {
+        return;
+    }
+    for effect in effects {
+        let (frequency, duration, channel) = effect.tone();
+        tone(frequency, duration, scale_volume(SOUND_EFFECT_LOUDNESS, volume), &channel);
+    }
+}
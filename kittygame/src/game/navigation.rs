@@ -0,0 +1,482 @@
+use crate::multiplatform_defs::{BUTTON_1, BUTTON_LEFT, BUTTON_RIGHT};
+
+use super::{
+    collision::get_bound_of_character,
+    entities::{Character, KittyStates},
+    game_constants::{TILE_HEIGHT_PX, TILE_WIDTH_PX},
+    game_map::GameMap,
+};
+
+// The navigation graph is a grid of cells over the whole map, each NAV_CELL_TILES x NAV_CELL_TILES tiles.
+// A cell is a node if all of its tiles are open. Edges go to the 4 neighboring cells, and are only added
+// where a kitty could actually move: walking on floors, jumping up (or across gaps) within jumping height
+// of a floor, climbing along walls, shuffling along ceilings, and falling.
+const NAV_CELL_TILES: usize = 2;
+const NAV_CELL_WIDTH_PX: i32 = (NAV_CELL_TILES * TILE_WIDTH_PX) as i32;
+const NAV_CELL_HEIGHT_PX: i32 = (NAV_CELL_TILES * TILE_HEIGHT_PX) as i32;
+
+// cells are indexed with u16s in the search queue.
+const NAV_MAX_CELLS: usize = u16::MAX as usize;
+
+// how many cells above a floor a held jump can get to, with a bit of room to spare.
+const NAV_JUMP_CELLS: u8 = 8;
+
+// how far down the path NPCs steer toward.
+const NAV_LOOKAHEAD_CELLS: u8 = 3;
+
+// the search only keeps this many cells waiting to be expanded. It's plenty for how open the maps are,
+// and if it does fill up, the cells it drops just don't get a path.
+const NAV_QUEUE_LEN: usize = 2048;
+
+// how many frames a follower can go without getting any closer to its player before it gives up.
+pub const NAV_GIVE_UP_FRAMES: u16 = 6 * 60;
+
+const NAV_LEFT: u8 = 0x1;
+const NAV_RIGHT: u8 = 0x2;
+const NAV_UP: u8 = 0x4;
+const NAV_DOWN: u8 = 0x8;
+
+// values in a flow field: which way to go from a cell to get to the player.
+const FLOW_UNREACHED: u8 = 0;
+const FLOW_ARRIVED: u8 = 0xf;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum NavEdge {
+    Walk,
+    Jump,
+    Fall,
+    WallHug,
+    Ceiling,
+}
+
+pub enum NavStep {
+    Arrived,
+    NoPath,
+    Inputs(u8),
+}
+
+/// Per-NPC bookkeeping, so that NPCs that can't find their way can fall back to teleporting.
+#[derive(Hash)]
+pub struct NavFollower {
+    // the cell the player was in, last we checked. Progress only counts toward the same goal.
+    pub goal: Option<usize>,
+    pub closest_dist: u32,
+    pub stuck_frames: u16,
+}
+
+impl NavFollower {
+    pub fn new() -> NavFollower {
+        NavFollower {
+            goal: None,
+            closest_dist: u32::MAX,
+            stuck_frames: 0,
+        }
+    }
+
+    /// Track whether the follower is making progress toward its player. When the player moves
+    /// to another cell, the follower starts over from where it is now.
+    pub fn update_progress(self: &mut Self, goal: Option<usize>, dist: u32) {
+        if goal != self.goal {
+            self.goal = goal;
+            self.closest_dist = dist;
+            self.stuck_frames = 0;
+        } else if dist < self.closest_dist {
+            self.closest_dist = dist;
+            self.stuck_frames = 0;
+        } else {
+            self.stuck_frames = self.stuck_frames.saturating_add(1);
+        }
+    }
+}
+
+/// Cells packed at half a byte each, the same way map chunks store their tiles.
+struct NibbleGrid {
+    nibbles: Vec<u8>,
+}
+
+impl NibbleGrid {
+    fn try_init(self: &mut Self, n_cells: usize) -> bool {
+        self.nibbles.clear();
+        let n_bytes = n_cells / 2 + 1;
+        match self.nibbles.try_reserve_exact(n_bytes) {
+            Ok(_) => {
+                self.nibbles.resize(n_bytes, 0);
+                true
+            }
+            Err(_) => false,
+        }
+    }
+
+    fn clear(self: &mut Self) {
+        for b in self.nibbles.iter_mut() {
+            *b = 0;
+        }
+    }
+
+    fn get(self: &Self, i: usize) -> u8 {
+        match i % 2 {
+            0 => self.nibbles[i / 2] & 0x0f,
+            _ => self.nibbles[i / 2] >> 4,
+        }
+    }
+
+    fn set(self: &mut Self, i: usize, val: u8) {
+        let b = &mut self.nibbles[i / 2];
+        match i % 2 {
+            0 => *b = (*b & 0xf0) | (val & 0x0f),
+            _ => *b = (*b & 0x0f) | (val << 4),
+        }
+    }
+}
+
+pub struct NavGraph {
+    // top left of the grid, in tiles.
+    origin_x: i32,
+    origin_y: i32,
+    // size of the grid, in cells. Zero if there's no graph for this map.
+    width: usize,
+    height: usize,
+    open: Vec<u8>,
+    edges: NibbleGrid,
+    // one flow field per player, pointing toward them. Only allocated once a player has followers.
+    flows: [NibbleGrid; 4],
+    flow_goals: [Option<usize>; 4],
+    queue: Vec<u16>,
+}
+
+impl NavGraph {
+    pub fn new() -> NavGraph {
+        NavGraph {
+            origin_x: 0,
+            origin_y: 0,
+            width: 0,
+            height: 0,
+            open: Vec::new(),
+            edges: NibbleGrid { nibbles: Vec::new() },
+            flows: [
+                NibbleGrid { nibbles: Vec::new() },
+                NibbleGrid { nibbles: Vec::new() },
+                NibbleGrid { nibbles: Vec::new() },
+                NibbleGrid { nibbles: Vec::new() },
+            ],
+            flow_goals: [None; 4],
+            queue: Vec::new(),
+        }
+    }
+
+    /// Drop the graph and give its memory back, so there's room to generate the next map.
+    pub fn clear(self: &mut Self) {
+        *self = NavGraph::new();
+    }
+
+    /// Build the graph for a freshly generated map. If the map is too big, or there isn't enough
+    /// memory, there's no graph and followers steer the old way.
+    pub fn rebuild(self: &mut Self, map: &GameMap) {
+        self.clear();
+
+        let extents = map.get_extents();
+        let width = (extents.width + NAV_CELL_TILES - 1) / NAV_CELL_TILES;
+        let height = (extents.height + NAV_CELL_TILES - 1) / NAV_CELL_TILES;
+        let n_cells = width * height;
+        if n_cells == 0 || n_cells > NAV_MAX_CELLS {
+            return;
+        }
+        if !self.edges.try_init(n_cells)
+            || self.open.try_reserve_exact(n_cells / 8 + 1).is_err()
+            || self.queue.try_reserve_exact(NAV_QUEUE_LEN).is_err()
+        {
+            self.clear();
+            return;
+        }
+        self.open.resize(n_cells / 8 + 1, 0);
+        self.origin_x = extents.x;
+        self.origin_y = extents.y;
+        self.width = width;
+        self.height = height;
+
+        // count the open tiles in each cell. Chunks don't overlap, so a cell is open if all of its tiles are.
+        for chunk in map.chunks.iter() {
+            for row in 0..chunk.bound.height {
+                for col in 0..chunk.bound.width {
+                    if chunk.get_tile(col, row) != 0 {
+                        continue;
+                    }
+                    let cx = (chunk.bound.x + col as i32 - self.origin_x) as usize / NAV_CELL_TILES;
+                    let cy = (chunk.bound.y + row as i32 - self.origin_y) as usize / NAV_CELL_TILES;
+                    let i = cy * width + cx;
+                    let n = self.edges.get(i);
+                    self.edges.set(i, (n + 1).min(0xf));
+                }
+            }
+        }
+        for i in 0..n_cells {
+            if self.edges.get(i) as usize >= NAV_CELL_TILES * NAV_CELL_TILES {
+                self.open[i / 8] |= 1 << (i % 8);
+            }
+        }
+        self.edges.clear();
+
+        for cy in 0..height as i32 {
+            for cx in 0..width as i32 {
+                if !self.is_open(cx, cy) {
+                    continue;
+                }
+                // kitties can hang onto walls and ceilings, and jump off of them. They're wider than a cell,
+                // so ceilings diagonally above count too, like right next to a hole in the ceiling.
+                let can_cling = !self.is_open(cx - 1, cy)
+                    || !self.is_open(cx + 1, cy)
+                    || !self.is_open(cx - 1, cy - 1)
+                    || !self.is_open(cx, cy - 1)
+                    || !self.is_open(cx + 1, cy - 1);
+                let height_above_floor = self.height_above_floor(cx, cy);
+                let can_reach = can_cling || height_above_floor <= NAV_JUMP_CELLS;
+
+                let mut edges = 0;
+                if can_reach && self.is_open(cx - 1, cy) {
+                    edges |= NAV_LEFT;
+                }
+                if can_reach && self.is_open(cx + 1, cy) {
+                    edges |= NAV_RIGHT;
+                }
+                if (can_cling || height_above_floor < NAV_JUMP_CELLS) && self.is_open(cx, cy - 1) {
+                    edges |= NAV_UP;
+                }
+                if self.is_open(cx, cy + 1) {
+                    edges |= NAV_DOWN;
+                }
+                self.edges.set(cy as usize * width + cx as usize, edges);
+            }
+        }
+    }
+
+    fn is_open(self: &Self, cx: i32, cy: i32) -> bool {
+        if cx < 0 || cy < 0 || cx >= self.width as i32 || cy >= self.height as i32 {
+            return false;
+        }
+        let i = cy as usize * self.width + cx as usize;
+        self.open[i / 8] & (1 << (i % 8)) != 0
+    }
+
+    /// How many open cells are under this one before hitting a floor, up to just past jumping height.
+    /// Floors that are diagonally under it count too, since a kitty is wider than a cell and can get
+    /// over the top of a wall or the edge of a ledge from there.
+    fn height_above_floor(self: &Self, cx: i32, cy: i32) -> u8 {
+        let mut h = 0;
+        while h <= NAV_JUMP_CELLS {
+            let below = cy + h as i32 + 1;
+            if !self.is_open(cx - 1, below) || !self.is_open(cx, below) || !self.is_open(cx + 1, below) {
+                break;
+            }
+            h += 1;
+        }
+        h
+    }
+
+    fn edge_kind(self: &Self, cx: i32, cy: i32, dir: u8) -> NavEdge {
+        let on_floor = !self.is_open(cx, cy + 1);
+        match dir {
+            NAV_DOWN => NavEdge::Fall,
+            NAV_UP => {
+                if !on_floor && (!self.is_open(cx - 1, cy) || !self.is_open(cx + 1, cy)) {
+                    NavEdge::WallHug
+                } else {
+                    NavEdge::Jump
+                }
+            }
+            _ => {
+                if on_floor {
+                    NavEdge::Walk
+                } else if !self.is_open(cx, cy - 1) {
+                    NavEdge::Ceiling
+                } else {
+                    NavEdge::Jump
+                }
+            }
+        }
+    }
+
+    /// The cell some point is in, or the closest open cell right next to it. Characters are a bit
+    /// bigger than cells, so they can be partly inside of cells that aren't open.
+    fn find_cell(self: &Self, x: i32, y: i32) -> Option<(i32, i32)> {
+        if self.width == 0 {
+            return None;
+        }
+        let rel_x = x - self.origin_x * TILE_WIDTH_PX as i32;
+        let rel_y = y - self.origin_y * TILE_HEIGHT_PX as i32;
+        let cx = rel_x.div_euclid(NAV_CELL_WIDTH_PX);
+        let cy = rel_y.div_euclid(NAV_CELL_HEIGHT_PX);
+        if self.is_open(cx, cy) {
+            return Some((cx, cy));
+        }
+        let mut closest = None;
+        let mut closest_dist = i32::MAX;
+        for (dx, dy) in [(0, -1), (-1, 0), (1, 0), (0, 1), (-1, -1), (1, -1), (-1, 1), (1, 1)] {
+            if !self.is_open(cx + dx, cy + dy) {
+                continue;
+            }
+            let center_x = (cx + dx) * NAV_CELL_WIDTH_PX + NAV_CELL_WIDTH_PX / 2;
+            let center_y = (cy + dy) * NAV_CELL_HEIGHT_PX + NAV_CELL_HEIGHT_PX / 2;
+            let dist = (center_x - rel_x).pow(2) + (center_y - rel_y).pow(2);
+            if dist < closest_dist {
+                closest_dist = dist;
+                closest = Some((cx + dx, cy + dy));
+            }
+        }
+        closest
+    }
+
+    /// The point of a character that the graph tracks: their feet, or their head when they're on the ceiling.
+    fn get_anchor_of_character(character: &Character) -> (i32, i32) {
+        let bound = get_bound_of_character(character);
+        let x = bound.x + bound.width as i32 / 2;
+        match character.state {
+            KittyStates::OnCeiling(_) => (x, bound.y + 1),
+            _ => (x, bound.y + bound.height as i32 - 2),
+        }
+    }
+
+    /// Point a player's flow field at wherever they are now. Only does the search when they've changed cells.
+    pub fn update_flow(self: &mut Self, player_i: usize, player: &Character) {
+        let (x, y) = NavGraph::get_anchor_of_character(player);
+        let goal = match self.find_cell(x, y) {
+            Some((cx, cy)) => cy as usize * self.width + cx as usize,
+            None => return,
+        };
+        if self.flow_goals[player_i] == Some(goal) {
+            return;
+        }
+
+        let n_cells = self.width * self.height;
+        if self.flows[player_i].nibbles.len() != n_cells / 2 + 1 && !self.flows[player_i].try_init(n_cells) {
+            self.flow_goals[player_i] = None;
+            return;
+        }
+        self.flow_goals[player_i] = Some(goal);
+
+        // breadth first search backward along the edges, from the player out to everywhere that can get to them.
+        let flow = &mut self.flows[player_i];
+        flow.clear();
+        flow.set(goal, FLOW_ARRIVED);
+        self.queue.clear();
+        self.queue.push(goal as u16);
+        let mut queue_start = 0;
+        while queue_start < self.queue.len() {
+            let i = self.queue[queue_start] as usize;
+            queue_start += 1;
+            let cx = (i % self.width) as i32;
+            let cy = (i / self.width) as i32;
+
+            // the neighbor, and the way it has to go to get to this cell.
+            for (nx, ny, dir) in [(cx + 1, cy, NAV_LEFT), (cx - 1, cy, NAV_RIGHT), (cx, cy + 1, NAV_UP), (cx, cy - 1, NAV_DOWN)] {
+                if nx < 0 || ny < 0 || nx >= self.width as i32 || ny >= self.height as i32 {
+                    continue;
+                }
+                let n = ny as usize * self.width + nx as usize;
+                if flow.get(n) != FLOW_UNREACHED || self.edges.get(n) & dir == 0 {
+                    continue;
+                }
+                flow.set(n, dir);
+                if self.queue.len() < NAV_QUEUE_LEN {
+                    self.queue.push(n as u16);
+                } else if queue_start > 0 {
+                    // make room by dropping what's already been expanded.
+                    self.queue.drain(..queue_start);
+                    queue_start = 0;
+                    self.queue.push(n as u16);
+                }
+            }
+        }
+    }
+
+    fn get_flow(self: &Self, player_i: usize, cx: i32, cy: i32) -> u8 {
+        self.flows[player_i].get(cy as usize * self.width + cx as usize)
+    }
+
+    /// Which way the wall next to a cell is, as a button to push into it.
+    fn get_wall_button(self: &Self, cx: i32, cy: i32) -> u8 {
+        if !self.is_open(cx - 1, cy) {
+            BUTTON_LEFT
+        } else if !self.is_open(cx + 1, cy) {
+            BUTTON_RIGHT
+        } else {
+            0
+        }
+    }
+
+    /// The cell a player's flow field points to, if they have one.
+    pub fn flow_goal(self: &Self, player_i: usize) -> Option<usize> {
+        self.flow_goals[player_i]
+    }
+
+    /// Work out the inputs that move an NPC along the way to the player it's following.
+    pub fn get_step(self: &Self, player_i: usize, npc: &Character) -> NavStep {
+        if self.flow_goals[player_i].is_none() {
+            return NavStep::NoPath;
+        }
+        let (x, y) = NavGraph::get_anchor_of_character(npc);
+        let (cx, cy) = match self.find_cell(x, y) {
+            Some(c) => c,
+            None => return NavStep::NoPath,
+        };
+        let first_dir = match self.get_flow(player_i, cx, cy) {
+            FLOW_UNREACHED => return NavStep::NoPath,
+            FLOW_ARRIVED => return NavStep::Arrived,
+            d => d,
+        };
+        let first_edge = self.edge_kind(cx, cy, first_dir);
+
+        // look a few cells down the path, so the NPC commits to jumps instead of jittering between cells.
+        let (mut tx, mut ty) = (cx, cy);
+        for _ in 0..NAV_LOOKAHEAD_CELLS {
+            match self.get_flow(player_i, tx, ty) {
+                NAV_LEFT => tx -= 1,
+                NAV_RIGHT => tx += 1,
+                NAV_UP => ty -= 1,
+                NAV_DOWN => ty += 1,
+                _ => break,
+            }
+        }
+        let target_x = self.origin_x * TILE_WIDTH_PX as i32 + tx * NAV_CELL_WIDTH_PX + NAV_CELL_WIDTH_PX / 2;
+        let toward_target = if x < target_x - 2 {
+            BUTTON_RIGHT
+        } else if x > target_x + 2 {
+            BUTTON_LEFT
+        } else {
+            0
+        };
+
+        let mut input = 0;
+        match (first_dir, first_edge) {
+            (NAV_UP, _) if matches!(npc.state, KittyStates::OnCeiling(_)) => {
+                // shuffle over to the opening. Dropping off the ceiling lets the NPC jump right away.
+                input |= BUTTON_1 | toward_target;
+            }
+            (NAV_UP, NavEdge::WallHug) => {
+                // jump off the wall, then steer right back into it, a bit higher up each time.
+                input |= BUTTON_1 | self.get_wall_button(cx, cy);
+            }
+            (NAV_UP, _) => input |= BUTTON_1 | toward_target,
+            (NAV_DOWN, _) => {
+                // let go of walls by pushing away from them, and of ceilings by letting go of everything.
+                match npc.state {
+                    KittyStates::HuggingWall(_) => {
+                        input |= match npc.is_facing_right {
+                            true => BUTTON_LEFT,
+                            false => BUTTON_RIGHT,
+                        }
+                    }
+                    KittyStates::OnCeiling(_) => {}
+                    _ => input |= toward_target,
+                }
+            }
+            (_, edge) => {
+                input |= toward_target;
+                // hop across gaps and up onto ledges instead of walking off or into them.
+                if edge == NavEdge::Jump || ty < cy {
+                    input |= BUTTON_1;
+                }
+            }
+        }
+        NavStep::Inputs(input)
+    }
+}
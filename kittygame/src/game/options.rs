@@ -0,0 +1,137 @@
+// What a player can set on the options screen. These are kept between sessions by the storage layer,
+// so they live apart from GameSettings, which is picked fresh on the select screen every run.
+
+use crate::{
+    multiplatform_defs::{BUTTON_1, BUTTON_2},
+    spritesheet::KITTY_SPRITESHEET_PALETTES,
+};
+
+pub const MAX_VOLUME: u8 = 10;
+// bigger than this and the game doesn't fit on most screens.
+pub const MAX_SCREEN_SCALE: u8 = 4;
+
+/// Which button jumps and which uses cards. The only remapping there is: x and z, swapped or not.
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ControlScheme {
+    // x (button 1) jumps, z (button 2) uses cards.
+    Standard,
+    Swapped,
+}
+
+impl ControlScheme {
+    /// Swap buttons 1 and 2 around if need be, so the rest of the game only sees the standard layout.
+    pub fn remap(self: &Self, buttons: u8) -> u8 {
+        match self {
+            ControlScheme::Standard => buttons,
+            ControlScheme::Swapped => {
+                let swapped = match buttons & (BUTTON_1 | BUTTON_2) {
+                    BUTTON_1 => BUTTON_2,
+                    BUTTON_2 => BUTTON_1,
+                    both_or_neither => both_or_neither,
+                };
+                (buttons & !(BUTTON_1 | BUTTON_2)) | swapped
+            }
+        }
+    }
+
+    /// The key that does what button 1 or 2 does in the standard layout, for menu hints.
+    pub fn key_name(self: &Self, button: u8) -> &'static str {
+        match (self, button) {
+            (ControlScheme::Standard, BUTTON_1) | (ControlScheme::Swapped, BUTTON_2) => "x",
+            _ => "z",
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub struct GameOptions {
+    // both 0 to MAX_VOLUME.
+    pub music_volume: u8,
+    pub sfx_volume: u8,
+    // Some palette to always use, instead of the one that goes with each level's mood.
+    pub palette_lock: Option<u8>,
+    pub controls: ControlScheme,
+    // how many screen pixels per game pixel, or 0 to fill the window. Only some platforms can scale.
+    pub screen_scale: u8,
+    // the on-screen buttons, on platforms that draw them for touch screens.
+    pub show_touch_overlay: bool,
+    // the how-to-play message at the start of the first level.
+    pub tutorial: bool,
+}
+
+impl GameOptions {
+    pub fn new() -> GameOptions {
+        GameOptions {
+            music_volume: 7,
+            sfx_volume: 7,
+            palette_lock: None,
+            controls: ControlScheme::Standard,
+            screen_scale: 0,
+            show_touch_overlay: true,
+            tutorial: true,
+        }
+    }
+
+    /// The palette to draw with, given the one that goes with the level.
+    pub fn palette_idx(self: &Self, mood_pallette_idx: usize) -> usize {
+        match self.palette_lock {
+            Some(idx) => idx as usize % KITTY_SPRITESHEET_PALETTES.len(),
+            None => mood_pallette_idx,
+        }
+    }
+
+    /// The palette lock as a pick from "follow the mood", then each palette in turn.
+    pub fn palette_choice(self: &Self) -> usize {
+        match self.palette_lock {
+            None => 0,
+            Some(idx) => idx as usize + 1,
+        }
+    }
+
+    pub fn set_palette_choice(self: &mut Self, choice: usize) {
+        self.palette_lock = match choice {
+            0 => None,
+            n => Some((n - 1).min(KITTY_SPRITESHEET_PALETTES.len() - 1) as u8),
+        };
+    }
+
+    pub const N_BYTES: usize = 7;
+
+    pub fn to_bytes(self: &Self) -> [u8; GameOptions::N_BYTES] {
+        [
+            self.music_volume,
+            self.sfx_volume,
+            match self.palette_lock {
+                None => 0,
+                Some(idx) => idx + 1,
+            },
+            match self.controls {
+                ControlScheme::Standard => 0,
+                ControlScheme::Swapped => 1,
+            },
+            self.screen_scale,
+            self.show_touch_overlay as u8,
+            self.tutorial as u8,
+        ]
+    }
+
+    /// Anything out of range is clamped, so a bad save can't break the menus.
+    pub fn from_bytes(bytes: &[u8]) -> Option<GameOptions> {
+        let bytes: &[u8; GameOptions::N_BYTES] = bytes.get(..GameOptions::N_BYTES)?.try_into().ok()?;
+        Some(GameOptions {
+            music_volume: bytes[0].min(MAX_VOLUME),
+            sfx_volume: bytes[1].min(MAX_VOLUME),
+            palette_lock: match bytes[2] {
+                0 => None,
+                n => Some((n - 1).min(KITTY_SPRITESHEET_PALETTES.len() as u8 - 1)),
+            },
+            controls: match bytes[3] {
+                1 => ControlScheme::Swapped,
+                _ => ControlScheme::Standard,
+            },
+            screen_scale: bytes[4].min(MAX_SCREEN_SCALE),
+            show_touch_overlay: bytes[5] != 0,
+            tutorial: bytes[6] != 0,
+        })
+    }
+}
@@ -0,0 +1,97 @@
+use core::hash::{Hash, Hasher};
+
+use super::{
+    ability_cards::AbilityCardTypes,
+    card_content::pickup_card,
+    collision::AbsoluteBoundingBox,
+    game_constants::{MAX_N_PICKUPS, TILE_HEIGHT_PX},
+    game_map::GameMap,
+    rng::GameRng,
+};
+
+pub const PICKUP_WIDTH_PX: u32 = 5;
+pub const PICKUP_HEIGHT_PX: u32 = 5;
+
+// what each pickup is worth
+pub const CLOCK_PICKUP_TIME: u32 = 3 * 60;
+pub const YARN_PICKUP_SCORE: u32 = 25;
+
+#[derive(Hash)]
+pub enum PickupKind {
+    // bonus time
+    Clock,
+    // bonus score
+    Yarn,
+    // rare! goes straight into the card stack.
+    Card(AbilityCardTypes),
+}
+
+pub struct Pickup {
+    pub kind: PickupKind,
+    pub x_pos: f32,
+    pub y_pos: f32,
+    pub count: u32,
+}
+
+// pickups sit still at whole pixels, so their float positions hash the same everywhere.
+impl Hash for Pickup {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.kind.hash(state);
+        self.x_pos.to_bits().hash(state);
+        self.y_pos.to_bits().hash(state);
+        self.count.hash(state);
+    }
+}
+
+impl Pickup {
+    pub fn new(kind: PickupKind, x: f32, y: f32) -> Pickup {
+        Pickup {
+            kind,
+            x_pos: x,
+            y_pos: y,
+            count: 0,
+        }
+    }
+
+    pub fn get_bound(self: &Self) -> AbsoluteBoundingBox<i32, u32> {
+        AbsoluteBoundingBox {
+            x: self.x_pos as i32,
+            y: self.y_pos as i32,
+            width: PICKUP_WIDTH_PX,
+            height: PICKUP_HEIGHT_PX,
+        }
+    }
+
+    // bob up and down a little so they stand out from the map.
+    pub fn get_bob_offset(self: &Self) -> i32 {
+        match (self.count / 20) % 4 {
+            0 => 0,
+            1 => -1,
+            2 => -2,
+            _ => -1,
+        }
+    }
+}
+
+pub fn get_n_pickups_for_difficulty(difficulty_level: u32) -> u32 {
+    (2 + difficulty_level / 4).min(MAX_N_PICKUPS as u32)
+}
+
+pub fn get_random_pickup_kind(rng: &mut GameRng) -> PickupKind {
+    match rng.next_for_worldgen() % 100 {
+        0..=39 => PickupKind::Clock, // 40 % chance
+        40..=91 => PickupKind::Yarn, // 52 % chance
+        _ => PickupKind::Card(pickup_card(rng.next_for_worldgen())), // 8 % chance
+    }
+}
+
+/// Look for an open tile sitting on some floor. Never in the origin chunk, where the players start.
+pub fn try_place_pickup(map: &GameMap, kind: PickupKind, rng: &mut GameRng) -> Option<Pickup> {
+    const N_PLACEMENT_TRIES: usize = 20;
+    let (x, y) = map.try_find_spot(N_PLACEMENT_TRIES, rng, |x, y| {
+        !map.is_solid_abs(x, y)
+            && !map.is_solid_abs(x, y - TILE_HEIGHT_PX as i32)
+            && map.is_solid_abs(x, y + TILE_HEIGHT_PX as i32)
+    })?;
+    Some(Pickup::new(kind, x as f32, y as f32))
+}
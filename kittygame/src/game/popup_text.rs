@@ -1,3 +1,5 @@
+use super::fixed::Fixed;
+
 
 pub enum PopupIcon {
     None,
@@ -7,10 +9,10 @@ pub enum PopupIcon {
 }
 
 pub struct PopupText {
-    pub x_pos: f32,
-    pub y_pos: f32,
-    pub target_x_pos: f32,
-    pub target_y_pos: f32,
+    pub x_pos: Fixed,
+    pub y_pos: Fixed,
+    pub target_x_pos: Fixed,
+    pub target_y_pos: Fixed,
     pub duration_timer: u32,
     pub text: String,
     pub icon: PopupIcon
@@ -22,9 +24,9 @@ pub struct PopTextRingbuffer {
 }
 
 impl PopTextRingbuffer {
-    pub fn add_new_popup(self: &mut Self, x: f32, y: f32, s: String, icon: PopupIcon) {
-        const POPUP_RISE_DIST: f32 = 15.0;
-        const POPUP_Y_OFFSET: f32 = -8.0;
+    pub fn add_new_popup(self: &mut Self, x: Fixed, y: Fixed, s: String, icon: PopupIcon) {
+        const POPUP_RISE_DIST: Fixed = Fixed::from_int(15);
+        const POPUP_Y_OFFSET: Fixed = Fixed::from_int(-8);
         self.texts[self.next_avail_idx as usize] = Some(PopupText {
             x_pos: x,
             y_pos: y + POPUP_Y_OFFSET,
@@ -57,7 +59,7 @@ impl PopTextRingbuffer {
 
 impl PopupText {
     pub fn update_position(self: &mut Self) {
-        const PID_P: f32 = 0.1;
+        const PID_P: Fixed = Fixed::from_f32(0.1);
 
         self.x_pos += PID_P * (self.target_x_pos - self.x_pos);
         self.y_pos += PID_P * (self.target_y_pos - self.y_pos);
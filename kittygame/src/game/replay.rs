@@ -0,0 +1,242 @@
+// The game plays out the same way every time it's given the same inputs, from the first frame on.
+// So a replay is just every frame's inputs, plus the state hash every so often, so whoever plays it
+// back can tell where their build stopped agreeing with the one that recorded it. The options change how
+// inputs play out too, so the save the game was loaded with goes at the top.
+
+use super::{
+    game_state::{Difficulty, GameSettings, RunType},
+    species::PlayableCharacter,
+};
+
+pub const REPLAY_CHECKPOINT_EVERY_N_FRAMES: u32 = 60;
+
+const REPLAY_HEADER: &str = "kittygame replay v1";
+
+/// A run of frames that all had the same inputs.
+pub struct ReplayInputs {
+    pub n_frames: u32,
+    // the camera follows the player based on the screen size, so that has to be played back too.
+    pub screen_width: u32,
+    pub screen_height: u32,
+    pub btns_pressed_this_frame: [u8; 4],
+    pub gamepads: [u8; 4],
+}
+
+/// The state hash after some frame. Frames count from 1.
+pub struct ReplayCheckpoint {
+    pub frame: u32,
+    pub state_hash: u64,
+}
+
+/// A game started from the select screen on some frame, with these settings. The inputs already
+/// decide this, but it's written down so a replay can be told apart from the others at a glance.
+pub struct ReplayRunStart {
+    pub frame: u32,
+    pub difficulty: Difficulty,
+    pub run_type: RunType,
+    // who each player picked.
+    pub characters: [PlayableCharacter; 4],
+}
+
+/// A seed mode run was paused on some frame, for this many frames. The paused time doesn't count
+/// toward the run's time, so it's written down for whoever checks the run.
+pub struct ReplayPause {
+    pub frame: u32,
+    pub n_frames: u32,
+}
+
+pub struct Replay {
+    // the save the game was loaded with, since the options change how inputs play out.
+    pub save: Option<Vec<u8>>,
+    pub n_frames: u32,
+    pub inputs: Vec<ReplayInputs>,
+    pub checkpoints: Vec<ReplayCheckpoint>,
+    pub run_starts: Vec<ReplayRunStart>,
+    pub pauses: Vec<ReplayPause>,
+}
+
+impl Replay {
+    pub fn new() -> Replay {
+        Replay {
+            save: None,
+            n_frames: 0,
+            inputs: Vec::new(),
+            checkpoints: Vec::new(),
+            run_starts: Vec::new(),
+            pauses: Vec::new(),
+        }
+    }
+
+    /// Record the inputs for the next frame.
+    pub fn record_inputs(self: &mut Self, sw: u32, sh: u32, btns_pressed_this_frame: &[u8; 4], gamepads: &[u8; 4]) {
+        self.n_frames += 1;
+        if let Some(last) = self.inputs.last_mut() {
+            if last.screen_width == sw
+                && last.screen_height == sh
+                && last.btns_pressed_this_frame == *btns_pressed_this_frame
+                && last.gamepads == *gamepads
+            {
+                last.n_frames += 1;
+                return;
+            }
+        }
+        self.inputs.push(ReplayInputs {
+            n_frames: 1,
+            screen_width: sw,
+            screen_height: sh,
+            btns_pressed_this_frame: *btns_pressed_this_frame,
+            gamepads: *gamepads,
+        });
+    }
+
+    /// Does the frame just recorded need a checkpoint?
+    pub fn needs_checkpoint(self: &Self) -> bool {
+        self.n_frames % REPLAY_CHECKPOINT_EVERY_N_FRAMES == 0
+    }
+
+    pub fn record_checkpoint(self: &mut Self, state_hash: u64) {
+        self.checkpoints.push(ReplayCheckpoint {
+            frame: self.n_frames,
+            state_hash,
+        });
+    }
+
+    /// A game is starting this frame.
+    pub fn record_run_start(self: &mut Self, settings: &GameSettings) {
+        self.run_starts.push(ReplayRunStart {
+            frame: self.n_frames,
+            difficulty: settings.difficulty,
+            run_type: settings.run_type,
+            characters: settings.characters,
+        });
+    }
+
+    /// A pause that started on frame is ending now.
+    pub fn record_pause(self: &mut Self, frame: u32, n_frames: u32) {
+        self.pauses.push(ReplayPause { frame, n_frames });
+    }
+
+    /// Every frame's inputs, in order.
+    pub fn frame_inputs(self: &Self) -> impl Iterator<Item = &ReplayInputs> + '_ {
+        self.inputs.iter().flat_map(|run| (0..run.n_frames).map(move |_| run))
+    }
+
+    /// One line per run of inputs or checkpoint, in the order they happened.
+    pub fn to_text(self: &Self) -> String {
+        let mut text = format!["{}\n", REPLAY_HEADER];
+        if let Some(save) = &self.save {
+            text += "save ";
+            for byte in save.iter() {
+                text += &format!["{:02x}", byte];
+            }
+            text += "\n";
+        }
+        let mut checkpoints = self.checkpoints.iter().peekable();
+        let mut run_starts = self.run_starts.iter().peekable();
+        let mut pauses = self.pauses.iter().peekable();
+        let mut frame = 0;
+        for run in self.inputs.iter() {
+            text += &format![
+                "input {} {}x{} {:08x} {:08x}\n",
+                run.n_frames,
+                run.screen_width,
+                run.screen_height,
+                u32::from_le_bytes(run.btns_pressed_this_frame),
+                u32::from_le_bytes(run.gamepads)
+            ];
+            frame += run.n_frames;
+            while let Some(run_start) = run_starts.next_if(|r| r.frame <= frame) {
+                text += &match run_start.run_type {
+                    RunType::Random => format!["start {} {} random", run_start.frame, run_start.difficulty.name()],
+                    RunType::Speedrun(seed) => format!["start {} {} seed {}", run_start.frame, run_start.difficulty.name(), seed],
+                    RunType::Tutorial => format!["start {} {} tutorial", run_start.frame, run_start.difficulty.name()],
+                };
+                text += " cats";
+                for character in run_start.characters.iter() {
+                    text += &format![" {}", character.name()];
+                }
+                text += "\n";
+            }
+            while let Some(pause) = pauses.next_if(|p| p.frame + p.n_frames <= frame) {
+                text += &format!["pause {} {}\n", pause.frame, pause.n_frames];
+            }
+            while let Some(checkpoint) = checkpoints.next_if(|c| c.frame <= frame) {
+                text += &format!["check {} {:016x}\n", checkpoint.frame, checkpoint.state_hash];
+            }
+        }
+        text
+    }
+
+    pub fn from_text(text: &str) -> Result<Replay, String> {
+        let mut lines = text.lines().enumerate();
+        match lines.next() {
+            Some((_, REPLAY_HEADER)) => {}
+            _ => return Err(format!["not a replay, or not a version this build can play (expected \"{}\")", REPLAY_HEADER]),
+        }
+
+        let mut replay = Replay::new();
+        for (line_i, line) in lines {
+            let bad_line = || format!["line {}: can't read \"{}\"", line_i + 1, line];
+            let words: Vec<&str> = line.split_whitespace().collect();
+            let num = |i: usize| words.get(i).and_then(|w| w.parse::<u32>().ok()).ok_or_else(bad_line);
+            let hex = |i: usize| words.get(i).and_then(|w| u64::from_str_radix(w, 16).ok()).ok_or_else(bad_line);
+            match words.first() {
+                Some(&"input") => {
+                    let n_frames = num(1)?;
+                    let (sw, sh) = words
+                        .get(2)
+                        .and_then(|w| w.split_once('x'))
+                        .and_then(|(w, h)| Some((w.parse::<u32>().ok()?, h.parse::<u32>().ok()?)))
+                        .ok_or_else(bad_line)?;
+                    replay.n_frames += n_frames;
+                    replay.inputs.push(ReplayInputs {
+                        n_frames,
+                        screen_width: sw,
+                        screen_height: sh,
+                        btns_pressed_this_frame: (hex(3)? as u32).to_le_bytes(),
+                        gamepads: (hex(4)? as u32).to_le_bytes(),
+                    });
+                }
+                Some(&"save") => {
+                    let hex_bytes = words.get(1).ok_or_else(bad_line)?;
+                    let save: Option<Vec<u8>> = (0..hex_bytes.len())
+                        .step_by(2)
+                        .map(|i| hex_bytes.get(i..i + 2).and_then(|b| u8::from_str_radix(b, 16).ok()))
+                        .collect();
+                    replay.save = Some(save.ok_or_else(bad_line)?);
+                }
+                Some(&"pause") => replay.pauses.push(ReplayPause {
+                    frame: num(1)?,
+                    n_frames: num(2)?,
+                }),
+                Some(&"check") => replay.checkpoints.push(ReplayCheckpoint {
+                    frame: num(1)?,
+                    state_hash: hex(2)?,
+                }),
+                Some(&"start") => replay.run_starts.push(ReplayRunStart {
+                    frame: num(1)?,
+                    difficulty: words.get(2).and_then(|w| Difficulty::from_name(w)).ok_or_else(bad_line)?,
+                    run_type: match words.get(3) {
+                        Some(&"random") => RunType::Random,
+                        Some(&"seed") => RunType::Speedrun(num(4)?),
+                        Some(&"tutorial") => RunType::Tutorial,
+                        _ => return Err(bad_line()),
+                    },
+                    characters: match words.iter().position(|w| *w == "cats") {
+                        Some(i) => {
+                            let mut characters = [PlayableCharacter::MainCat; 4];
+                            for (p_i, character) in characters.iter_mut().enumerate() {
+                                *character = words.get(i + 1 + p_i).and_then(|w| PlayableCharacter::from_name(w)).ok_or_else(bad_line)?;
+                            }
+                            characters
+                        }
+                        None => return Err(bad_line()),
+                    },
+                }),
+                None => {}
+                Some(_) => return Err(bad_line()),
+            }
+        }
+        Ok(replay)
+    }
+}
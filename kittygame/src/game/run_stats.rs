@@ -0,0 +1,156 @@
+// How a run went, in numbers: added up as it's played, shown page by page once it's over, and
+// written out next to its replay. None of it changes how the game plays.
+
+use crate::spritesheet::PresetSprites;
+
+use super::{
+    ability_cards::AbilityCardTypes,
+    card_content::CARD_DEFS,
+    entities::{Character, KittyStates, WarpAbility, WarpState},
+    fixed::Fixed,
+};
+
+// every kind of kitty there is to find, with the sprites it comes in. The first is its icon.
+pub const NPC_SPECIES: [(&str, &[PresetSprites]); 4] = [
+    ("Kitty", &[PresetSprites::Kitty1, PresetSprites::Kitty2, PresetSprites::Kitty3, PresetSprites::Kitty4]),
+    ("Pig", &[PresetSprites::Pig]),
+    ("Lizard", &[PresetSprites::Lizard]),
+    ("Bird", &[PresetSprites::BirdIsntReal]),
+];
+
+/// How one level went.
+pub struct LevelStats {
+    pub level: u32,
+    pub frames: u32,
+    // the lowest the countdown got.
+    pub lowest_countdown: u32,
+}
+
+/// What a player was up to before they moved this frame, to see what they did.
+pub struct MoveSnapshot {
+    x_pos: Fixed,
+    y_pos: Fixed,
+    jump_started: bool,
+    hugging_wall: bool,
+    warp_ready: bool,
+}
+
+impl MoveSnapshot {
+    pub fn of(character: &Character) -> MoveSnapshot {
+        MoveSnapshot {
+            x_pos: character.x_pos,
+            y_pos: character.y_pos,
+            jump_started: character.state == KittyStates::JumpingUp(0),
+            hugging_wall: matches!(character.state, KittyStates::HuggingWall(_)),
+            warp_ready: character.warp_ability == WarpAbility::CanWarp(WarpState::Ready),
+        }
+    }
+}
+
+pub struct RunStats {
+    pub jumps: u32,
+    pub wall_jumps: u32,
+    pub distance: Fixed,
+    pub warps: u32,
+    // by card type, in CARD_DEFS order.
+    pub cards_used: [u32; CARD_DEFS.len()],
+    // by species, in NPC_SPECIES order.
+    pub npcs_found: [u32; NPC_SPECIES.len()],
+    // every level that's over: won, lost or restarted.
+    pub levels: Vec<LevelStats>,
+    current_level: Option<LevelStats>,
+}
+
+impl RunStats {
+    pub fn new() -> RunStats {
+        RunStats {
+            jumps: 0,
+            wall_jumps: 0,
+            distance: Fixed::ZERO,
+            warps: 0,
+            cards_used: [0; CARD_DEFS.len()],
+            npcs_found: [0; NPC_SPECIES.len()],
+            levels: Vec::new(),
+            current_level: None,
+        }
+    }
+
+    /// A level is starting, or starting over.
+    pub fn start_level(self: &mut Self, level: u32, countdown: u32) {
+        self.current_level = Some(LevelStats {
+            level,
+            frames: 0,
+            lowest_countdown: countdown,
+        });
+    }
+
+    /// Another frame of the level went by.
+    pub fn tick(self: &mut Self, countdown: u32) {
+        if let Some(level) = &mut self.current_level {
+            level.frames += 1;
+            level.lowest_countdown = level.lowest_countdown.min(countdown);
+        }
+    }
+
+    /// The level was won, or time ran out.
+    pub fn end_level(self: &mut Self) {
+        if let Some(level) = self.current_level.take() {
+            self.levels.push(level);
+        }
+    }
+
+    /// A player moved from where they were in before.
+    pub fn track_move(self: &mut Self, before: &MoveSnapshot, after: &Character) {
+        // a jump starts from nothing, and heads up.
+        if after.state == KittyStates::JumpingUp(0) && !before.jump_started && after.y_vel < Fixed::ZERO {
+            self.jumps += 1;
+            if before.hugging_wall {
+                self.wall_jumps += 1;
+            }
+        }
+        // letting go of a charged warp is the only way back to charging from ready.
+        if before.warp_ready && after.warp_ability == WarpAbility::CanWarp(WarpState::Charging(0)) {
+            self.warps += 1;
+        } else {
+            self.distance += (after.x_pos - before.x_pos).abs() + (after.y_pos - before.y_pos).abs();
+        }
+    }
+
+    pub fn cards_used(self: &mut Self, card_type: AbilityCardTypes, n: u32) {
+        if let Some(i) = CARD_DEFS.iter().position(|def| def.card_type == card_type) {
+            self.cards_used[i] += n;
+        }
+    }
+
+    pub fn npc_found(self: &mut Self, sprite_type: PresetSprites) {
+        if let Some(i) = NPC_SPECIES.iter().position(|(_, sprites)| sprites.contains(&sprite_type)) {
+            self.npcs_found[i] += 1;
+        }
+    }
+
+    /// The closest calls first.
+    pub fn closest_calls(self: &Self) -> Vec<&LevelStats> {
+        let mut levels: Vec<&LevelStats> = self.levels.iter().collect();
+        levels.sort_by_key(|level| level.lowest_countdown);
+        levels
+    }
+
+    /// One stat a line, as "name value", for keeping next to a replay.
+    pub fn to_text(self: &Self) -> String {
+        let mut text = String::new();
+        text += &format!["jumps {}\n", self.jumps];
+        text += &format!["wall_jumps {}\n", self.wall_jumps];
+        text += &format!["distance_px {}\n", self.distance.to_i32()];
+        text += &format!["warps {}\n", self.warps];
+        for (def, n) in CARD_DEFS.iter().zip(self.cards_used.iter()) {
+            text += &format!["cards_used {} {}\n", def.name.replace(' ', "_"), n];
+        }
+        for ((name, _), n) in NPC_SPECIES.iter().zip(self.npcs_found.iter()) {
+            text += &format!["found {} {}\n", name, n];
+        }
+        for level in self.levels.iter() {
+            text += &format!["level {} frames {} lowest_countdown {}\n", level.level, level.frames, level.lowest_countdown];
+        }
+        text
+    }
+}
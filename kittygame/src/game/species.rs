@@ -0,0 +1,373 @@
+use crate::{
+    multiplatform_defs::{BUTTON_1, BUTTON_DOWN, BUTTON_LEFT, BUTTON_RIGHT, BUTTON_UP},
+    spritesheet::PresetSprites,
+};
+
+use super::{
+    ability_cards::AbilityCardTypes,
+    entities::{Character, KittyStates, OptionallyEnabledPlayer},
+    fixed::Fixed,
+};
+
+/// How a character accelerates, jumps and falls in update_pos.
+pub struct PhysicsProfile {
+    pub btn_accel: Fixed,
+    pub hop_v: Fixed,
+    pub h_decay: Fixed,
+    pub gravity: Fixed,
+    pub x_vel_cap: Fixed,
+    pub y_vel_cap: Fixed,
+    // grabs walls from any jump or walk, doesn't let go of ceilings when it stops,
+    // and can crawl up and down walls with up/down.
+    pub clings: bool,
+    pub climb_v: Fixed,
+    pub can_fly: bool,
+    pub kicks_up_clouds: bool,
+}
+
+/// What an NPC does with its inputs while nobody has found it yet.
+pub enum NpcBehavior {
+    Wander,
+    Hover,
+    Cling,
+    Flee,
+}
+
+pub struct SpeciesProfile {
+    pub behavior: NpcBehavior,
+    pub physics: PhysicsProfile,
+}
+
+pub const KITTY_PROFILE: SpeciesProfile = SpeciesProfile {
+    behavior: NpcBehavior::Wander,
+    physics: PhysicsProfile {
+        btn_accel: Fixed::from_f32(0.85),
+        hop_v: Fixed::from_f32(-5.0),
+        h_decay: Fixed::from_f32(0.92),
+        gravity: Fixed::from_f32(0.3),
+        x_vel_cap: Fixed::from_f32(2.0),
+        y_vel_cap: Fixed::from_f32(7.0),
+        clings: false,
+        climb_v: Fixed::from_f32(0.0),
+        can_fly: false,
+        kicks_up_clouds: true,
+    },
+};
+
+// heavy and slow, but runs away.
+pub const PIG_PROFILE: SpeciesProfile = SpeciesProfile {
+    behavior: NpcBehavior::Flee,
+    physics: PhysicsProfile {
+        btn_accel: Fixed::from_f32(0.5),
+        hop_v: Fixed::from_f32(-4.0),
+        h_decay: Fixed::from_f32(0.85),
+        gravity: Fixed::from_f32(0.4),
+        x_vel_cap: Fixed::from_f32(1.4),
+        y_vel_cap: Fixed::from_f32(8.0),
+        clings: false,
+        climb_v: Fixed::from_f32(0.0),
+        can_fly: false,
+        kicks_up_clouds: true,
+    },
+};
+
+pub const LIZARD_PROFILE: SpeciesProfile = SpeciesProfile {
+    behavior: NpcBehavior::Cling,
+    physics: PhysicsProfile {
+        btn_accel: Fixed::from_f32(0.7),
+        hop_v: Fixed::from_f32(-4.5),
+        h_decay: Fixed::from_f32(0.8),
+        gravity: Fixed::from_f32(0.3),
+        x_vel_cap: Fixed::from_f32(1.6),
+        y_vel_cap: Fixed::from_f32(7.0),
+        clings: true,
+        climb_v: Fixed::from_f32(1.0),
+        can_fly: false,
+        kicks_up_clouds: false,
+    },
+};
+
+// floaty, flaps instead of hopping.
+pub const BIRD_PROFILE: SpeciesProfile = SpeciesProfile {
+    behavior: NpcBehavior::Hover,
+    physics: PhysicsProfile {
+        btn_accel: Fixed::from_f32(0.4),
+        hop_v: Fixed::from_f32(-2.0),
+        h_decay: Fixed::from_f32(0.97),
+        gravity: Fixed::from_f32(0.12),
+        x_vel_cap: Fixed::from_f32(1.8),
+        y_vel_cap: Fixed::from_f32(3.0),
+        clings: false,
+        climb_v: Fixed::from_f32(0.0),
+        can_fly: true,
+        kicks_up_clouds: false,
+    },
+};
+
+/// Who a player can pick on the select screen.
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PlayableCharacter {
+    MainCat,
+    Kitty1,
+    Kitty2,
+    Kitty3,
+    Kitty4,
+}
+
+pub struct PlayableCharacterProfile {
+    pub character: PlayableCharacter,
+    // short enough to fit next to the character select, and one word, for replays.
+    pub name: &'static str,
+    pub sprite: PresetSprites,
+    pub species: SpeciesProfile,
+    pub starting_card: Option<AbilityCardTypes>,
+}
+
+// the order left and right go through them on the select screen.
+pub const PLAYABLE_CHARACTERS: [PlayableCharacterProfile; 5] = [
+    PlayableCharacterProfile {
+        character: PlayableCharacter::MainCat,
+        name: "Tabby",
+        sprite: PresetSprites::MainCat,
+        species: KITTY_PROFILE,
+        starting_card: None,
+    },
+    // quick on the ground, with a small hop.
+    PlayableCharacterProfile {
+        character: PlayableCharacter::Kitty1,
+        name: "Zippy",
+        sprite: PresetSprites::Kitty1,
+        species: SpeciesProfile {
+            behavior: NpcBehavior::Wander,
+            physics: PhysicsProfile {
+                btn_accel: Fixed::from_f32(1.0),
+                hop_v: Fixed::from_f32(-4.5),
+                h_decay: Fixed::from_f32(0.92),
+                gravity: Fixed::from_f32(0.3),
+                x_vel_cap: Fixed::from_f32(2.6),
+                y_vel_cap: Fixed::from_f32(7.0),
+                clings: false,
+                climb_v: Fixed::from_f32(0.0),
+                can_fly: false,
+                kicks_up_clouds: true,
+            },
+        },
+        starting_card: Some(AbilityCardTypes::Dash),
+    },
+    // jumps high, but floats down slow and is slow to turn.
+    PlayableCharacterProfile {
+        character: PlayableCharacter::Kitty2,
+        name: "Bouncy",
+        sprite: PresetSprites::Kitty2,
+        species: SpeciesProfile {
+            behavior: NpcBehavior::Wander,
+            physics: PhysicsProfile {
+                btn_accel: Fixed::from_f32(0.6),
+                hop_v: Fixed::from_f32(-5.8),
+                h_decay: Fixed::from_f32(0.95),
+                gravity: Fixed::from_f32(0.26),
+                x_vel_cap: Fixed::from_f32(1.8),
+                y_vel_cap: Fixed::from_f32(5.5),
+                clings: false,
+                climb_v: Fixed::from_f32(0.0),
+                can_fly: false,
+                kicks_up_clouds: true,
+            },
+        },
+        starting_card: Some(AbilityCardTypes::DoubleJump),
+    },
+    // slow and heavy, but starts with some extra time.
+    PlayableCharacterProfile {
+        character: PlayableCharacter::Kitty3,
+        name: "Chonk",
+        sprite: PresetSprites::Kitty3,
+        species: SpeciesProfile {
+            behavior: NpcBehavior::Wander,
+            physics: PhysicsProfile {
+                btn_accel: Fixed::from_f32(0.6),
+                hop_v: Fixed::from_f32(-4.8),
+                h_decay: Fixed::from_f32(0.88),
+                gravity: Fixed::from_f32(0.35),
+                x_vel_cap: Fixed::from_f32(1.6),
+                y_vel_cap: Fixed::from_f32(8.0),
+                clings: false,
+                climb_v: Fixed::from_f32(0.0),
+                can_fly: false,
+                kicks_up_clouds: true,
+            },
+        },
+        starting_card: Some(AbilityCardTypes::Piggy),
+    },
+    // climbs walls and ceilings like a lizard.
+    PlayableCharacterProfile {
+        character: PlayableCharacter::Kitty4,
+        name: "Sticky",
+        sprite: PresetSprites::Kitty4,
+        species: SpeciesProfile {
+            behavior: NpcBehavior::Cling,
+            physics: PhysicsProfile {
+                btn_accel: Fixed::from_f32(0.75),
+                hop_v: Fixed::from_f32(-4.5),
+                h_decay: Fixed::from_f32(0.85),
+                gravity: Fixed::from_f32(0.3),
+                x_vel_cap: Fixed::from_f32(1.8),
+                y_vel_cap: Fixed::from_f32(7.0),
+                clings: true,
+                climb_v: Fixed::from_f32(1.2),
+                can_fly: false,
+                kicks_up_clouds: true,
+            },
+        },
+        starting_card: None,
+    },
+];
+
+impl PlayableCharacter {
+    pub fn profile(self: &Self) -> &'static PlayableCharacterProfile {
+        // every character has a row.
+        PLAYABLE_CHARACTERS.iter().find(|p| p.character == *self).unwrap_or(&PLAYABLE_CHARACTERS[0])
+    }
+
+    pub fn name(self: &Self) -> &'static str {
+        self.profile().name
+    }
+
+    pub fn from_name(name: &str) -> Option<PlayableCharacter> {
+        PLAYABLE_CHARACTERS.iter().find(|p| p.name.eq_ignore_ascii_case(name)).map(|p| p.character)
+    }
+
+    /// The next or previous character on the select screen, wrapping around.
+    pub fn cycled(self: &Self, forward: bool) -> PlayableCharacter {
+        let n = PLAYABLE_CHARACTERS.len();
+        let i = PLAYABLE_CHARACTERS.iter().position(|p| p.character == *self).unwrap_or(0);
+        let next_i = match forward {
+            true => (i + 1) % n,
+            false => (i + n - 1) % n,
+        };
+        PLAYABLE_CHARACTERS[next_i].character
+    }
+}
+
+impl SpeciesProfile {
+    pub fn from_preset(sprite_type: &PresetSprites) -> &'static SpeciesProfile {
+        match sprite_type {
+            PresetSprites::Pig => &PIG_PROFILE,
+            PresetSprites::Lizard => &LIZARD_PROFILE,
+            PresetSprites::BirdIsntReal => &BIRD_PROFILE,
+            _ => &KITTY_PROFILE,
+        }
+    }
+}
+
+/// The original random walk: mostly idle, with short taps left, right or jump.
+pub fn get_random_input(rand_val: u8) -> u8 {
+    if rand_val < 20 {
+        BUTTON_LEFT
+    } else if rand_val < 40 {
+        BUTTON_RIGHT
+    } else if rand_val < 42 {
+        BUTTON_1
+    } else {
+        0x0
+    }
+}
+
+fn get_toward_facing(npc: &Character) -> u8 {
+    match npc.is_facing_right {
+        true => BUTTON_RIGHT,
+        false => BUTTON_LEFT,
+    }
+}
+
+fn get_nearest_player_offset(npc: &Character, players: &[OptionallyEnabledPlayer; 4]) -> Option<(Fixed, Fixed)> {
+    let mut nearest: Option<(Fixed, Fixed)> = None;
+    for opt_p in players.iter() {
+        if let OptionallyEnabledPlayer::Enabled(p) = opt_p {
+            let dx = p.character.x_pos - npc.x_pos;
+            let dy = p.character.y_pos - npc.y_pos;
+            let closer = match nearest {
+                None => true,
+                Some((ndx, ndy)) => dx.abs() + dy.abs() < ndx.abs() + ndy.abs(),
+            };
+            if closer {
+                nearest = Some((dx, dy));
+            }
+        }
+    }
+    nearest
+}
+
+/// Inputs for an NPC that hasn't been found yet, based on its species.
+pub fn get_wander_input(npc: &Character, players: &[OptionallyEnabledPlayer; 4], rand_val: u8) -> u8 {
+    match npc.species.behavior {
+        NpcBehavior::Wander => get_random_input(rand_val),
+        NpcBehavior::Hover => {
+            // flap whenever we start sinking, and drift around.
+            let mut input = 0;
+            if npc.y_vel > Fixed::from_f32(0.5) || rand_val < 4 {
+                input |= BUTTON_1;
+            }
+            if rand_val < 60 {
+                input |= get_toward_facing(npc);
+            } else if rand_val < 64 {
+                input |= (BUTTON_LEFT | BUTTON_RIGHT) & !get_toward_facing(npc);
+            }
+            input
+        }
+        NpcBehavior::Cling => {
+            match npc.state {
+                KittyStates::HuggingWall(_) => {
+                    // once in a while, let go.
+                    if rand_val < 2 {
+                        return (BUTTON_LEFT | BUTTON_RIGHT) & !get_toward_facing(npc);
+                    }
+                    // crawl up for a bit, then down for a bit.
+                    let climb = match (npc.count / 90) % 2 {
+                        0 => BUTTON_UP,
+                        _ => BUTTON_DOWN,
+                    };
+                    get_toward_facing(npc) | climb
+                }
+                KittyStates::OnCeiling(_) => {
+                    if rand_val < 3 {
+                        (BUTTON_LEFT | BUTTON_RIGHT) & !get_toward_facing(npc)
+                    } else if rand_val < 120 {
+                        get_toward_facing(npc)
+                    } else {
+                        0
+                    }
+                }
+                _ => {
+                    // scurry along until we find a wall to climb.
+                    if rand_val < 4 {
+                        BUTTON_1 | get_toward_facing(npc)
+                    } else if rand_val < 6 {
+                        (BUTTON_LEFT | BUTTON_RIGHT) & !get_toward_facing(npc)
+                    } else if rand_val < 150 {
+                        get_toward_facing(npc)
+                    } else {
+                        0
+                    }
+                }
+            }
+        }
+        NpcBehavior::Flee => {
+            const FLEE_X_DIST: Fixed = Fixed::from_int(64);
+            const FLEE_Y_DIST: Fixed = Fixed::from_int(40);
+            match get_nearest_player_offset(npc, players) {
+                Some((dx, dy)) if dx.abs() < FLEE_X_DIST && dy.abs() < FLEE_Y_DIST => {
+                    let mut input = match dx > Fixed::ZERO {
+                        true => BUTTON_LEFT,
+                        false => BUTTON_RIGHT,
+                    };
+                    // hop over whatever is in the way
+                    if npc.x_vel == Fixed::ZERO || rand_val < 10 {
+                        input |= BUTTON_1;
+                    }
+                    input
+                }
+                _ => get_random_input(rand_val),
+            }
+        }
+    }
+}
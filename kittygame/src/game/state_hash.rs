@@ -0,0 +1,53 @@
+use core::hash::Hasher;
+
+const FNV_OFFSET_BASIS: u64 = 0xcbf29ce484222325;
+const FNV_PRIME: u64 = 0x100000001b3;
+
+/// FNV-1a, for hashing the game state. Unlike std's hashers, it comes out the same on every
+/// platform and every Rust version, so a hash from a wasm build can be checked against a native one.
+pub struct StateHasher(u64);
+
+impl StateHasher {
+    pub fn new() -> StateHasher {
+        StateHasher(FNV_OFFSET_BASIS)
+    }
+}
+
+// integers go in little-endian, and usizes (lengths, enum discriminants) as 64 bits,
+// so 32 and 64 bit targets hash the same things to the same bytes.
+impl Hasher for StateHasher {
+    fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.0 ^= *byte as u64;
+            self.0 = self.0.wrapping_mul(FNV_PRIME);
+        }
+    }
+
+    fn write_u16(&mut self, n: u16) {
+        self.write(&n.to_le_bytes());
+    }
+
+    fn write_u32(&mut self, n: u32) {
+        self.write(&n.to_le_bytes());
+    }
+
+    fn write_u64(&mut self, n: u64) {
+        self.write(&n.to_le_bytes());
+    }
+
+    fn write_u128(&mut self, n: u128) {
+        self.write(&n.to_le_bytes());
+    }
+
+    fn write_usize(&mut self, n: usize) {
+        self.write_u64(n as u64);
+    }
+
+    fn write_isize(&mut self, n: isize) {
+        self.write_u64(n as i64 as u64);
+    }
+
+    fn finish(&self) -> u64 {
+        self.0
+    }
+}
@@ -0,0 +1,59 @@
+// Everything kept between sessions, as a handful of bytes. The game never touches storage itself:
+// platforms hand over what they saved last time with kittygame_load_save(), and store whatever
+// kittygame_take_save() gives back, however they can (wasm4 on its disk, the others in a file).
+
+use super::{achievements::Achievements, high_scores::HighScores, options::GameOptions};
+
+const SAVE_MAGIC: [u8; 2] = *b"kg";
+const SAVE_VERSION: u8 = 3;
+// from before there were high scores: options and achievements.
+const SAVE_VERSION_NO_HIGH_SCORES: u8 = 2;
+// from before there were achievements: just the options.
+const SAVE_VERSION_OPTIONS_ONLY: u8 = 1;
+
+pub struct SaveData {
+    pub options: GameOptions,
+    pub achievements: Achievements,
+    pub high_scores: HighScores,
+}
+
+impl SaveData {
+    pub fn new() -> SaveData {
+        SaveData {
+            options: GameOptions::new(),
+            achievements: Achievements::new(),
+            high_scores: HighScores::new(),
+        }
+    }
+
+    pub fn to_bytes(self: &Self) -> Vec<u8> {
+        let mut bytes = SAVE_MAGIC.to_vec();
+        bytes.push(SAVE_VERSION);
+        bytes.extend_from_slice(&self.options.to_bytes());
+        bytes.extend_from_slice(&self.achievements.to_bytes());
+        bytes.extend_from_slice(&self.high_scores.to_bytes());
+        bytes
+    }
+
+    /// None if there's nothing saved yet, or it's not something this build wrote.
+    pub fn from_bytes(bytes: &[u8]) -> Option<SaveData> {
+        match bytes {
+            [m0, m1, SAVE_VERSION, rest @ ..] if [*m0, *m1] == SAVE_MAGIC => Some(SaveData {
+                options: GameOptions::from_bytes(rest)?,
+                achievements: Achievements::from_bytes(rest.get(GameOptions::N_BYTES..)?)?,
+                high_scores: HighScores::from_bytes(rest.get(GameOptions::N_BYTES + Achievements::N_BYTES..)?)?,
+            }),
+            [m0, m1, SAVE_VERSION_NO_HIGH_SCORES, rest @ ..] if [*m0, *m1] == SAVE_MAGIC => Some(SaveData {
+                options: GameOptions::from_bytes(rest)?,
+                achievements: Achievements::from_bytes(rest.get(GameOptions::N_BYTES..)?)?,
+                high_scores: HighScores::new(),
+            }),
+            [m0, m1, SAVE_VERSION_OPTIONS_ONLY, rest @ ..] if [*m0, *m1] == SAVE_MAGIC => Some(SaveData {
+                options: GameOptions::from_bytes(rest)?,
+                achievements: Achievements::new(),
+                high_scores: HighScores::new(),
+            }),
+            _ => None,
+        }
+    }
+}
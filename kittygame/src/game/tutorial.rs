@@ -0,0 +1,142 @@
+// The tutorial level: one hand-built map, and a script of things to try on it, one at a time. Each step
+// waits for player 1 to actually do it, seen from how their KittyStates change and from the cards they
+// use, and shows a hint pinned somewhere in the level until they do.
+
+use crate::multiplatform_defs::{BUTTON_1, BUTTON_2};
+
+use super::{
+    entities::{Character, KittyStates, WarpAbility, WarpState},
+    game_constants::{TILE_HEIGHT_PX, TILE_WIDTH_PX},
+    options::ControlScheme,
+};
+
+// The map, in tiles: a long room with something to practice on along it, and a smaller room
+// off its right end for the lizard. Chunks are (x, y, width, height).
+pub const TUTORIAL_CHUNKS: [(i32, i32, usize, usize); 2] = [(0, 0, 60, 20), (60, 0, 16, 20)];
+// solid blocks in the first room, as (x, y, width, height): a step to jump up, a pillar to grab
+// onto, and a low ceiling to crawl along.
+pub const TUTORIAL_SOLIDS: [(usize, usize, usize, usize); 3] = [(16, 15, 3, 4), (28, 8, 2, 11), (40, 1, 19, 11)];
+
+// walking or crawling has to keep up this long to count.
+const WALK_FRAMES: u8 = 30;
+const CRAWL_FRAMES: u8 = 20;
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TutorialStep {
+    Walk,
+    Jump,
+    HugWall,
+    CrawlCeiling,
+    FindKitty,
+    UseCard,
+    Warp,
+    Done,
+}
+
+impl TutorialStep {
+    // in the order they're taught.
+    pub const ALL: [TutorialStep; 8] = [
+        TutorialStep::Walk,
+        TutorialStep::Jump,
+        TutorialStep::HugWall,
+        TutorialStep::CrawlCeiling,
+        TutorialStep::FindKitty,
+        TutorialStep::UseCard,
+        TutorialStep::Warp,
+        TutorialStep::Done,
+    ];
+
+    fn next(self: &Self) -> TutorialStep {
+        let i = TutorialStep::ALL.iter().position(|s| s == self).unwrap_or(0);
+        TutorialStep::ALL[(i + 1).min(TutorialStep::ALL.len() - 1)]
+    }
+
+    /// Short enough to fit over the level.
+    pub fn hint(self: &Self, controls: &ControlScheme) -> String {
+        match self {
+            TutorialStep::Walk => "<> to walk".to_string(),
+            TutorialStep::Jump => format!["{}: jump up", controls.key_name(BUTTON_1)],
+            TutorialStep::HugWall => "jump at the wall".to_string(),
+            TutorialStep::CrawlCeiling => "cling to the top".to_string(),
+            TutorialStep::FindKitty => "find the lizard!".to_string(),
+            TutorialStep::UseCard => format!["{}: use its card", controls.key_name(BUTTON_2)],
+            TutorialStep::Warp => "hold down, let go".to_string(),
+            TutorialStep::Done => String::new(),
+        }
+    }
+
+    // where the hint goes, in tiles, or None to leave it where player 1 was when the step started.
+    fn anchor_tile(self: &Self) -> Option<(i32, i32)> {
+        match self {
+            TutorialStep::Walk => Some((8, 12)),
+            TutorialStep::Jump => Some((17, 11)),
+            TutorialStep::HugWall => Some((28, 5)),
+            TutorialStep::CrawlCeiling => Some((49, 14)),
+            TutorialStep::FindKitty => Some((68, 8)),
+            TutorialStep::UseCard | TutorialStep::Warp | TutorialStep::Done => None,
+        }
+    }
+}
+
+#[derive(Hash)]
+pub struct Tutorial {
+    pub step: TutorialStep,
+    // where the current step's hint is shown, in absolute pixels.
+    pub hint_x: i32,
+    pub hint_y: i32,
+    // player 1's warp was charged last frame, to see when it's let go.
+    last_warp_ready: bool,
+    // player 1 used a card since the last update.
+    used_card: bool,
+}
+
+impl Tutorial {
+    pub fn new() -> Tutorial {
+        let mut tutorial = Tutorial {
+            step: TutorialStep::Walk,
+            hint_x: 0,
+            hint_y: 0,
+            last_warp_ready: false,
+            used_card: false,
+        };
+        tutorial.place_hint(0, 0);
+        tutorial
+    }
+
+    fn place_hint(self: &mut Self, player_x: i32, player_y: i32) {
+        (self.hint_x, self.hint_y) = match self.step.anchor_tile() {
+            Some((x, y)) => (x * TILE_WIDTH_PX as i32, y * TILE_HEIGHT_PX as i32),
+            None => (player_x, player_y - 2 * TILE_HEIGHT_PX as i32),
+        };
+    }
+
+    pub fn card_used(self: &mut Self) {
+        self.used_card = true;
+    }
+
+    /// Move on if player 1 did what the current step asks since last frame. True if they just did.
+    pub fn update(self: &mut Self, player: &Character, n_found_npcs: u32) -> bool {
+        let warp_ready = player.warp_ability == WarpAbility::CanWarp(WarpState::Ready);
+        let done = match self.step {
+            TutorialStep::Walk => matches!(player.state, KittyStates::Walking(t) if t >= WALK_FRAMES),
+            // jumping starts at 0, and only stays there the one frame.
+            TutorialStep::Jump => player.state == KittyStates::JumpingUp(0),
+            TutorialStep::HugWall => matches!(player.state, KittyStates::HuggingWall(_)),
+            TutorialStep::CrawlCeiling => matches!(player.state, KittyStates::OnCeiling(t) if t >= CRAWL_FRAMES),
+            TutorialStep::FindKitty => n_found_npcs > 0,
+            // a card was used this frame and the player can now warp, so there's a warp to try next.
+            TutorialStep::UseCard => self.used_card && player.warp_ability != WarpAbility::CannotWarp,
+            // letting go of a charged warp is the only way back to charging from ready.
+            TutorialStep::Warp => self.last_warp_ready && player.warp_ability == WarpAbility::CanWarp(WarpState::Charging(0)),
+            TutorialStep::Done => false,
+        };
+        self.last_warp_ready = warp_ready;
+        self.used_card = false;
+
+        if done {
+            self.step = self.step.next();
+            self.place_hint(player.x_pos.to_i32(), player.y_pos.to_i32());
+        }
+        done
+    }
+}
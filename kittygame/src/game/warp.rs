@@ -0,0 +1,179 @@
+use core::hash::{Hash, Hasher};
+
+use crate::multiplatform_defs::{BUTTON_LEFT, BUTTON_RIGHT};
+
+use super::{
+    fixed::Fixed,
+    game_constants::{TILE_HEIGHT_PX, TILE_WIDTH_PX},
+    game_map::GameMap,
+};
+
+// how long down has to be held before letting go warps.
+pub const WARP_CHARGE_FRAMES: u8 = 25;
+
+// where everyone starts each level.
+const ORIGIN: (i32, i32) = (10, 10);
+
+/// Where letting go of a charged warp sends a player.
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub enum WarpDestination {
+    Origin,
+    // dropped by using a lizard card when already able to warp.
+    Beacon,
+    LastFoundNpc,
+    // a chunk the player has been in this level, by id into map.chunks.
+    Chunk(usize),
+}
+
+/// The places one player can warp to this level, and which one is picked.
+pub struct WarpDestinations {
+    pub selected: WarpDestination,
+    pub beacon: Option<(Fixed, Fixed)>,
+    pub last_found_npc: Option<(Fixed, Fixed)>,
+    // in the order the player first stood in them.
+    pub discovered_chunks: Vec<usize>,
+    // left or right was held last frame, so holding one only moves the pick once.
+    cycle_held: bool,
+}
+
+// a derived hash would write the chunk ids as raw usize bytes, 4 on wasm and 8 natively,
+// so they go through write_usize one at a time instead.
+impl Hash for WarpDestinations {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.selected.hash(state);
+        self.beacon.hash(state);
+        self.last_found_npc.hash(state);
+        state.write_usize(self.discovered_chunks.len());
+        for chunk_id in self.discovered_chunks.iter() {
+            state.write_usize(*chunk_id);
+        }
+        self.cycle_held.hash(state);
+    }
+}
+
+impl WarpDestinations {
+    pub fn new() -> WarpDestinations {
+        WarpDestinations {
+            selected: WarpDestination::Origin,
+            beacon: None,
+            last_found_npc: None,
+            discovered_chunks: Vec::new(),
+            cycle_held: false,
+        }
+    }
+
+    /// Positions and chunk ids don't carry over to a new map.
+    pub fn reset_for_level(self: &mut Self) {
+        *self = WarpDestinations::new();
+    }
+
+    pub fn discover_chunk(self: &mut Self, chunk_id: usize) {
+        if !self.discovered_chunks.contains(&chunk_id) {
+            self.discovered_chunks.push(chunk_id);
+        }
+    }
+
+    // the chunks we could warp to. The origin chunk is already covered by Origin.
+    fn rooms(self: &Self) -> impl Iterator<Item = usize> + '_ {
+        self.discovered_chunks.iter().copied().filter(|id| *id != 0)
+    }
+
+    // left and right go through the start, the beacon, the last found NPC (if there are those), then the rooms.
+    fn n_fixed_options(self: &Self) -> usize {
+        1 + self.beacon.is_some() as usize + self.last_found_npc.is_some() as usize
+    }
+
+    fn option(self: &Self, i: usize) -> WarpDestination {
+        let fixed = [
+            Some(WarpDestination::Origin),
+            self.beacon.map(|_| WarpDestination::Beacon),
+            self.last_found_npc.map(|_| WarpDestination::LastFoundNpc),
+        ];
+        match fixed.into_iter().flatten().nth(i) {
+            Some(destination) => destination,
+            None => match self.rooms().nth(i - self.n_fixed_options()) {
+                Some(chunk_id) => WarpDestination::Chunk(chunk_id),
+                None => WarpDestination::Origin,
+            },
+        }
+    }
+
+    fn selected_index(self: &Self) -> usize {
+        let i = match self.selected {
+            WarpDestination::Origin => Some(0),
+            WarpDestination::Beacon => self.beacon.map(|_| 1),
+            WarpDestination::LastFoundNpc => self.last_found_npc.map(|_| self.n_fixed_options() - 1),
+            WarpDestination::Chunk(chunk_id) => self.rooms().position(|id| id == chunk_id).map(|n| self.n_fixed_options() + n),
+        };
+        i.unwrap_or(0)
+    }
+
+    /// While a warp charges, each press of left or right picks the previous or next destination.
+    pub fn cycle(self: &mut Self, input: u8) {
+        let dir = input & (BUTTON_LEFT | BUTTON_RIGHT);
+        if dir != 0 && !self.cycle_held {
+            let n_options = self.n_fixed_options() + self.rooms().count();
+            let i = self.selected_index();
+            let next_i = match dir {
+                BUTTON_LEFT => (i + n_options - 1) % n_options,
+                _ => (i + 1) % n_options,
+            };
+            self.selected = self.option(next_i);
+        }
+        self.cycle_held = dir != 0;
+    }
+
+    /// Where the picked destination is, in absolute pixels.
+    pub fn position(self: &Self, map: &GameMap) -> (Fixed, Fixed) {
+        let origin = (Fixed::from_int(ORIGIN.0), Fixed::from_int(ORIGIN.1));
+        match self.selected {
+            WarpDestination::Origin => origin,
+            WarpDestination::Beacon => self.beacon.unwrap_or(origin),
+            WarpDestination::LastFoundNpc => self.last_found_npc.unwrap_or(origin),
+            WarpDestination::Chunk(chunk_id) => match find_open_spot_in_chunk(map, chunk_id) {
+                Some((x, y)) => (Fixed::from_int(x), Fixed::from_int(y)),
+                None => origin,
+            },
+        }
+    }
+
+    /// Short enough for the HUD.
+    pub fn label(self: &Self) -> String {
+        match self.selected {
+            WarpDestination::Origin => "start".to_string(),
+            WarpDestination::Beacon => "beacon".to_string(),
+            WarpDestination::LastFoundNpc => "last found".to_string(),
+            WarpDestination::Chunk(_) => {
+                let n = self.rooms().position(|id| WarpDestination::Chunk(id) == self.selected);
+                format!["room {}", n.unwrap_or(0) + 1]
+            }
+        }
+    }
+}
+
+// the open spot standing on a floor nearest the middle of the chunk, with room for a kitty.
+fn find_open_spot_in_chunk(map: &GameMap, chunk_id: usize) -> Option<(i32, i32)> {
+    let bound = &map.chunks.get(chunk_id)?.bound;
+    let mid_x = bound.x + bound.width as i32 / 2;
+    let mid_y = bound.y + bound.height as i32 / 2;
+    let mut best: Option<((i32, i32), i32)> = None;
+    for tile_y in bound.y + 1..bound.y + bound.height as i32 - 1 {
+        for tile_x in bound.x..bound.x + bound.width as i32 - 1 {
+            let x = tile_x * TILE_WIDTH_PX as i32;
+            let y = tile_y * TILE_HEIGHT_PX as i32;
+            let open = |x: i32, y: i32| !map.is_solid_abs(x, y) && map.is_inside_abs(x, y);
+            if open(x, y)
+                && open(x + TILE_WIDTH_PX as i32, y)
+                && open(x, y - TILE_HEIGHT_PX as i32)
+                && open(x + TILE_WIDTH_PX as i32, y - TILE_HEIGHT_PX as i32)
+                && map.is_solid_abs(x, y + TILE_HEIGHT_PX as i32)
+            {
+                let dist = (tile_x - mid_x).abs() + (tile_y - mid_y).abs();
+                if best.is_none_or(|(_, best_dist)| dist < best_dist) {
+                    best = Some(((x, y - TILE_HEIGHT_PX as i32), dist));
+                }
+            }
+        }
+    }
+    best.map(|(spot, _)| spot)
+}
@@ -18,30 +18,42 @@ pub mod multiplatform_defs;
 
 use game::{
     camera::Camera,
-    collision::{check_entity_collisions, update_pos},
+    collision::{check_entity_collisions, check_hazard_collisions, check_pickup_collisions, update_pos},
+    hazards::{Hazard, HazardKind, HazardState, HAZARD_HEIGHT_PX, HAZARD_WIDTH_PX},
+    pickups::{Pickup, PickupKind},
+    dynamic_solids::{DynamicSolidKind, DynamicSolidState},
+    ability_cards::{AbilityCardTypes, AbilityCardUsageResult, CARD_HEIGHT_PX, CARD_WIDTH_PX},
+    card_content::{CARD_DEFS, DECK_RULES},
     entities::{Character, MovingEntity, KittyStates, WarpAbility, WarpState},
+    warp::{WarpDestination, WARP_CHARGE_FRAMES},
     game_constants::{
         MAX_N_NPCS, TILE_HEIGHT_PX, TILE_WIDTH_PX, X_LEFT_BOUND, X_RIGHT_BOUND, Y_LOWER_BOUND,
         Y_UPPER_BOUND,
     },
     game_state::GameState,
     menus::GameMode,
-    music::{play_bgm, SONGS}, game_map::MAP_TILESETS, cloud::Cloud,
+    music::{play_bgm, play_sound_effects, SoundEffect, SONGS}, game_map::MAP_TILESETS, cloud::Cloud,
+    replay::Replay,
+    options::{ControlScheme, GameOptions, MAX_SCREEN_SCALE, MAX_VOLUME},
+    menu_widgets::{Menu, MenuEvent, MenuRow},
+    species::PLAYABLE_CHARACTERS,
+    storage::SaveData,
 };
-use multiplatform_defs::{BlitSubFlags, BlitSubFunc, DrawColor, LineFunc, RectFunc, Spritesheet, SwitchPalletteFunc, TextStrFunc};
+use multiplatform_defs::{BlitSubFlags, BlitSubFunc, DrawColor, LineFunc, LogFunc, RectFunc, Spritesheet, SwitchPalletteFunc, TextStrFunc, ToneFunc};
 
 // use title_ss::{OUTPUT_ONLINEPNGTOOLS_WIDTH, OUTPUT_ONLINEPNGTOOLS_HEIGHT};
 
 const OUTPUT_ONLINEPNGTOOLS_WIDTH: u32 = 152;
 const OUTPUT_ONLINEPNGTOOLS_HEIGHT: u32 = 50;
 
-mod game;
+pub mod game;
 // mod title_ss;
 
 use crate::{game::{
+        fixed::Fixed,
         collision::{get_bound_of_character, AbsoluteBoundingBox},
-        entities::OptionallyEnabledPlayer,
-        menus::{Modal, NormalPlayModes, MenuTypes, SelectSetup, SelectMenuFocuses}, game_constants::{COUNTDOWN_TIMER_START, FINAL_LEVEL, INCR_VERSION, LEVELS_PER_MOOD, MAJOR_VERSION, MINOR_VERSION, START_DIFFICULTY_LEVEL}, popup_text::{PopTextRingbuffer, PopupIcon}, rng::{GameRng, Rng}, game_state::RunType,}, multiplatform_defs::{Pallette, BUTTON_1, BUTTON_2, BUTTON_LEFT, BUTTON_RIGHT}};
+        entities::{OptionallyEnabledPlayer, Player},
+        menus::{Modal, NormalPlayModes, MenuTypes, PauseMenuFocuses, SelectSetup, SelectMenuFocuses, OptionsSetup, OptionsMenuFocuses, OptionsReturnTo, RunSummaryPage}, game_constants::{DIFFICULTY_SETTINGS, FINAL_LEVEL, INCR_VERSION, LEVELS_PER_MOOD, MAJOR_VERSION, MINOR_VERSION, START_DIFFICULTY_LEVEL}, navigation::{NavFollower, NavStep, NAV_GIVE_UP_FRAMES}, species::{get_random_input, get_wander_input}, popup_text::{PopTextRingbuffer, PopupIcon}, rng::{GameRng, Rng}, game_state::{Difficulty, GameSettings, RunSeed, RunType}, tutorial::TutorialStep, achievements::{Achievement, AchievementEvent, Achievements}, run_stats::{MoveSnapshot, RunStats, NPC_SPECIES},}, multiplatform_defs::{Pallette, BUTTON_1, BUTTON_2, BUTTON_START, BUTTON_LEFT, BUTTON_RIGHT}};
 
 /// draw the tiles in the map, relative to the camera.
 fn drawmap(game_state: &GameState, blit_sub: &mut BlitSubFunc, sw: u32, sh: u32) {
@@ -64,9 +76,9 @@ fn drawmap(game_state: &GameState, blit_sub: &mut BlitSubFunc, sw: u32, sh: u32)
                         let chunk_x_offset: i32 = (TILE_WIDTH_PX) as i32 * chunk.bound.x;
                         let chunk_y_offset: i32 = (TILE_HEIGHT_PX) as i32 * chunk.bound.y;
                         let x_loc = (chunk_x_offset + col as i32 * TILE_HEIGHT_PX as i32)
-                            - camera.current_viewing_x_offset as i32;
+                            - camera.current_viewing_x_offset.to_i32();
                         let y_loc = (chunk_y_offset + row as i32 * TILE_WIDTH_PX as i32)
-                            - camera.current_viewing_y_offset as i32;
+                            - camera.current_viewing_y_offset.to_i32();
 
                         if x_loc >= 0 && x_loc < sw as i32 && y_loc > 0 && y_loc < sh as i32 {
                             blit_sub(
@@ -87,8 +99,397 @@ fn drawmap(game_state: &GameState, blit_sub: &mut BlitSubFunc, sw: u32, sh: u32)
     }
 }
 
+/// Draw the moving platforms and crumbling blocks with the current tileset.
+fn drawdynamicsolids(game_state: &GameState, blit_sub: &mut BlitSubFunc) {
+    let camera = &game_state.camera;
+    let tileset = &MAP_TILESETS[game_state.tileset_idx];
+
+    for solid in game_state.map.dynamic_solids.iter() {
+        // platforms look like the top of a chunk, crumbling blocks like the rubble in its walls.
+        let tile_i = match solid.kind {
+            DynamicSolidKind::Crumbling => tileset[10],
+            _ => tileset[1],
+        } as usize;
+        let jitter = match solid.state {
+            DynamicSolidState::Gone(_) => continue,
+            DynamicSolidState::Shaking(t) => (t as i32 / 3) % 2,
+            _ => 0,
+        };
+        if tile_i == 0 {
+            continue;
+        }
+        let frame = &game_state.background_tiles[tile_i].frames[0];
+        for col in 0..(solid.width / TILE_WIDTH_PX as u32) as i32 {
+            blit_sub(
+                Spritesheet::Main,
+                solid.x_pos + col * TILE_WIDTH_PX as i32 + jitter - camera.current_viewing_x_offset.to_i32(),
+                solid.y_pos - camera.current_viewing_y_offset.to_i32(),
+                frame.width as u32,
+                frame.height as u32,
+                frame.start_x as u32,
+                frame.start_y as u32,
+                BlitSubFlags{flip_x: false, flip_y: false},
+            );
+        }
+    }
+}
+
 static mut GAME_STATE_HOLDER: Option<GameState> = None;
 
+fn level_text(difficulty_level: u32) -> String {
+    format!["W{}-L{}", ((difficulty_level - 1) / LEVELS_PER_MOOD as u32) + 1, ((difficulty_level - 1) % LEVELS_PER_MOOD as u32) + 1]
+}
+
+fn score_text(run_type: &RunType, score: u32, speedrun_timer_msec: u32) -> String {
+    match run_type {
+        RunType::Random => format!["Sc: {}p", score],
+        RunType::Speedrun(n) => format!["Sd.{}: {}s", n, speedrun_timer_msec / 60],
+        RunType::Tutorial => "Tutorial".to_string(),
+    }
+}
+
+/// What a modal says, as things stand when it comes up.
+fn modal_menu(game_state: &GameState, menu_type: &MenuTypes) -> Menu<PauseMenuFocuses> {
+    let world_level_text = level_text(game_state.difficulty_level);
+    let score_text = score_text(&game_state.settings.run_type, game_state.score, game_state.speedrun_timer_msec);
+    match menu_type {
+        MenuTypes::WonLevel => {
+            let n_pickups = game_state.pickups_collected + game_state.pickups.len() as u32;
+            Menu { first_row_y: 10, row_height: 11, ..Menu::new(vec![
+                MenuRow::blinking_title(&world_level_text),
+                MenuRow::blinking_title("Clear!"),
+                MenuRow::label(&format!["Items {}/{}", game_state.pickups_collected, n_pickups]),
+            ])}
+        },
+        MenuTypes::StartLevel => Menu { first_row_y: 12, row_height: 11, ..Menu::new(vec![
+            MenuRow::title(&world_level_text),
+            MenuRow::title("Start!"),
+            MenuRow { centered: true, ..MenuRow::icon_label(spritesheet::PresetSprites::Clock, &format!["+{}", game_state.countdown_and_score_bonus / 60]) },
+        ])},
+        MenuTypes::Done => {
+            let mut rows = vec![
+                MenuRow::blinking_title("Time's Up!"),
+                MenuRow::label(&format!["End: {}", world_level_text]),
+                MenuRow::label(&score_text),
+                MenuRow::label(&format!["on {}", game_state.settings.difficulty.name()]),
+            ];
+            if let RunType::Random = game_state.settings.run_type {
+                rows.push(MenuRow::label(&format!["Best: {}p", game_state.high_scores.get(game_state.settings.difficulty).score]));
+            }
+            Menu::new(rows)
+        },
+        MenuTypes::WonGame => {
+            let mut rows = vec![
+                MenuRow::blinking_title("YOU WON!!!"),
+                MenuRow::label(&format!["End: {}", world_level_text]),
+                MenuRow::label(&score_text),
+            ];
+            rows.push(MenuRow::label(&format!["on {}", game_state.settings.difficulty.name()]));
+            if let RunType::Random = game_state.settings.run_type {
+                rows.push(MenuRow::label(&format!["Time: {}s", game_state.speedrun_timer_msec / 60]));
+                rows.push(MenuRow::label(&format!["Best: {}s", game_state.high_scores.get(game_state.settings.difficulty).fastest_win_frames / 60]));
+            }
+            Menu::new(rows)
+        },
+        MenuTypes::Paused(_) => pause_menu(&game_state.settings.run_type),
+        MenuTypes::TutorialDone => Menu { first_row_y: 10, row_height: 11, ..Menu::new(vec![
+            MenuRow::blinking_title("Well done!"),
+            MenuRow::title("Now go find"),
+            MenuRow::title("them all!"),
+        ])},
+        MenuTypes::StartGameMessage => Menu { margin_x: 20, first_row_y: 10, row_height: 13, ..Menu::new(vec![
+            MenuRow::title("-- GOAL --"),
+            MenuRow::title("Find all the"),
+            MenuRow::title("kitties in time!"),
+            MenuRow::label(""),
+            MenuRow::icon_label(spritesheet::PresetSprites::CatHead, "= # kitties"),
+            MenuRow::icon_label(spritesheet::PresetSprites::Clock, "= time left"),
+            MenuRow::label(""),
+            MenuRow::title("-- CONTROLS --"),
+            MenuRow::title("<> to move,"),
+            MenuRow::title(&format!["{}=jump, {}=card", game_state.options.controls.key_name(BUTTON_1), game_state.options.controls.key_name(BUTTON_2)]),
+        ])},
+    }
+}
+
+fn open_modal(game_state: &GameState, target_position: AbsoluteBoundingBox<i32, u32>, menu_type: MenuTypes) -> Modal {
+    let menu = modal_menu(game_state, &menu_type);
+    Modal::new(target_position, menu_type, menu)
+}
+
+fn start_level_modal(game_state: &GameState, center_x: f32, center_y: f32) -> Modal {
+    open_modal(
+        game_state,
+        AbsoluteBoundingBox {
+            x: center_x as i32 - 35,
+            y: center_y as i32 - 40,
+            width: 70,
+            height: 50,
+        },
+        MenuTypes::StartLevel,
+    )
+}
+
+fn pause_modal(game_state: &GameState, center_x: f32, center_y: f32, focus: PauseMenuFocuses) -> Modal {
+    open_modal(
+        game_state,
+        AbsoluteBoundingBox {
+            x: center_x as i32 - 62,
+            y: center_y as i32 - 45,
+            width: 124,
+            height: 88,
+        },
+        MenuTypes::Paused(focus),
+    )
+}
+
+fn pause_menu(run_type: &RunType) -> Menu<PauseMenuFocuses> {
+    Menu::new(vec![
+        MenuRow::title("Paused"),
+        MenuRow::title(&match run_type {
+            RunType::Random => "Random run".to_string(),
+            RunType::Speedrun(n) => format!["Seed {}", n],
+            RunType::Tutorial => "Tutorial".to_string(),
+        }),
+        MenuRow::button(PauseMenuFocuses::Resume, "Resume"),
+        MenuRow::button(PauseMenuFocuses::RestartLevel, "Restart level"),
+        MenuRow::button(PauseMenuFocuses::QuitToTitle, "Quit to title"),
+        MenuRow::button(PauseMenuFocuses::Options, "Options"),
+    ])
+}
+
+// the choices for the list rows, in the order their indices go.
+const PALETTE_CHOICES: [&str; spritesheet::KITTY_SPRITESHEET_PALETTES.len() + 1] = ["mood", "1", "2", "3", "4", "5", "6", "7", "8", "9"];
+const SCREEN_SCALE_CHOICES: [&str; MAX_SCREEN_SCALE as usize + 1] = ["fit", "1x", "2x", "3x", "4x"];
+const DIFFICULTY_CHOICES: [&str; Difficulty::ALL.len()] = [DIFFICULTY_SETTINGS[0].name, DIFFICULTY_SETTINGS[1].name, DIFFICULTY_SETTINGS[2].name];
+const CHARACTER_CHOICES: [&str; PLAYABLE_CHARACTERS.len()] = [
+    PLAYABLE_CHARACTERS[0].name,
+    PLAYABLE_CHARACTERS[1].name,
+    PLAYABLE_CHARACTERS[2].name,
+    PLAYABLE_CHARACTERS[3].name,
+    PLAYABLE_CHARACTERS[4].name,
+];
+const RUN_TYPE_CHOICES: [&str; 3] = ["Normal", "Seed", "Tutorial"];
+const RUN_SUMMARY_PAGE_CHOICES: [&str; RunSummaryPage::ALL.len()] = [
+    RunSummaryPage::ALL[0].name(),
+    RunSummaryPage::ALL[1].name(),
+    RunSummaryPage::ALL[2].name(),
+    RunSummaryPage::ALL[3].name(),
+];
+
+fn options_screen(options: &GameOptions, return_to: OptionsReturnTo) -> GameMode {
+    GameMode::OptionsScreen(OptionsSetup{current_selection: OptionsMenuFocuses::MusicVolume, return_to, menu: options_menu(options)})
+}
+
+fn options_menu(options: &GameOptions) -> Menu<OptionsMenuFocuses> {
+    Menu {
+        first_row_y: 12,
+        row_height: 14,
+        value_x: 92,
+        ..Menu::new(vec![
+            MenuRow::title("Options"),
+            MenuRow::spinner(OptionsMenuFocuses::MusicVolume, "Music", options.music_volume as u32, 0, MAX_VOLUME as u32),
+            MenuRow::spinner(OptionsMenuFocuses::SfxVolume, "Sounds", options.sfx_volume as u32, 0, MAX_VOLUME as u32),
+            MenuRow::list(OptionsMenuFocuses::Palette, "Colors", &PALETTE_CHOICES, options.palette_choice()),
+            MenuRow::toggle(OptionsMenuFocuses::SwapButtons, "Swap x/z", options.controls == ControlScheme::Swapped),
+            MenuRow::list(OptionsMenuFocuses::ScreenScale, "Scale", &SCREEN_SCALE_CHOICES, options.screen_scale as usize),
+            MenuRow::toggle(OptionsMenuFocuses::TouchOverlay, "Touch pad", options.show_touch_overlay),
+            MenuRow::toggle(OptionsMenuFocuses::Tutorial, "Tutorial", options.tutorial),
+            MenuRow::label(""),
+            MenuRow::title("x/z: back"),
+        ])
+    }
+}
+
+// the rows of the select menu that change while it's up.
+const SELECT_MENU_CHARACTER_ROW: usize = 1;
+const SELECT_MENU_SEED_ROW: usize = 3;
+
+fn select_screen(game_state: &GameState, focus: SelectMenuFocuses) -> GameMode {
+    GameMode::SelectScreen(SelectSetup{current_selection: focus, menu: select_menu(&game_state.settings, &game_state.players, &game_state.options.controls)})
+}
+
+// everyone who's joined, as who they picked.
+fn joined_character_icons<'a>(settings: &'a GameSettings, players: &'a [OptionallyEnabledPlayer]) -> impl Iterator<Item = spritesheet::PresetSprites> + Clone + 'a {
+    players.iter().enumerate()
+        .filter(|(_, p)| matches!(p, OptionallyEnabledPlayer::Enabled(_)))
+        .map(|(i, _)| settings.characters[i].profile().sprite)
+}
+
+// under the run type: the seed, if there is one.
+fn seed_row(run_type: &RunType) -> MenuRow<SelectMenuFocuses> {
+    match run_type {
+        RunType::Random => MenuRow::label("Random levels"),
+        RunType::Speedrun(n) => MenuRow::spinner(SelectMenuFocuses::Seed, "Seed", *n, 0, RunSeed::MAX),
+        RunType::Tutorial => MenuRow::label("Learn to play"),
+    }
+}
+
+fn select_menu(settings: &GameSettings, players: &[OptionallyEnabledPlayer], controls: &ControlScheme) -> Menu<SelectMenuFocuses> {
+    let difficulty_i = Difficulty::ALL.iter().position(|d| *d == settings.difficulty).unwrap_or(0);
+    let character_i = PLAYABLE_CHARACTERS.iter().position(|p| p.character == settings.characters[0]).unwrap_or(0);
+    let run_type_i = match settings.run_type {
+        RunType::Random => 0,
+        RunType::Speedrun(_) => 1,
+        RunType::Tutorial => 2,
+    };
+    Menu {
+        first_row_y: 64,
+        row_height: 14,
+        value_x: 72,
+        ..Menu::new(vec![
+            MenuRow::list(SelectMenuFocuses::Difficulty, "Skill", &DIFFICULTY_CHOICES, difficulty_i),
+            // the icons, then player 1's pick by name.
+            MenuRow {
+                icons: joined_character_icons(settings, players).collect(),
+                ..MenuRow::list(SelectMenuFocuses::CharacterSelect, "", &CHARACTER_CHOICES, character_i)
+            },
+            MenuRow::list(SelectMenuFocuses::RunType, "Run", &RUN_TYPE_CHOICES, run_type_i),
+            seed_row(&settings.run_type),
+            MenuRow::button(SelectMenuFocuses::Start, &format!["{}: Start!", controls.key_name(BUTTON_1)]),
+            MenuRow::button(SelectMenuFocuses::Options, "Options"),
+            MenuRow::button(SelectMenuFocuses::Achievements, "Achievements"),
+        ])
+    }
+}
+
+// left and right flip through the pages.
+fn run_summary_menu(stats: &RunStats, page: RunSummaryPage) -> Menu<()> {
+    let page_i = RunSummaryPage::ALL.iter().position(|p| *p == page).unwrap_or(0);
+    let stat = |name: &str, value: String| MenuRow::label(&format!["{:<13}{:>5}", name, value]);
+    let mut rows = vec![
+        MenuRow::title("Run stats"),
+        MenuRow::list((), "Page", &RUN_SUMMARY_PAGE_CHOICES, page_i),
+        MenuRow::label(""),
+    ];
+    match page {
+        RunSummaryPage::Moves => {
+            rows.push(stat("Jumps", format!["{}", stats.jumps]));
+            rows.push(stat("Wall jumps", format!["{}", stats.wall_jumps]));
+            rows.push(stat("Distance", format!["{}", stats.distance.to_i32() / TILE_WIDTH_PX as i32]));
+            rows.push(stat("Warps", format!["{}", stats.warps]));
+        }
+        RunSummaryPage::Cards => {
+            for (def, n) in CARD_DEFS.iter().zip(stats.cards_used.iter()) {
+                rows.push(stat(def.name, format!["{}", n]));
+            }
+        }
+        RunSummaryPage::Kitties => {
+            for ((name, sprites), n) in NPC_SPECIES.iter().zip(stats.npcs_found.iter()) {
+                rows.push(MenuRow::icon_label(sprites[0], &format!["{:<8}{:>3}", name, n]));
+            }
+        }
+        RunSummaryPage::Levels => {
+            let n_levels = stats.levels.len() as u32;
+            let total_frames: u32 = stats.levels.iter().map(|level| level.frames).sum();
+            rows.push(stat("Levels", format!["{}", n_levels]));
+            rows.push(stat("Average", format!["{}s", total_frames / n_levels.max(1) / 60]));
+            if let Some(fastest) = stats.levels.iter().min_by_key(|level| level.frames) {
+                rows.push(stat(&format!["Fastest L{}", fastest.level], format!["{}s", fastest.frames / 60]));
+            }
+            if let Some(slowest) = stats.levels.iter().max_by_key(|level| level.frames) {
+                rows.push(stat(&format!["Slowest L{}", slowest.level], format!["{}s", slowest.frames / 60]));
+            }
+            rows.push(MenuRow::label("Closest calls"));
+            for level in stats.closest_calls().iter().take(3) {
+                rows.push(stat(&format![" L{}", level.level], format!["{}s", level.lowest_countdown / 60]));
+            }
+        }
+    }
+    Menu { first_row_y: 8, row_height: 12, value_x: 56, ..Menu::new(rows) }
+}
+
+// nothing to pick here, it's just a list.
+fn achievements_menu(achievements: &Achievements) -> Menu<()> {
+    let mut rows = vec![
+        MenuRow::title("Achievements"),
+        MenuRow::title(&format!["{}/{}", achievements.n_unlocked(), Achievement::ALL.len()]),
+    ];
+    for achievement in Achievement::ALL {
+        let check = match achievements.is_unlocked(achievement) {
+            true => "[x]",
+            false => "[ ]",
+        };
+        rows.push(MenuRow::label(&format!["{} {}", check, achievement.name()]));
+        rows.push(MenuRow::label(&format!["  {}", achievement.description()]));
+    }
+    rows.push(MenuRow::label(""));
+    rows.push(MenuRow::title("x/z: back"));
+    Menu { first_row_y: 8, row_height: 10, margin_x: 4, ..Menu::new(rows) }
+}
+
+/// Debugging aids a platform can switch on before the first frame.
+pub struct DebugOptions {
+    /// Log the state hash every this many frames, or never if 0.
+    pub log_state_hash_every_n_frames: u32,
+    /// Record every frame's inputs, and a state hash every so often, for kittygame_replay_text().
+    pub record_replay: bool,
+}
+
+static mut DEBUG_OPTIONS: DebugOptions = DebugOptions {
+    log_state_hash_every_n_frames: 0,
+    record_replay: false,
+};
+
+pub fn kittygame_set_debug_options(options: DebugOptions) {
+    unsafe { DEBUG_OPTIONS = options }
+}
+
+// the game state, once the first frame has set it up.
+fn existing_game_state() -> Option<&'static GameState> {
+    unsafe { (*core::ptr::addr_of!(GAME_STATE_HOLDER)).as_ref() }
+}
+
+fn existing_game_state_mut() -> Option<&'static mut GameState> {
+    unsafe { (*core::ptr::addr_of_mut!(GAME_STATE_HOLDER)).as_mut() }
+}
+
+// what the platform loaded, until the first frame picks it up.
+static mut LOADED_SAVE: Option<SaveData> = None;
+
+/// Hand over whatever kittygame_take_save() gave last session, before the first frame.
+/// Anything that isn't a save from this build is ignored, and the defaults are used.
+pub fn kittygame_load_save(bytes: &[u8]) {
+    unsafe { *core::ptr::addr_of_mut!(LOADED_SAVE) = SaveData::from_bytes(bytes) }
+}
+
+/// If anything worth keeping changed since the last call, the bytes to store for next time.
+pub fn kittygame_take_save() -> Option<Vec<u8>> {
+    let game_state = existing_game_state_mut()?;
+    if !game_state.save_dirty {
+        return None;
+    }
+    game_state.save_dirty = false;
+    Some(game_state.save_data().to_bytes())
+}
+
+/// The options as they are now, for platforms that handle the screen scale or touch overlay themselves.
+pub fn kittygame_options() -> GameOptions {
+    match existing_game_state() {
+        Some(game_state) => game_state.options,
+        None => match unsafe { &*core::ptr::addr_of!(LOADED_SAVE) } {
+            Some(save) => save.options,
+            None => GameOptions::new(),
+        },
+    }
+}
+
+/// The state hash as of the last frame, or None before the first.
+pub fn kittygame_state_hash() -> Option<u64> {
+    existing_game_state().map(|game_state| game_state.state_hash())
+}
+
+/// Everything recorded so far, if the replay is being recorded.
+pub fn kittygame_replay_text() -> Option<String> {
+    existing_game_state()?.replay.as_ref().map(|replay| replay.to_text())
+}
+
+/// The stats of the run being played, or the last one, one per line. Platforms that record replays
+/// keep them alongside.
+pub fn kittygame_run_stats_text() -> Option<String> {
+    existing_game_state().map(|game_state| game_state.run_stats.to_text())
+}
+
 /// Draw a character on-screen, relative to the camera.
 fn drawcharacter(
     camera: &Camera,
@@ -101,6 +502,10 @@ fn drawcharacter(
         MovingEntity::OptionalPlayer(optionally_enabled_player) => {
             match optionally_enabled_player {
                 OptionallyEnabledPlayer::Enabled(p) => {
+                    // blink while invulnerable
+                    if p.invulnerable_frames % 8 >= 4 {
+                        return;
+                    }
                     the_char = &mut p.character;
                 }
                 OptionallyEnabledPlayer::Disabled => return,
@@ -114,8 +519,8 @@ fn drawcharacter(
     let i = the_char.current_sprite_i as usize;
     blit_sub(
         Spritesheet::Main,
-        (the_char.x_pos - camera.current_viewing_x_offset) as i32,
-        (the_char.y_pos - camera.current_viewing_y_offset) as i32,
+        (the_char.x_pos - camera.current_viewing_x_offset).to_i32(),
+        (the_char.y_pos - camera.current_viewing_y_offset).to_i32(),
         the_char.sprite.frames[i].width as u32,
         the_char.sprite.frames[i].height as u32,
         the_char.sprite.frames[i].start_x as u32,
@@ -130,6 +535,95 @@ fn drawcharacter(
     );
 }
 
+fn drawhazard(camera: &Camera, hazard: &Hazard, line: &mut LineFunc, rect: &mut RectFunc) {
+    let x = (hazard.x_pos - camera.current_viewing_x_offset).to_i32();
+    let y = (hazard.y_pos - camera.current_viewing_y_offset).to_i32();
+    let w = HAZARD_WIDTH_PX as i32;
+    let h = HAZARD_HEIGHT_PX as i32;
+    match hazard.kind {
+        HazardKind::Spikeball => {
+            // a lil ball with spikes that spin as it rolls
+            rect(x + 1, y + 1, 3, 3, &DrawColor::PigsLizards);
+            match (hazard.count / 6) % 2 {
+                0 => {
+                    line(x + w / 2, y, x + w / 2, y + h - 1, &DrawColor::Foreground);
+                    line(x, y + h / 2, x + w - 1, y + h / 2, &DrawColor::Foreground);
+                }
+                _ => {
+                    line(x, y, x + w - 1, y + h - 1, &DrawColor::Foreground);
+                    line(x + w - 1, y, x, y + h - 1, &DrawColor::Foreground);
+                }
+            }
+        }
+        HazardKind::Icicle => {
+            // regrowing icicles poke back out of the ceiling
+            let grown_h = match hazard.state {
+                HazardState::Regrowing(t) => (h - t as i32 / 36).max(0),
+                _ => h,
+            };
+            if grown_h > 0 {
+                rect(x + 1, y, 3, (grown_h - 2).max(1) as u32, &DrawColor::PigsLizards);
+                line(x + w / 2, y, x + w / 2, y + grown_h - 1, &DrawColor::PigsLizards);
+            }
+        }
+    }
+}
+
+/// Draw a card with its top left corner at (x, y). Cards without their own art are a letter on a plain card.
+fn drawcard(card_type: &AbilityCardTypes, x: i32, y: i32, blit_sub: &mut BlitSubFunc, rect: &mut RectFunc, text_str: &mut TextStrFunc) {
+    match card_type.sprite() {
+        Some(sprite) => {
+            let frame = &sprite.frames[0];
+            blit_sub(
+                Spritesheet::Main,
+                x,
+                y,
+                frame.width as u32,
+                frame.height as u32,
+                frame.start_x as u32,
+                frame.start_y as u32,
+                BlitSubFlags { flip_x: false, flip_y: false }
+            );
+        }
+        None => {
+            rect(x, y, CARD_WIDTH_PX, CARD_HEIGHT_PX, &DrawColor::Foreground);
+            rect(x + 1, y + 1, CARD_WIDTH_PX - 2, CARD_HEIGHT_PX - 2, &DrawColor::Background);
+            text_str(card_type.letter(), x + 2, y + 2, &DrawColor::MainKitty);
+        }
+    }
+}
+
+fn drawpickup(camera: &Camera, pickup: &Pickup, blit_sub: &mut BlitSubFunc, line: &mut LineFunc, rect: &mut RectFunc, text_str: &mut TextStrFunc) {
+    let x = (pickup.x_pos - camera.current_viewing_x_offset.to_f32()) as i32;
+    let y = (pickup.y_pos - camera.current_viewing_y_offset.to_f32()) as i32 + pickup.get_bob_offset();
+    let preset = match &pickup.kind {
+        PickupKind::Yarn => {
+            // a ball of yarn with a bit of loose string
+            rect(x + 1, y + 1, 4, 4, &DrawColor::PigsLizards);
+            line(x + 2, y + 2, x + 3, y + 3, &DrawColor::Foreground);
+            line(x, y + 4, x + 1, y + 4, &DrawColor::PigsLizards);
+            return;
+        }
+        PickupKind::Clock => spritesheet::PresetSprites::Clock,
+        PickupKind::Card(card_type) => {
+            drawcard(card_type, x + 2 - CARD_WIDTH_PX as i32 / 2, y + 5 - CARD_HEIGHT_PX as i32, blit_sub, rect, text_str);
+            return;
+        }
+    };
+    // sprites are bigger than the pickup, so sit them on the same floor.
+    let frame = &spritesheet::Sprite::from_preset(&preset).frames[0];
+    blit_sub(
+        Spritesheet::Main,
+        x + 2 - frame.width as i32 / 2,
+        y + 5 - frame.height as i32,
+        frame.width as u32,
+        frame.height as u32,
+        frame.start_x as u32,
+        frame.start_y as u32,
+        BlitSubFlags { flip_x: false, flip_y: false }
+    );
+}
+
 static mut NPC_INPUTS: [u8; MAX_N_NPCS] = [0; MAX_N_NPCS];
 
 
@@ -233,6 +727,69 @@ fn layertext(t: &str, x: i32, y: i32, text_str: &mut TextStrFunc) {
     text_str(t, x, y, &DrawColor::Foreground);
 }
 
+// just draw a spriteframe at a location. Put a colored layer behind it, like layertext() does.
+fn draw_spriteframe (spriteframe: &spritesheet::SpriteFrame, x: i32, y: i32, blit_sub: &mut BlitSubFunc) {
+    let cf = spriteframe;
+    // for (xx, yy, colors) in [(x, y, 0x1111), (x+1, y+1, 0x1111), (x, y, spritesheet::KITTY_SPRITESHEET_DRAW_COLORS)] {
+
+    for (xx, yy, _colors) in [(x, y, spritesheet::KITTY_SPRITESHEET_DRAW_COLORS)] {
+        // unsafe {*DRAW_COLORS = colors}
+        blit_sub(
+            Spritesheet::Main,
+            xx,
+            yy,
+            cf.width as u32,
+            cf.height as u32,
+            cf.start_x as u32,
+            cf.start_y as u32,
+            BlitSubFlags { flip_x: false, flip_y: false }
+        );
+    }
+    
+}
+
+// between a menu row's icons, and its text.
+const ICON_SPACING: i32 = 1;
+
+/// Draw a menu with its top left at x, y, in a space width wide. The focused row gets a box around it.
+/// blink_on shows the blinking rows, arrows_on the arrows either side of the focused row's value.
+fn draw_menu<Id: Copy + PartialEq>(menu: &Menu<Id>, focus: Option<Id>, x: i32, y: i32, width: i32, blink_on: bool, arrows_on: bool, blit_sub: &mut BlitSubFunc, line: &mut LineFunc, rect: &mut RectFunc, text_str: &mut TextStrFunc) {
+    for (row_i, row) in menu.rows.iter().enumerate() {
+        let row_y = y + menu.row_y(row_i);
+        let focused = row.id.is_some() && row.id == focus;
+        if focused {
+            draw_modal_bg(&AbsoluteBoundingBox{x: (x + menu.margin_x - 4) as f32, y: (row_y - 3) as f32, width: (width - 2 * menu.margin_x + 8) as f32, height: menu.row_height as f32}, 1, &DrawColor::MainKitty, line, rect);
+        }
+        if row.blinks && !blink_on {
+            continue;
+        }
+
+        let icon_frame = |icon| &spritesheet::Sprite::from_preset(icon).frames[0];
+        let icons_width: i32 = row.icons.iter().map(|icon| icon_frame(icon).width as i32 + ICON_SPACING).sum();
+        let mut text_x = match row.centered {
+            true => x + (width - icons_width - row.text.len() as i32 * 8) / 2,
+            false => x + menu.margin_x,
+        };
+        for icon in row.icons.iter() {
+            let frame = icon_frame(icon);
+            draw_spriteframe(frame, text_x, row_y - 1, blit_sub);
+            text_x += frame.width as i32 + ICON_SPACING;
+        }
+        if !row.text.is_empty() {
+            layertext(&row.text, text_x, row_y, text_str);
+        }
+
+        if let Some(value) = row.widget.value_text() {
+            let value_x = x + menu.value_x;
+            layertext(value, value_x, row_y, text_str);
+            if focused && arrows_on {
+                text_str("<", value_x - 10, row_y, &DrawColor::MainKitty);
+                text_str(">", value_x + 2 + value.len() as i32 * 8, row_y, &DrawColor::MainKitty);
+            }
+        }
+    }
+}
+
 const TIMER_INTERACTIVE_START: u32 = 100;
 const TITLE_Y: i32 = 15;
 
@@ -260,7 +817,7 @@ fn render_title(game_state: &GameState, x: i32, y: i32, blit_sub: &mut BlitSubFu
 
 /// Main loop that runs every frame. Progress the game state and render.
 #[no_mangle]
-pub fn kittygame_update(blit_sub: &mut BlitSubFunc, line: &mut LineFunc, rect: &mut RectFunc, text_str: &mut TextStrFunc, set_palette: &mut SwitchPalletteFunc, sw: u32, sh: u32, btns_pressed_this_frame: &[u8; 4], gamepads: &[u8; 4]) {
+pub fn kittygame_update(blit_sub: &mut BlitSubFunc, line: &mut LineFunc, rect: &mut RectFunc, text_str: &mut TextStrFunc, set_palette: &mut SwitchPalletteFunc, log: &mut LogFunc, tone: &mut ToneFunc, sw: u32, sh: u32, btns_pressed_this_frame: &[u8; 4], gamepads: &[u8; 4]) {
     
     let (center_x, center_y) = (sw as f32 / 2., sh as f32 / 2.);
     
@@ -272,6 +829,9 @@ pub fn kittygame_update(blit_sub: &mut BlitSubFunc, line: &mut LineFunc, rect: &
             None => {
                 spritesheet::Sprite::init_all_sprites();
                 let mut new_game_state = GameState::new();
+                if let Some(save) = (*core::ptr::addr_of_mut!(LOADED_SAVE)).take() {
+                    new_game_state.load_save_data(save);
+                }
                 for _ in 0..20 {
                     new_game_state.rng.next_for_worldgen();
                 }
@@ -289,9 +849,25 @@ pub fn kittygame_update(blit_sub: &mut BlitSubFunc, line: &mut LineFunc, rect: &
         }
     }
 
+    // ----------- RECORD INPUTS FOR THE REPLAY -----------
+    game_state.frame_count += 1;
+    if unsafe { DEBUG_OPTIONS.record_replay } && game_state.frame_count == 1 {
+        let mut replay = Replay::new();
+        // the options decide how the inputs play out, so the replay starts from the same save.
+        replay.save = Some(game_state.save_data().to_bytes());
+        game_state.replay = Some(replay);
+    }
+    if let Some(replay) = &mut game_state.replay {
+        replay.record_inputs(sw, sh, btns_pressed_this_frame, gamepads);
+    }
+    // the rest of the game only sees the standard button layout.
+    let controls = game_state.options.controls;
+    let btns_pressed_this_frame = &btns_pressed_this_frame.map(|b| controls.remap(b));
+    let gamepads = &gamepads.map(|b| controls.remap(b));
+
     // ----------- UPDATE TIMER AND PLAY BGM -----------
     game_state.song_timer += 1;
-    play_bgm(game_state.song_timer, &SONGS[game_state.song_idx]);
+    play_bgm(game_state.song_timer, &SONGS[game_state.song_idx], game_state.options.music_volume, tone);
 
 
     // let mut player_idx: u8 = 0b0;
@@ -312,14 +888,14 @@ pub fn kittygame_update(blit_sub: &mut BlitSubFunc, line: &mut LineFunc, rect: &
         OptionallyEnabledPlayer::Disabled => {}
         OptionallyEnabledPlayer::Enabled(player) => {
             game_state.camera.current_viewing_x_target = num::clamp(
-                player.character.x_pos - center_x,
-                X_LEFT_BOUND as f32,
-                X_RIGHT_BOUND as f32,
+                player.character.x_pos - Fixed::from_int(sw as i32) / 2,
+                Fixed::from_int(X_LEFT_BOUND),
+                Fixed::from_int(X_RIGHT_BOUND),
             );
             game_state.camera.current_viewing_y_target = num::clamp(
-                player.character.y_pos - center_y,
-                Y_LOWER_BOUND as f32,
-                Y_UPPER_BOUND as f32,
+                player.character.y_pos - Fixed::from_int(sh as i32) / 2,
+                Fixed::from_int(Y_LOWER_BOUND),
+                Fixed::from_int(Y_UPPER_BOUND),
             );
         }
     }
@@ -349,25 +925,38 @@ pub fn kittygame_update(blit_sub: &mut BlitSubFunc, line: &mut LineFunc, rect: &
         },
         _ => {}
     }
+    // nothing in the level moves while it's paused, even with the options open on top.
+    let paused = game_state.paused_since_frame.is_some();
     // ON TITLE SCREEN, MOVE PLAYER 1 BASED ON TIME
     
     // CHECK IF CHARACTERS / CATS ARE COLLIDING
     if !showing_modal {
+        game_state.update_contacts();
         check_entity_collisions(&mut game_state);
     }
+    if let GameMode::NormalPlay(NormalPlayModes::MainGameplay) = game_state.game_mode {
+        check_hazard_collisions(&mut game_state);
+        check_pickup_collisions(&mut game_state);
+    }
     
     // PREPARE TO RENDER THE MAP & ENTITIES
     // unsafe {
     //     *PALETTE = spritesheet::KITTY_SPRITESHEET_PALETTES[game_state.pallette_idx];
     // }
+    let pallette_idx = game_state.options.palette_idx(game_state.pallette_idx);
     set_palette(&Pallette{
-        main_kitty: spritesheet::KITTY_SPRITESHEET_PALETTES[game_state.pallette_idx][3],
-        pigs_lizards: spritesheet::KITTY_SPRITESHEET_PALETTES[game_state.pallette_idx][2],
-        foreground: spritesheet::KITTY_SPRITESHEET_PALETTES[game_state.pallette_idx][1],
-        background: spritesheet::KITTY_SPRITESHEET_PALETTES[game_state.pallette_idx][0],
+        main_kitty: spritesheet::KITTY_SPRITESHEET_PALETTES[pallette_idx][3],
+        pigs_lizards: spritesheet::KITTY_SPRITESHEET_PALETTES[pallette_idx][2],
+        foreground: spritesheet::KITTY_SPRITESHEET_PALETTES[pallette_idx][1],
+        background: spritesheet::KITTY_SPRITESHEET_PALETTES[pallette_idx][0],
     });
     // unsafe { *DRAW_COLORS = spritesheet::KITTY_SPRITESHEET_DRAW_COLORS }
 
+    // MOVE THE PLATFORMS (before anyone standing on them)
+    if !paused {
+        game_state.update_dynamic_solids();
+    }
+
     // MOVE AND RENDER THE PLAYERS 
     {
         let optional_players: &mut [OptionallyEnabledPlayer; 4] = &mut game_state.players;
@@ -399,14 +988,30 @@ pub fn kittygame_update(blit_sub: &mut BlitSubFunc, line: &mut LineFunc, rect: &
                 }
             }
             
+            if !paused {
+                // join in as whoever this player picked.
+                if let OptionallyEnabledPlayer::Disabled = optional_player {
+                    if input != 0 {
+                        *optional_player = OptionallyEnabledPlayer::Enabled(Player::new(game_state.settings.characters[i]));
+                    }
+                }
 
-            update_pos(
-                &game_state.map,
-                MovingEntity::OptionalPlayer(optional_player),
-                input,
-                game_state.godmode,
-                &mut game_state.clouds,
-            );
+                // only what's done in a level counts toward the run's stats.
+                let before = match (&game_state.game_mode, &optional_player) {
+                    (GameMode::NormalPlay(NormalPlayModes::MainGameplay), OptionallyEnabledPlayer::Enabled(p)) => Some(MoveSnapshot::of(&p.character)),
+                    _ => None,
+                };
+                update_pos(
+                    &game_state.map,
+                    MovingEntity::OptionalPlayer(optional_player),
+                    input,
+                    game_state.godmode,
+                    &mut game_state.clouds,
+                );
+                if let (Some(before), OptionallyEnabledPlayer::Enabled(p)) = (before, &optional_player) {
+                    game_state.run_stats.track_move(&before, &p.character);
+                }
+            }
             
         
 
@@ -421,11 +1026,23 @@ pub fn kittygame_update(blit_sub: &mut BlitSubFunc, line: &mut LineFunc, rect: &
    
 
 
+    // POINT FOLLOWERS AT THEIR PLAYERS
+    for (p_i, opt_p) in game_state.players.iter().enumerate() {
+        if let OptionallyEnabledPlayer::Enabled(p) = opt_p {
+            if game_state.npcs.iter().any(|npc| npc.following_i == Some(p_i as u8)) {
+                game_state.nav_graph.update_flow(p_i, &p.character);
+            }
+        }
+    }
+
     // CREATE INPUTS FOR NPCS
     let inputs: &mut [u8; MAX_N_NPCS] = unsafe { &mut NPC_INPUTS };
     let l;
     {
-        l = game_state.npcs.len();
+        l = match paused {
+            true => 0,
+            false => game_state.npcs.len(),
+        };
     }
     for i in 0..l {
         let rng = &mut game_state.rng;
@@ -434,7 +1051,11 @@ pub fn kittygame_update(blit_sub: &mut BlitSubFunc, line: &mut LineFunc, rect: &
         let mut use_rng_input = false;
         match current_npc.following_i {
             None => {
-                use_rng_input = true;
+                // nobody found this one yet, so it does its own thing. Lower rolls are what make it move,
+                // so squeezing the rolls down makes it move more often.
+                let wander_percent = game_state.settings.difficulty.setting().npc_wander_percent;
+                let rand_val = (rand_val as u32 * 100 / wander_percent).min(254) as u8;
+                inputs[i] = get_wander_input(current_npc, &game_state.players, rand_val);
             }
             Some(p_i) => {
                 let the_opt_player = &game_state.players[p_i as usize];
@@ -442,25 +1063,36 @@ pub fn kittygame_update(blit_sub: &mut BlitSubFunc, line: &mut LineFunc, rect: &
                     let p_bound = get_bound_of_character(&p.character);
                     let npc_bound: AbsoluteBoundingBox<i32, u32> =
                         get_bound_of_character(&current_npc);
+                    let step = game_state.nav_graph.get_step(p_i as usize, current_npc);
+                    current_npc.nav.update_progress(
+                        game_state.nav_graph.flow_goal(p_i as usize),
+                        p_bound.x.abs_diff(npc_bound.x) + p_bound.y.abs_diff(npc_bound.y),
+                    );
                     let needs_teleport;
                     {
-                        // teleportAyh-shon if needed
+                        // teleportAyh-shon, but only if the NPC can't find its own way
                         const TELEPORT_AXIS_MIN_DIST: u32 = 160;
-                        if p_bound.x.abs_diff(npc_bound.x) > TELEPORT_AXIS_MIN_DIST
-                            || p_bound.y.abs_diff(npc_bound.y) > TELEPORT_AXIS_MIN_DIST
-                        {
-                            needs_teleport = true
-                        } else {
-                            needs_teleport = false
-                        }
+                        let is_far = p_bound.x.abs_diff(npc_bound.x) > TELEPORT_AXIS_MIN_DIST
+                            || p_bound.y.abs_diff(npc_bound.y) > TELEPORT_AXIS_MIN_DIST;
+                        let is_lost = match step {
+                            NavStep::NoPath => true,
+                            _ => current_npc.nav.stuck_frames > NAV_GIVE_UP_FRAMES,
+                        };
+                        needs_teleport = is_far && is_lost;
                     }
 
                     if needs_teleport {
-                        current_npc.x_pos = p_bound.x as f32;
-                        current_npc.y_pos = p_bound.y as f32;
-                        current_npc.x_vel = 0.0;
-                        current_npc.y_vel = 0.0;
+                        current_npc.x_pos = Fixed::from_int(p_bound.x);
+                        current_npc.y_pos = Fixed::from_int(p_bound.y);
+                        current_npc.x_vel = Fixed::ZERO;
+                        current_npc.y_vel = Fixed::ZERO;
+                        current_npc.nav = NavFollower::new();
+                    } else if let NavStep::Inputs(nav_input) = step {
+                        inputs[i] = nav_input;
                     } else {
+                        if let NavStep::Arrived = step {
+                            current_npc.nav = NavFollower::new();
+                        }
                         if rng.next_for_input() % 10 > 1 {
                             inputs[i] = 0;
 
@@ -471,8 +1103,8 @@ pub fn kittygame_update(blit_sub: &mut BlitSubFunc, line: &mut LineFunc, rect: &
                             let mut tryhard_get_to_0: bool = true;
                             let ch = &p.character;
                             // fall by doing nothing
-                            if current_npc.y_pos + (npc_bound.height as f32) < ch.y_pos {
-                            } else if current_npc.y_pos > ch.y_pos + p_bound.height as f32 {
+                            if current_npc.y_pos + Fixed::from_int(npc_bound.height as i32) < ch.y_pos {
+                            } else if current_npc.y_pos > ch.y_pos + Fixed::from_int(p_bound.height as i32) {
                                 inputs[i] |= BUTTON_1;
                             } else {
                                 tryhard_get_to_0 = false;
@@ -485,9 +1117,9 @@ pub fn kittygame_update(blit_sub: &mut BlitSubFunc, line: &mut LineFunc, rect: &
                                     inputs[i] |= BUTTON_LEFT;
                                 }
                             } else {
-                                if current_npc.x_pos + (npc_bound.width as f32) < ch.x_pos {
+                                if current_npc.x_pos + Fixed::from_int(npc_bound.width as i32) < ch.x_pos {
                                     inputs[i] |= BUTTON_RIGHT;
-                                } else if current_npc.x_pos > ch.x_pos + p_bound.width as f32
+                                } else if current_npc.x_pos > ch.x_pos + Fixed::from_int(p_bound.width as i32)
                                 {
                                     inputs[i] |= BUTTON_LEFT;
                                 }
@@ -503,15 +1135,7 @@ pub fn kittygame_update(blit_sub: &mut BlitSubFunc, line: &mut LineFunc, rect: &
         }
 
         if use_rng_input {
-            if rand_val < 20 {
-                inputs[i] = 0x10;
-            } else if rand_val < 40 {
-                inputs[i] = 0x20;
-            } else if rand_val < 42 {
-                inputs[i] = BUTTON_1;
-            } else {
-                inputs[i] = 0x0;
-            }
+            inputs[i] = get_random_input(rand_val);
         }
         
 
@@ -519,7 +1143,7 @@ pub fn kittygame_update(blit_sub: &mut BlitSubFunc, line: &mut LineFunc, rect: &
     }
 
     // MOVE NPCS
-    for (i, npc) in game_state.npcs.iter_mut().enumerate() {
+    for (i, npc) in game_state.npcs.iter_mut().enumerate().filter(|_| !paused) {
         update_pos(
             &game_state.map,
             MovingEntity::NPC(npc),
@@ -538,12 +1162,31 @@ pub fn kittygame_update(blit_sub: &mut BlitSubFunc, line: &mut LineFunc, rect: &
         );
     }
 
+    // DRAW PICKUPS
+    for pickup in game_state.pickups.iter_mut() {
+        if !paused {
+            pickup.count += 1;
+        }
+        drawpickup(&game_state.camera, pickup, blit_sub, line, rect, text_str);
+    }
+
+    // MOVE AND DRAW HAZARDS
+    for hazard in game_state.hazards.iter_mut() {
+        if !paused {
+            hazard.update(&game_state.map, &game_state.players, &mut game_state.clouds);
+        }
+        drawhazard(&game_state.camera, hazard, line, rect);
+    }
+
  
     // ------ RENDER THE MAP -----------
     drawmap(&game_state, blit_sub, sw, sh);
+    drawdynamicsolids(&game_state, blit_sub);
 
     // UPDATE CLOUDS
-    Cloud::update_clouds(&mut game_state.clouds);
+    if !paused {
+        Cloud::update_clouds(&mut game_state.clouds);
+    }
 
     // DRAW CLOUDS
     for cloud in game_state.clouds.iter() {
@@ -551,8 +1194,8 @@ pub fn kittygame_update(blit_sub: &mut BlitSubFunc, line: &mut LineFunc, rect: &
         let cloud_sprite: &spritesheet::Sprite = spritesheet::Sprite::from_preset(&spritesheet::PresetSprites::Cloud);
         blit_sub(
             Spritesheet::Main,
-            (cloud.x - cam.current_viewing_x_offset) as i32,
-            (cloud.y - cam.current_viewing_y_offset) as i32,
+            (cloud.x - cam.current_viewing_x_offset.to_f32()) as i32,
+            (cloud.y - cam.current_viewing_y_offset.to_f32()) as i32,
             cloud_sprite.frames[0].width as u32,
             cloud_sprite.frames[0].height as u32,
             cloud_sprite.frames[0].start_x as u32,
@@ -564,27 +1207,6 @@ pub fn kittygame_update(blit_sub: &mut BlitSubFunc, line: &mut LineFunc, rect: &
         );
     }
 
-    // just draw a spriteframe at a location. Put a colored layer behind it, like layertext() does.
-    fn draw_spriteframe (spriteframe: &spritesheet::SpriteFrame, x: i32, y: i32, blit_sub: &mut BlitSubFunc) {
-        let cf = spriteframe;
-        // for (xx, yy, colors) in [(x, y, 0x1111), (x+1, y+1, 0x1111), (x, y, spritesheet::KITTY_SPRITESHEET_DRAW_COLORS)] {
-
-        for (xx, yy, _colors) in [(x, y, spritesheet::KITTY_SPRITESHEET_DRAW_COLORS)] {
-            // unsafe {*DRAW_COLORS = colors}
-            blit_sub(
-                Spritesheet::Main,
-                xx,
-                yy,
-                cf.width as u32,
-                cf.height as u32,
-                cf.start_x as u32,
-                cf.start_y as u32,
-                BlitSubFlags { flip_x: false, flip_y: false }
-            );
-        }
-        
-    }
-
     // Depending on what gamemode we're in, we do different update steps.
     match &mut game_state.game_mode {
         GameMode::NormalPlay(play_mode) => {
@@ -623,15 +1245,8 @@ pub fn kittygame_update(blit_sub: &mut BlitSubFunc, line: &mut LineFunc, rect: &
                 .fold(0, |acc, e| acc + match e.following_i {None => 0, Some(_) => 1});
 
             // COMPUTE SCORE, LEVEL, # KITTIES (used later either in modal or normal screen)
-            let world_level_text = &format!["W{}-L{}", ((game_state.difficulty_level - 1) / LEVELS_PER_MOOD as u32) + 1, ((game_state.difficulty_level - 1) % LEVELS_PER_MOOD as u32) + 1];
-            let score_text = match game_state.settings.run_type {
-                game::game_state::RunType::Random => {
-                    format!["Sc: {}p", game_state.score]
-                },
-                game::game_state::RunType::Speedrun(n) => {
-                    format!["Sd.{}: {}s", n, game_state.speedrun_timer_msec/ 60]
-                },
-            };
+            let world_level_text = &level_text(game_state.difficulty_level);
+            let score_text = score_text(&game_state.settings.run_type, game_state.score, game_state.speedrun_timer_msec);
             let found_kitties_text = &format![
                 "{:<5} {:<3}", &format!["{:.2}/{:.2}", current_found_npcs, game_state.total_npcs_to_find],
                 game_state.countdown_timer_msec as u32 / 60
@@ -652,7 +1267,7 @@ pub fn kittygame_update(blit_sub: &mut BlitSubFunc, line: &mut LineFunc, rect: &
                         Some(p) => {
                             const T_BEFORE_BLINK: u32 = 60;
                             if p.duration_timer < T_BEFORE_BLINK || p.duration_timer % 6 < 3 {
-                                let (dx, dy) = ((p.x_pos - camera.current_viewing_x_offset) as i32, (p.y_pos - camera.current_viewing_y_offset) as i32);
+                                let (dx, dy) = ((p.x_pos - camera.current_viewing_x_offset).to_i32(), (p.y_pos - camera.current_viewing_y_offset).to_i32());
                                 layertext(&p.text, dx, dy, text_str);
                                 match p.icon {
                                     PopupIcon::None => {},
@@ -681,46 +1296,82 @@ pub fn kittygame_update(blit_sub: &mut BlitSubFunc, line: &mut LineFunc, rect: &
                 for (p_i, pr) in game_state.players.iter_mut().enumerate() {
                     match pr {
                         OptionallyEnabledPlayer::Enabled(p) => {
+                            if p.magnet_frames > 0 {
+                                p.magnet_frames -= 1;
+                            }
                             if !showing_modal && btns_pressed_this_frame[p_i] & BUTTON_2 != 0 {
+                                let counts_before = CARD_DEFS.map(|def| p.card_stack.count(def.card_type));
                                 let res = p.card_stack.try_use_cards();
-                                let added_t;
-                                let popup_t: Option<String>;
-                                let popup_icon: PopupIcon;
-                                match res {
-                                    game::ability_cards::AbilityCardUsageResult::NothingHappened => {
-                                        added_t = 0;
-                                        popup_t = None;
-                                        popup_icon = PopupIcon::None;
-                                    },
-                                    game::ability_cards::AbilityCardUsageResult::GainedTime(t) => {
-                                        added_t = t;
-                                        popup_t = Some(format![" +{}", t]);
-                                        popup_icon = PopupIcon::Clock;
-                                    },
-                                    game::ability_cards::AbilityCardUsageResult::EnabledFlyAndTime(t) => {
-                                        if p.character.can_fly {
-                                            added_t = t;
-                                            popup_t = Some(format![" +{}", t]);
-                                            popup_icon = PopupIcon::Clock;
-                                        } else {
-                                            p.character.can_fly = true;
-                                            added_t = t - 3;
-                                            popup_t = Some("fly!".to_string());
-                                            popup_icon = PopupIcon::None;
-                                        }
-                                        
-                                    },
-                                    game::ability_cards::AbilityCardUsageResult::EnabledWarpAndTime(t) => {
-                                        if p.character.warp_ability == WarpAbility::CannotWarp {
-                                            p.character.warp_ability = WarpAbility::CanWarp(WarpState::Charging(0));
-                                            added_t = t - 3;
-                                            popup_t = Some("hold   : warp".to_string());
-                                            popup_icon = PopupIcon::DownArrow;
-                                        } else {
-                                            added_t = 10;
-                                            popup_t = Some(format![" +{}", t]);
-                                            popup_icon = PopupIcon::Clock;
-                                        }
+                                for (def, n_before) in CARD_DEFS.iter().zip(counts_before) {
+                                    game_state.run_stats.cards_used(def.card_type, n_before - p.card_stack.count(def.card_type));
+                                }
+                                if !matches!(res, AbilityCardUsageResult::NothingHappened) {
+                                    game_state.sound_effects.push(SoundEffect::UsedCard);
+                                    if let (0, Some(tutorial)) = (p_i, &mut game_state.tutorial) {
+                                        tutorial.card_used();
+                                    }
+                                }
+                                let mut added_t = 0;
+                                let mut popup_t: Option<String> = None;
+                                let mut popup_icon = PopupIcon::None;
+                                // a combo does two things at once, and gets its name shown instead.
+                                let results = match res {
+                                    AbilityCardUsageResult::Combo(combo, n_consumed) => {
+                                        const COMBO_BANNER_FRAMES: u32 = 2 * 60;
+                                        game_state.combo_banner = Some((combo, COMBO_BANNER_FRAMES));
+                                        game_state.achievement_events.push(AchievementEvent::UsedCombo { n_cards: n_consumed[0] + n_consumed[1] });
+                                        popup_t = Some(format!["{}!", combo.name]);
+                                        combo.results(n_consumed)
+                                    }
+                                    res => [res, AbilityCardUsageResult::NothingHappened],
+                                };
+                                for res in results {
+                                    let (t, popup): (u32, Option<(String, PopupIcon)>) = match res {
+                                        // combos don't nest.
+                                        AbilityCardUsageResult::NothingHappened | AbilityCardUsageResult::Combo(..) => (0, None),
+                                        AbilityCardUsageResult::GainedTime(t) => {
+                                            (t, Some((format![" +{}", t], PopupIcon::Clock)))
+                                        },
+                                        AbilityCardUsageResult::EnabledFlyAndTime(t, unlock_cost) => {
+                                            if p.character.can_fly {
+                                                (t, Some((format![" +{}", t], PopupIcon::Clock)))
+                                            } else {
+                                                p.character.can_fly = true;
+                                                (t.saturating_sub(unlock_cost), Some(("fly!".to_string(), PopupIcon::None)))
+                                            }
+                                        },
+                                        AbilityCardUsageResult::EnabledWarpAndTime(t, unlock_cost) => {
+                                            if p.character.warp_ability == WarpAbility::CannotWarp {
+                                                p.character.warp_ability = WarpAbility::CanWarp(WarpState::Charging(0));
+                                                (t.saturating_sub(unlock_cost), Some(("hold   : warp".to_string(), PopupIcon::DownArrow)))
+                                            } else {
+                                                // already able to warp: drop a beacon here to come back to.
+                                                p.warp.beacon = Some((p.character.x_pos, p.character.y_pos));
+                                                p.warp.selected = WarpDestination::Beacon;
+                                                (10, Some(("beacon!".to_string(), PopupIcon::None)))
+                                            }
+                                        },
+                                        AbilityCardUsageResult::ShowedNearestKitty(frames) => {
+                                            p.magnet_frames += frames;
+                                            (0, Some(("magnet!".to_string(), PopupIcon::None)))
+                                        },
+                                        AbilityCardUsageResult::FrozeCountdown(frames) => {
+                                            game_state.countdown_frozen_frames += frames;
+                                            (0, Some(("freeze!".to_string(), PopupIcon::None)))
+                                        },
+                                        AbilityCardUsageResult::Dashed(frames) => {
+                                            p.character.dash_frames += frames;
+                                            (0, Some(("dash!".to_string(), PopupIcon::None)))
+                                        },
+                                        AbilityCardUsageResult::GainedAirJumps(n) => {
+                                            p.character.banked_air_jumps += n;
+                                            (0, Some((format!["+{} jump", n], PopupIcon::None)))
+                                        },
+                                    };
+                                    added_t += t;
+                                    if let (None, Some((pt, icon))) = (&popup_t, popup) {
+                                        popup_t = Some(pt);
+                                        popup_icon = icon;
                                     }
                                 }
                                 match popup_t {
@@ -731,10 +1382,10 @@ pub fn kittygame_update(blit_sub: &mut BlitSubFunc, line: &mut LineFunc, rect: &
 
                                             let vx = CARD_CLOUD_SPEED * dir.0;
                                             let vy = CARD_CLOUD_SPEED * dir.1;
-                                            Cloud::try_push_cloud(&mut game_state.clouds, p.character.x_pos + 2.0, p.character.y_pos + 3.0, vx, vy);
+                                            Cloud::try_push_cloud(&mut game_state.clouds, p.character.x_pos.to_f32() + 2.0, p.character.y_pos.to_f32() + 3.0, vx, vy);
 
                                         }
-                                        game_state.popup_text_ringbuffer.add_new_popup(p.character.x_pos - 14.0, p.character.y_pos, pt, popup_icon);
+                                        game_state.popup_text_ringbuffer.add_new_popup(p.character.x_pos - Fixed::from_int(14), p.character.y_pos, pt, popup_icon);
                                     }
                                     _ => {}
                                 }
@@ -755,8 +1406,8 @@ pub fn kittygame_update(blit_sub: &mut BlitSubFunc, line: &mut LineFunc, rect: &
                     for (i, card) in p.card_stack.cards.iter_mut().enumerate() {
                         match card {
                             Some(c) => {
-                                c.target_x = (sw as usize - 80 + 15 * i) as f32;
-                                c.target_y = 1.0;
+                                c.target_x = Fixed::from_int((sw as usize - (15 * DECK_RULES.stack_capacity + 5) + 15 * i) as i32);
+                                c.target_y = Fixed::from_int(1);
                             },
                             None => {}
                         }
@@ -776,16 +1427,7 @@ pub fn kittygame_update(blit_sub: &mut BlitSubFunc, line: &mut LineFunc, rect: &
                             
                             Some(c) => {
                                 // trace(&format!["{}", i]);
-                                blit_sub(
-                                    Spritesheet::Main,
-                                    c.x_pos as i32,
-                                    c.y_pos as i32,
-                                    c.sprite.frames[0].width as u32,
-                                    c.sprite.frames[0].height as u32,
-                                    c.sprite.frames[0].start_x as u32,
-                                    c.sprite.frames[0].start_y as u32,
-                                    BlitSubFlags { flip_x: false, flip_y: false },
-                                );
+                                drawcard(&c.card_type, c.x_pos.to_i32(), c.y_pos.to_i32(), blit_sub, rect, text_str);
                             },
                             None => {},
                         }
@@ -838,172 +1480,88 @@ pub fn kittygame_update(blit_sub: &mut BlitSubFunc, line: &mut LineFunc, rect: &
                             }
                         }
                         
-                        let mut modal_text = |st: &str, x, y| {
-                            // unsafe {*DRAW_COLORS = 0x0002}
-                            text_str(st, m.actual_position.x as i32 + x, m.actual_position.y as i32 + y, &DrawColor::Foreground);
-                        };
-
-                        let modal_offs = |x: i32, y: i32| {
-                            (m.actual_position.x as i32 + x, m.actual_position.y as i32 + y)
-                        };
-                        
-                        
+                        const BLINK_START: u32 = 50;
+                        const BLINK_TITLE_PERIOD: u32 = 17;
+                        let blink_on = text_timer < BLINK_START || (text_timer / BLINK_TITLE_PERIOD) % 2 == 0;
 
                         if ready_to_show_text {
-                            // let cursor_opt: u8;
-                            let mut btn_pressed: bool = false;
-                            if options_ready_to_select {
-                                // cursor_opt = *option;
-                                if btns_pressed_this_frame[0] & (BUTTON_1 | BUTTON_2) != 0 {
-                                    btn_pressed = true
-                                }
+                            // the pause menu takes input as soon as it's up, the rest only once they've been read.
+                            let mut pause_event = MenuEvent::Nothing;
+                            if let MenuTypes::Paused(focus) = &mut m.menu_type {
+                                pause_event = m.menu.update(focus, btns_pressed_this_frame[0]);
                             }
-                            match m.menu_type {
-                                MenuTypes::WonLevel => {
-                                    const BLINK_START: u32 = 50;
-                                    const BLINK_TITLE_PERIOD: u32 = 17;
-                                    if text_timer < BLINK_START || (text_timer / BLINK_TITLE_PERIOD) % 2 == 0 {
-                                        // modal_text("Found!!", 12, 15);
-                                        modal_text(world_level_text, 16, 12);
-                                        modal_text("Clear!", 16, 22);
-
+                            let focus = match m.menu_type {
+                                MenuTypes::Paused(focus) => Some(focus),
+                                _ => None,
+                            };
+                            draw_menu(&m.menu, focus, m.actual_position.x as i32, m.actual_position.y as i32, m.target_position.width as i32, blink_on, game_state.song_timer % 30 >= 15, blit_sub, line, rect, text_str);
 
-                                    }
-
-                                    match btn_pressed {
-                                        true => {
-                                            game_state.difficulty_level += 1;
-                                            // game_state.game_mode =
-                                            //     GameMode::NormalPlay(NormalPlayModes::MainGameplay);
-                                            game_state.game_mode =
-                                                GameMode::NormalPlay(NormalPlayModes::HoverModal(Modal::new(
-                                                    AbsoluteBoundingBox {
-                                                        x: center_x as i32 - 35,
-                                                        y: center_y as i32 - 40,
-                                                        width: 70,
-                                                        height: 50,
-                                                    },
-                                                    MenuTypes::StartLevel,
-                                                )));
-                                            game_state.regenerate_map();
-                                        }
-                                        _ => {}
-                                    }
+                            let btn_pressed = options_ready_to_select && btns_pressed_this_frame[0] & (BUTTON_1 | BUTTON_2) != 0;
+                            match m.menu_type {
+                                MenuTypes::WonLevel if btn_pressed => {
+                                    game_state.difficulty_level += 1;
+                                    game_state.achievement_events.push(AchievementEvent::ReachedLevel(game_state.difficulty_level));
+                                    game_state.regenerate_map();
+                                    game_state.game_mode = GameMode::NormalPlay(NormalPlayModes::HoverModal(start_level_modal(game_state, center_x, center_y)));
                                 },
-                                MenuTypes::StartLevel => {
-                                    modal_text(world_level_text, 16, 12);
-                                    modal_text("Start!", 16, 22);
-                                    modal_text(&format!["+{}", game_state.countdown_and_score_bonus / 60], 33, 35);
-                                    let (xx, yy) = modal_offs(25, 34);
-                                    draw_spriteframe( &spritesheet::Sprite::from_preset(&spritesheet::PresetSprites::Clock).frames[0], xx, yy, blit_sub);
-
-                                    let mut start_normal_play = false;
-                                    if text_timer > 100 {
-                                        start_normal_play = true;
-                                    }
-
-
-                                    match btn_pressed {
-                                        true => {
-                                            start_normal_play = true; 
-                                        }
-                                        _ => {}
-                                    }
-                                    if start_normal_play {
-                                        game_state.game_mode =
-                                            GameMode::NormalPlay(NormalPlayModes::MainGameplay);
-                                    }
+                                MenuTypes::StartLevel if btn_pressed || text_timer > 100 => {
+                                    game_state.game_mode = GameMode::NormalPlay(NormalPlayModes::MainGameplay);
                                 },
-                                MenuTypes::Done => {
-                                    const BLINK_START: u32 = 50;
-                                    const BLINK_TITLE_PERIOD: u32 = 17;
-                                    if text_timer < BLINK_START || (text_timer / BLINK_TITLE_PERIOD) % 2 == 0 {
-                                        modal_text("Time's Up!", 20, 14);
-                                    }
-
-                                    modal_text(&format!["End: {}", world_level_text], 8, 30);
-                                    modal_text(&score_text, 8, 40);
-                                    
-
-                                    match btn_pressed {
-                                        true => {
-                                            game_state.difficulty_level = START_DIFFICULTY_LEVEL;
-                                            game_state.game_mode = GameMode::StartScreen;
-                                        }
-                                        _ => {}
-                                    }
+                                MenuTypes::Done | MenuTypes::WonGame if btn_pressed => {
+                                    game_state.difficulty_level = START_DIFFICULTY_LEVEL;
+                                    game_state.game_mode = GameMode::RunSummary(RunSummaryPage::Moves, run_summary_menu(&game_state.run_stats, RunSummaryPage::Moves));
                                 },
-                                MenuTypes::WonGame => {
-                                    const BLINK_START: u32 = 50;
-                                    const BLINK_TITLE_PERIOD: u32 = 17;
-                                    if text_timer < BLINK_START || (text_timer / BLINK_TITLE_PERIOD) % 2 == 0 {
-                                        modal_text("YOU WON!!!", 20, 14);
-                                    }
-
-                                    modal_text(&format!["End: {}", world_level_text], 8, 30);
-                                    modal_text(&score_text, 8, 40);
-                                    match game_state.settings.run_type {
-                                        RunType::Random => {
-                                            modal_text(&format!["Time: {}s", game_state.speedrun_timer_msec / 60], 8, 50);
+                                MenuTypes::TutorialDone if btn_pressed => {
+                                    // straight on to a real run.
+                                    game_state.tutorial = None;
+                                    game_state.settings.run_type = RunType::Random;
+                                    game_state.difficulty_level = START_DIFFICULTY_LEVEL;
+                                    game_state.game_mode = select_screen(game_state, SelectMenuFocuses::Start);
+                                },
+                                MenuTypes::StartGameMessage if btn_pressed => {
+                                    game_state.game_mode = GameMode::NormalPlay(NormalPlayModes::MainGameplay);
+                                },
+                                MenuTypes::Paused(_) => {
+                                    // start resumes too, whatever's picked.
+                                    let picked = match pause_event {
+                                        _ if btns_pressed_this_frame.iter().any(|b| b & BUTTON_START != 0) => Some(PauseMenuFocuses::Resume),
+                                        MenuEvent::Pressed(focus) => Some(focus),
+                                        _ => None,
+                                    };
+                                    match picked {
+                                        Some(PauseMenuFocuses::Resume) => {
+                                            game_state.end_pause();
+                                            game_state.game_mode = GameMode::NormalPlay(NormalPlayModes::MainGameplay);
                                         },
-                                        _ => {}
-                                    }
-
-                                    match btn_pressed {
-                                        true => {
+                                        Some(PauseMenuFocuses::RestartLevel) => {
+                                            game_state.end_pause();
+                                            game_state.restart_level();
+                                            game_state.game_mode = GameMode::NormalPlay(NormalPlayModes::HoverModal(start_level_modal(game_state, center_x, center_y)));
+                                        },
+                                        Some(PauseMenuFocuses::QuitToTitle) => {
+                                            game_state.end_pause();
                                             game_state.difficulty_level = START_DIFFICULTY_LEVEL;
                                             game_state.game_mode = GameMode::StartScreen;
-                                        }
-                                        _ => {}
+                                        },
+                                        Some(PauseMenuFocuses::Options) => {
+                                            // still paused until the pause menu is left.
+                                            game_state.game_mode = options_screen(&game_state.options, OptionsReturnTo::Pause);
+                                        },
+                                        None => {},
                                     }
                                 },
-                                MenuTypes::StartGameMessage => {
-                                    modal_text("-- GOAL --", 30, 10);
-                                    modal_text("Find all the", 20, 25);
-                                    modal_text("kitties in time!", 10, 40);
-                                    modal_text("-- CONTROLS --", 14, 100);
-
-
-
-                                    modal_text("     to move,", 24, 114); 
-                                    modal_text(" =jump,  =card", 16, 126);
-                                    let (xx, yy) = modal_offs(0, 0);
- 
-                                    if game_state.song_timer % 30 >= 15 {
-                                        // unsafe {*DRAW_COLORS = 0x0004}
-                                        // text_bytes(&[b'\x84'], xx+32, yy+114);
-                                        // text_bytes(&[ b'\x85'], xx+48, yy+114);
-                                        // text_bytes(&[b'\x80'], xx+15, yy+126);
-                                        // text_bytes(&[ b'\x81'], xx+79, yy+126);
-                                        // text_str("<", xx+32, yy+114, &DrawColor::MainKitty);
-                                        // text_str(">", xx+48, yy+114, &DrawColor::MainKitty);
-                                        // text_str("x", xx+15, yy+126, &DrawColor::MainKitty);
-                                        // text_str("z", xx+79, yy+126, &DrawColor::MainKitty);
-                                    }
-                                    
-                                    draw_spriteframe( &spritesheet::Sprite::from_preset(&spritesheet::PresetSprites::CatHead).frames[0], xx+20, yy+62, blit_sub);
-
-                                    modal_text(" = # kittes", 28, 62);
-                                    draw_spriteframe( &spritesheet::Sprite::from_preset(&spritesheet::PresetSprites::Clock).frames[0], xx+20, yy+78, blit_sub);
-
-                                    modal_text(" = time left", 28, 78);
-                                    match btn_pressed {
-                                        true => {
-                                            game_state.game_mode = GameMode::NormalPlay(NormalPlayModes::MainGameplay);
-                                        }
-                                        _ => {}
-                                    }
-                                }
-                            }     
+                                _ => {},
+                            }
                         }
                     }
                 }
             } else {
 
                 // HELP TEXT AT START OF GAME
-                if game_state.difficulty_level == 1 && game_state.countdown_timer_msec == COUNTDOWN_TIMER_START - 1 && game_state.tutorial_text_counter == 0 {
+                if game_state.difficulty_level == 1 && game_state.countdown_timer_msec == game_state.settings.difficulty.setting().countdown_timer_start - 1 && game_state.tutorial_text_counter == 0 && game_state.options.tutorial && game_state.tutorial.is_none() {
                     game_state.tutorial_text_counter += 1;
-                    game_state.game_mode = GameMode::NormalPlay(NormalPlayModes::HoverModal(Modal::new(
+                    game_state.game_mode = GameMode::NormalPlay(NormalPlayModes::HoverModal(open_modal(
+                            game_state,
                         AbsoluteBoundingBox {
                             x: center_x as i32 - 70,
                             y: center_y as i32 - 70,
@@ -1015,28 +1573,59 @@ pub fn kittygame_update(blit_sub: &mut BlitSubFunc, line: &mut LineFunc, rect: &
                 }
                 
 
+                // ------- TUTORIAL STEPS -----------
+                if let (Some(tutorial), OptionallyEnabledPlayer::Enabled(p)) = (&mut game_state.tutorial, &game_state.players[0]) {
+                    if tutorial.update(&p.character, current_found_npcs) {
+                        game_state.popup_text_ringbuffer.add_new_popup(p.character.x_pos - Fixed::from_int(8), p.character.y_pos, "nice!".to_string(), PopupIcon::None);
+                        if tutorial.step == TutorialStep::Done {
+                            game_state.song_idx = 0;
+                            game_state.song_timer = 0;
+                            game_state.game_mode = GameMode::NormalPlay(NormalPlayModes::HoverModal(open_modal(
+                            game_state,
+                                AbsoluteBoundingBox {
+                                    x: center_x as i32 - 48,
+                                    y: center_y as i32 - 40,
+                                    width: 96,
+                                    height: 50,
+                                },
+                                MenuTypes::TutorialDone
+                            )));
+                        }
+                    }
+                }
+
                 // ------- LEVEL WIN CONDITION -----------
-                if game_state.total_npcs_to_find == current_found_npcs {
+                // the tutorial is over when its steps are, not when the lizard's found.
+                if game_state.total_npcs_to_find == current_found_npcs && game_state.tutorial.is_none() {
+                    game_state.achievement_events.push(AchievementEvent::WonLevel { countdown_timer_msec: game_state.countdown_timer_msec });
+                    game_state.run_stats.end_level();
                     if game_state.difficulty_level == FINAL_LEVEL {
+                        game_state.record_high_score(true);
                         game_state.game_mode =
-                        GameMode::NormalPlay(NormalPlayModes::HoverModal(Modal::new(
+                        GameMode::NormalPlay(NormalPlayModes::HoverModal(open_modal(
+                            game_state,
                             AbsoluteBoundingBox {
                                 x: 25,
-                                y: 35,
+                                y: 30,
                                 width: 110,
-                                height: 65,
+                                height: 85,
                             },
                             MenuTypes::WonGame
                         )));
+                        game_state.achievement_events.push(AchievementEvent::WonGame {
+                            run_type: game_state.settings.run_type,
+                            speedrun_timer_msec: game_state.speedrun_timer_msec,
+                        });
                         game_state.song_idx = 0;
                     } else {
                         game_state.game_mode =
-                        GameMode::NormalPlay(NormalPlayModes::HoverModal(Modal::new(
+                        GameMode::NormalPlay(NormalPlayModes::HoverModal(open_modal(
+                            game_state,
                             AbsoluteBoundingBox {
-                                x: center_x as i32 - 40,
+                                x: center_x as i32 - 48,
                                 y: center_y as i32 - 40,
-                                width: 80,
-                                height: 40,
+                                width: 96,
+                                height: 50,
                             },
                             MenuTypes::WonLevel
                         )));
@@ -1046,28 +1635,43 @@ pub fn kittygame_update(blit_sub: &mut BlitSubFunc, line: &mut LineFunc, rect: &
                     game_state.song_timer = 0;
                 }
 
-                // PROGRESS TIME, CHECK FOR GAME END
-                if !game_state.countdown_paused {
+                // PROGRESS TIME, CHECK FOR GAME END. There's no rush in the tutorial.
+                if !game_state.countdown_paused && game_state.tutorial.is_none() {
                     game_state.speedrun_timer_msec += 1;
-                    game_state.countdown_timer_msec -= 1;
+                    // freeze cards hold the countdown where it is.
+                    if game_state.countdown_frozen_frames > 0 {
+                        game_state.countdown_frozen_frames -= 1;
+                    } else {
+                        game_state.countdown_timer_msec -= 1;
+                    }
+                    game_state.run_stats.tick(game_state.countdown_timer_msec);
             
                     // ---- LOSE CONDITION ----
                     if game_state.countdown_timer_msec <= 0 {
             
                         game_state.song_idx = 0;
+                        game_state.run_stats.end_level();
+                        game_state.record_high_score(false);
             
-                        game_state.game_mode = GameMode::NormalPlay(NormalPlayModes::HoverModal(Modal::new(
+                        game_state.game_mode = GameMode::NormalPlay(NormalPlayModes::HoverModal(open_modal(
+                            game_state,
                             AbsoluteBoundingBox {
                                 x: 15,
-                                y: 50,
+                                y: 44,
                                 width: 130,
-                                height: 60,
+                                height: 72,
                             },
                             MenuTypes::Done
                         )));
                     }
                 }
 
+                // PAUSE, unless the level just ended or the tutorial just came up.
+                let still_playing = matches!(game_state.game_mode, GameMode::NormalPlay(NormalPlayModes::MainGameplay));
+                if still_playing && btns_pressed_this_frame.iter().any(|b| b & BUTTON_START != 0) {
+                    game_state.paused_since_frame = Some(game_state.frame_count);
+                    game_state.game_mode = GameMode::NormalPlay(NormalPlayModes::HoverModal(pause_modal(game_state, center_x, center_y, PauseMenuFocuses::Resume)));
+                }
 
 
                 // DRAW SCORE, LEVEL, # KITTIES during normal play
@@ -1078,8 +1682,90 @@ pub fn kittygame_update(blit_sub: &mut BlitSubFunc, line: &mut LineFunc, rect: &
                 draw_spriteframe( &spritesheet::Sprite::from_preset(&spritesheet::PresetSprites::Clock).frames[0], 48, TOP_UI_TEXT_Y - 1, blit_sub);
                 draw_spriteframe( &spritesheet::Sprite::from_preset(&spritesheet::PresetSprites::CatHead).frames[0], 1, TOP_UI_TEXT_Y + 1, blit_sub);
 
+                // DRAW CARD EFFECTS: the frozen countdown, what the last combo did,
+                // and where the nearest lost kitty is while a magnet lasts.
+                if game_state.countdown_frozen_frames > 0 {
+                    layertext(&format!["frozen {}s", game_state.countdown_frozen_frames / 60 + 1], 1, TOP_UI_TEXT_Y + 11, text_str);
+                }
+                if let Some((combo, frames_left)) = &mut game_state.combo_banner {
+                    layertext(&format!["{}!", combo.name], 1, TOP_UI_TEXT_Y + 21, text_str);
+                    layertext(combo.description, 1, TOP_UI_TEXT_Y + 31, text_str);
+                    *frames_left -= 1;
+                    if *frames_left == 0 {
+                        game_state.combo_banner = None;
+                    }
+                }
+                // the tutorial's hint, where it's about, or at the edge of the screen nearest it.
+                if let Some(tutorial) = game_state.tutorial.as_ref().filter(|t| t.step != TutorialStep::Done) {
+                    let hint = tutorial.step.hint(&game_state.options.controls);
+                    let (x, y) = game_state.camera.cvt_world_to_screen_coords(Fixed::from_int(tutorial.hint_x), Fixed::from_int(tutorial.hint_y));
+                    let hint_width = hint.len() as i32 * 8;
+                    layertext(
+                        &hint,
+                        (x.to_i32() - hint_width / 2).clamp(1, (sw as i32 - hint_width - 1).max(1)),
+                        y.to_i32().clamp(TOP_UI_TEXT_Y + 11, sh as i32 + BOTTOM_UI_TEXT_Y_OFFSET - 10),
+                        text_str
+                    );
+                }
+                if let OptionallyEnabledPlayer::Enabled(p) = &game_state.players[player_idx as usize] {
+                    if p.magnet_frames > 0 {
+                        let nearest = game_state.npcs.iter().filter(|npc| npc.following_i.is_none()).min_by_key(|npc| {
+                            let dx = (npc.x_pos - p.character.x_pos).to_i32() as i64;
+                            let dy = (npc.y_pos - p.character.y_pos).to_i32() as i64;
+                            dx * dx + dy * dy
+                        });
+                        if let Some(npc) = nearest {
+                            // blink over their head, or at the edge of the screen nearest them if they're off it.
+                            let (x, y) = game_state.camera.cvt_world_to_screen_coords(npc.x_pos, npc.y_pos);
+                            if (p.magnet_frames / 8) % 2 == 0 {
+                                draw_spriteframe(
+                                    &spritesheet::Sprite::from_preset(&spritesheet::PresetSprites::CatHead).frames[0],
+                                    x.to_i32().clamp(1, sw as i32 - 7),
+                                    (y.to_i32() - 8).clamp(TOP_UI_TEXT_Y + 9, sh as i32 + BOTTOM_UI_TEXT_Y_OFFSET - 8),
+                                    blit_sub
+                                );
+                            }
+                        }
+                    }
+
+                    // while a warp charges, show how far along it is over our head, and where it'll go.
+                    let charge = match p.character.warp_ability {
+                        WarpAbility::CanWarp(WarpState::Charging(t)) if t > 0 => Some(t),
+                        WarpAbility::CanWarp(WarpState::Ready) => Some(WARP_CHARGE_FRAMES),
+                        _ => None,
+                    };
+                    if let Some(t) = charge {
+                        const WARP_BAR_WIDTH: u32 = 20;
+                        let (x, y) = game_state.camera.cvt_world_to_screen_coords(p.character.x_pos, p.character.y_pos);
+                        let (x, y) = (x.to_i32() + 4, y.to_i32());
+                        let bar_x = x - WARP_BAR_WIDTH as i32 / 2;
+                        rect(bar_x, y - 5, WARP_BAR_WIDTH, 3, &DrawColor::Foreground);
+                        rect(bar_x + 1, y - 4, WARP_BAR_WIDTH - 2, 1, &DrawColor::Background);
+                        rect(bar_x + 1, y - 4, (WARP_BAR_WIDTH - 2) * t as u32 / WARP_CHARGE_FRAMES as u32, 1, &DrawColor::MainKitty);
+                        let label = p.warp.label();
+                        let label_text = match t >= WARP_CHARGE_FRAMES {
+                            true => format!["<{}>", label],
+                            false => label,
+                        };
+                        layertext(&label_text, x - label_text.len() as i32 * 4, y - 14, text_str);
+                    }
+                }
+
                 
             }
+
+            // ACHIEVEMENT TOASTS, one at a time, over whatever else is up.
+            if let Some(achievement) = game_state.achievement_toasts.first() {
+                const ACHIEVEMENT_TOAST_FRAMES: u32 = 3 * 60;
+                let y = sh as i32 + BOTTOM_UI_TEXT_Y_OFFSET - 24;
+                layertext("Achievement!", sw as i32 / 2 - 48, y, text_str);
+                layertext(achievement.name(), sw as i32 / 2 - achievement.name().len() as i32 * 4, y + 10, text_str);
+                game_state.achievement_toast_timer += 1;
+                if game_state.achievement_toast_timer >= ACHIEVEMENT_TOAST_FRAMES {
+                    game_state.achievement_toast_timer = 0;
+                    game_state.achievement_toasts.remove(0);
+                }
+            }
         }
         GameMode::StartScreen => {
             
@@ -1104,15 +1790,18 @@ pub fn kittygame_update(blit_sub: &mut BlitSubFunc, line: &mut LineFunc, rect: &
                 );
                 // unsafe{*DRAW_COLORS = 0x0002};
                 if game_state.song_timer % 30 >= 15 {
-                    text_str("Any key: play", center_x as i32 - 50, 110, &DrawColor::MainKitty);
+                    text_str("Any key: play", center_x as i32 - 50, 108, &DrawColor::MainKitty);
                 }
+                text_str(&format!["{}: options", game_state.options.controls.key_name(BUTTON_2)], center_x as i32 - 50, 117, &DrawColor::MainKitty);
                 
-                text_str("by CanyonTurtle", center_x as i32 - 55, 125, &DrawColor::MainKitty);
-                text_str(" & BurntSugar  ", center_x as i32 - 50, 135, &DrawColor::MainKitty);
+                text_str("by CanyonTurtle", center_x as i32 - 55, 127, &DrawColor::MainKitty);
+                text_str(" & BurntSugar  ", center_x as i32 - 50, 136, &DrawColor::MainKitty);
                 text_str(&format!["ver. {}.{}.{}", MAJOR_VERSION, MINOR_VERSION, INCR_VERSION], 40, 150, &DrawColor::MainKitty);
-                if btns_pressed_this_frame[0] != 0 {
+                if btns_pressed_this_frame[0] & BUTTON_2 != 0 {
+                    game_state.game_mode = options_screen(&game_state.options, OptionsReturnTo::StartScreen);
+                } else if btns_pressed_this_frame[0] != 0 {
                     // game_state.game_mode = GameMode::NormalPlay(NormalPlayModes::MainGameplay);
-                    game_state.game_mode = GameMode::SelectScreen(SelectSetup{current_selection: SelectMenuFocuses::RunType});
+                    game_state.game_mode = select_screen(game_state, SelectMenuFocuses::RunType);
                     // game_state.regenerate_map();
                 }
             }
@@ -1126,140 +1815,140 @@ pub fn kittygame_update(blit_sub: &mut BlitSubFunc, line: &mut LineFunc, rect: &
             
         },
         GameMode::SelectScreen(select_setup) => {
+            let menu_x = (sw as i32 - 160) / 2;
+            draw_modal_bg(&AbsoluteBoundingBox{x: 0f32, y: 0f32, width: 159f32, height: 159f32}, 0, &DrawColor::Foreground, line, rect);
 
-            const BOX_WIDTH: i32 = 120;
-            let box_margin = (sw as i32 - BOX_WIDTH) / 2;
-            const BOX_HEIGHT: i32 = 60;
-
-            const RUN_TYPE_Y: i32 = 66;
-            // const DIFFICULTY_Y: i32 = 33;
-            // const CHARACTER_Y: i32 = 46;
-            const START_Y: i32 = 130;
-            let start_x: i32 = center_x as i32 - 25;
-
-            // const START_WIDTH: i32 = 60;
-            // const START_HEIGHT: i32 = 19;
-
-            const SETTING_GROUP_INLAY_DIST: i32 = 5;
-
-            // let mut selected_box_dims = (0, 0, 0, 0);
-
-            fn draw_selected_box(dims: (i32, i32, i32, i32), style: u8, color: &DrawColor, line: &mut LineFunc, rect: &mut RectFunc) {
-                draw_modal_bg(&AbsoluteBoundingBox{x: dims.0 as f32, y: dims.1 as f32, width: dims.2 as f32, height: dims.3 as f32}, style, color, line, rect);
+            let event = select_setup.menu.update(&mut select_setup.current_selection, btns_pressed_this_frame[0]);
+            let settings = &mut game_state.settings;
+            match event {
+                MenuEvent::Changed(SelectMenuFocuses::Difficulty, i) => settings.difficulty = Difficulty::ALL[i as usize],
+                MenuEvent::Changed(SelectMenuFocuses::CharacterSelect, i) => settings.characters[0] = PLAYABLE_CHARACTERS[i as usize].character,
+                MenuEvent::Changed(SelectMenuFocuses::RunType, i) => {
+                    settings.run_type = match i {
+                        0 => RunType::Random,
+                        1 => RunType::Speedrun(0),
+                        _ => RunType::Tutorial,
+                    };
+                    select_setup.menu.rows[SELECT_MENU_SEED_ROW] = seed_row(&settings.run_type);
+                },
+                MenuEvent::Changed(SelectMenuFocuses::Seed, n) => settings.run_type = RunType::Speedrun(n),
+                _ => {},
             }
-
-            // draw background for menus
-            draw_modal_bg(&AbsoluteBoundingBox{x: 0f32, y: 0f32, width: 159f32, height: 159f32}, 0, &DrawColor::Foreground, line, rect);
-            draw_selected_box((box_margin, RUN_TYPE_Y, BOX_WIDTH, BOX_HEIGHT), 0, &DrawColor::Foreground, line, rect);
-
-            // draw options that get overdrawn later if they're not selected
-            // layertext("Run Type", box_margin + SETTING_GROUP_INLAY_DIST, RUN_TYPE_Y + SETTING_GROUP_INLAY_DIST);
-            // layertext("Difficulty", box_margin + SETTING_GROUP_INLAY_DIST, DIFFICULTY_Y + SETTING_GROUP_INLAY_DIST);
-            // layertext("Character", box_margin + SETTING_GROUP_INLAY_DIST, CHARACTER_Y + SETTING_GROUP_INLAY_DIST);
-
-            // if btns_pressed_this_frame[0] & BUTTON_DOWN != 0 {
-            //     select_setup.current_selection = match select_setup.current_selection {
-            //         SelectMenuFocuses::RunType => SelectMenuFocuses::StartGameBtn,
-            //         // SelectMenuFocuses::Difficulty => SelectMenuFocuses::CharacterSelect,
-            //         // SelectMenuFocuses::CharacterSelect => SelectMenuFocuses::StartGameBtn,
-            //         SelectMenuFocuses::StartGameBtn => SelectMenuFocuses::StartGameBtn,
-            //     }   
-            // }
-
-            // if btns_pressed_this_frame[0] & BUTTON_UP != 0 {
-            //     select_setup.current_selection = match select_setup.current_selection {
-            //         SelectMenuFocuses::RunType => SelectMenuFocuses::RunType,
-            //         // SelectMenuFocuses::Difficulty => SelectMenuFocuses::RunType,
-            //         // SelectMenuFocuses::CharacterSelect => SelectMenuFocuses::Difficulty,
-            //         SelectMenuFocuses::StartGameBtn => SelectMenuFocuses::RunType,
-            //     }   
-            // }
-
-            match select_setup.current_selection {
-                SelectMenuFocuses::RunType => {
-                    if btns_pressed_this_frame[0] & (BUTTON_RIGHT | BUTTON_LEFT) != 0 {
-                        game_state.settings.run_type = match game_state.settings.run_type {
-                            game::game_state::RunType::Random => game::game_state::RunType::Speedrun(0),
-                            game::game_state::RunType::Speedrun(_) => game::game_state::RunType::Random,
-                        }   
+            // everyone else picks their own, with their own left and right.
+            if select_setup.current_selection == SelectMenuFocuses::CharacterSelect {
+                for (i, character) in settings.characters.iter_mut().enumerate().skip(1) {
+                    if btns_pressed_this_frame[i] & BUTTON_LEFT != 0 {
+                        *character = character.cycled(false);
                     }
-                    // draw box around run type
-                    draw_selected_box((box_margin, RUN_TYPE_Y, BOX_WIDTH, BOX_HEIGHT), 1, &DrawColor::MainKitty, line, rect);
-                    // layertext("Run Type", box_margin + SETTING_GROUP_INLAY_DIST, RUN_TYPE_Y + SETTING_GROUP_INLAY_DIST);
-
-                    if game_state.song_timer % 30 >= 15 {
-                        // unsafe {*DRAW_COLORS = 0x0004}
-                        // text_bytes(&[b'\x85'], 132, 72);
-                        // text_bytes(&[b'\x80'], 45, 136);
-                        text_str(">", center_x as i32 + 52, 72, &DrawColor::MainKitty);
-                        text_str("x", start_x - 10, 136, &DrawColor::MainKitty);
-                    }
-
-                    if btns_pressed_this_frame[0] & (BUTTON_2) != 0 {
-                        if let game::game_state::RunType::Speedrun(n) = game_state.settings.run_type {
-                            game_state.settings.run_type = game::game_state::RunType::Speedrun(n + 1);
-                        }
+                    if btns_pressed_this_frame[i] & BUTTON_RIGHT != 0 {
+                        *character = character.cycled(true);
                     }
+                }
+            }
 
-                    layertext("Start!", start_x + SETTING_GROUP_INLAY_DIST + 3, START_Y + SETTING_GROUP_INLAY_DIST + 1, text_str);
+            select_setup.menu.rows[SELECT_MENU_CHARACTER_ROW].set_icons(joined_character_icons(&game_state.settings, &game_state.players));
+            draw_menu(&select_setup.menu, Some(select_setup.current_selection), menu_x, 0, 160, true, game_state.song_timer % 30 >= 15, blit_sub, line, rect, text_str);
+            render_title(&game_state, sw as i32 / 2 - 76, TITLE_Y - 8, blit_sub);
 
-                    if btns_pressed_this_frame[0] & BUTTON_1 != 0 {
-                        game_state.game_mode = GameMode::NormalPlay(NormalPlayModes::MainGameplay);
-                        if let game::game_state::RunType::Speedrun(n) = game_state.settings.run_type {
-                            game_state.rng = GameRng::FixedSeed(Rng::new_from_seed(n), Rng::new_from_seed(n));
-                        }
-                        game_state.regenerate_map();
+            // whatever's picked, x starts the game, unless it's the options.
+            if let MenuEvent::Pressed(SelectMenuFocuses::Options) = event {
+                game_state.game_mode = options_screen(&game_state.options, OptionsReturnTo::SelectScreen);
+            } else if let MenuEvent::Pressed(SelectMenuFocuses::Achievements) = event {
+                game_state.game_mode = GameMode::AchievementsScreen(achievements_menu(&game_state.achievements));
+            } else if btns_pressed_this_frame[0] & BUTTON_1 != 0 {
+                game_state.game_mode = GameMode::NormalPlay(NormalPlayModes::MainGameplay);
+                if let RunType::Speedrun(n) = game_state.settings.run_type {
+                    game_state.rng = GameRng::FixedSeed(Rng::new_from_seed(n), Rng::new_from_seed(n));
+                }
+                game_state.run_stats = RunStats::new();
+                // everyone starts fresh, as who they picked.
+                for (i, optional_player) in game_state.players.iter_mut().enumerate() {
+                    if let OptionallyEnabledPlayer::Enabled(p) = optional_player {
+                        *p = Player::new(game_state.settings.characters[i]);
                     }
-                },
-                // SelectMenuFocuses::Difficulty => {
-                //     // draw box around difficulty
-                //     draw_selected_box((box_margin, DIFFICULTY_Y, BOX_WIDTH, BOX_HEIGHT));
-                //     layertext("Difficulty", box_margin + SETTING_GROUP_INLAY_DIST, DIFFICULTY_Y + SETTING_GROUP_INLAY_DIST);
-                // },
-                // SelectMenuFocuses::CharacterSelect => {
-                //     draw_selected_box((box_margin, CHARACTER_Y, BOX_WIDTH, BOX_HEIGHT));
-                //     layertext("Character", box_margin + SETTING_GROUP_INLAY_DIST, CHARACTER_Y + SETTING_GROUP_INLAY_DIST);
-
-                // },
-                // SelectMenuFocuses::StartGameBtn => {
-                //     draw_selected_box((START_X, START_Y, START_WIDTH, START_HEIGHT), 1, 0x0004);
-
-
-                //     if game_state.song_timer % 30 >= 15 {
-                //         layertext("Start!", START_X + SETTING_GROUP_INLAY_DIST + 3, START_Y + SETTING_GROUP_INLAY_DIST + 1);
-                //     }
-                // }
+                }
+                if let Some(replay) = &mut game_state.replay {
+                    replay.record_run_start(&game_state.settings);
+                }
+                game_state.regenerate_map();
             }
+        }
+        GameMode::OptionsScreen(options_setup) => {
+            let menu_x = (sw as i32 - 160) / 2;
+            draw_modal_bg(&AbsoluteBoundingBox{x: 0f32, y: 0f32, width: 159f32, height: 159f32}, 0, &DrawColor::Foreground, line, rect);
 
-            match game_state.settings.run_type {
-                game::game_state::RunType::Random => {
-                    layertext("Normal Mode", box_margin + SETTING_GROUP_INLAY_DIST + 20, RUN_TYPE_Y + SETTING_GROUP_INLAY_DIST, text_str);
-                    layertext("Random levels.", box_margin + SETTING_GROUP_INLAY_DIST, RUN_TYPE_Y + SETTING_GROUP_INLAY_DIST + 15, text_str);
-                    layertext("Find kitties", box_margin + SETTING_GROUP_INLAY_DIST, RUN_TYPE_Y + SETTING_GROUP_INLAY_DIST + 25, text_str);
-                    layertext("in time!", box_margin + SETTING_GROUP_INLAY_DIST, RUN_TYPE_Y + SETTING_GROUP_INLAY_DIST + 35, text_str);
-
+            let options = &mut game_state.options;
+            match options_setup.menu.update(&mut options_setup.current_selection, btns_pressed_this_frame[0]) {
+                MenuEvent::Changed(OptionsMenuFocuses::MusicVolume, v) => options.music_volume = v as u8,
+                MenuEvent::Changed(OptionsMenuFocuses::SfxVolume, v) => options.sfx_volume = v as u8,
+                MenuEvent::Changed(OptionsMenuFocuses::Palette, i) => options.set_palette_choice(i as usize),
+                MenuEvent::Changed(OptionsMenuFocuses::SwapButtons, swapped) => options.controls = match swapped != 0 {
+                    true => ControlScheme::Swapped,
+                    false => ControlScheme::Standard,
                 },
-                game::game_state::RunType::Speedrun(n) => {
-                    layertext("Seed Mode", box_margin + SETTING_GROUP_INLAY_DIST + 25, RUN_TYPE_Y + SETTING_GROUP_INLAY_DIST, text_str);
-                    layertext("Fixed maps", box_margin + SETTING_GROUP_INLAY_DIST, RUN_TYPE_Y + SETTING_GROUP_INLAY_DIST + 15, text_str);
-                    layertext("For speedruns!", box_margin + SETTING_GROUP_INLAY_DIST, RUN_TYPE_Y + SETTING_GROUP_INLAY_DIST + 25, text_str);
-                    layertext(&format![" for seed: {}", n],box_margin + SETTING_GROUP_INLAY_DIST + 1, RUN_TYPE_Y + SETTING_GROUP_INLAY_DIST + 35, text_str);
-                    if game_state.song_timer % 30 >= 15 {
-                        // unsafe {*DRAW_COLORS = 0x0004}
-
-                        // text_bytes(&[b'\x81'], box_margin + SETTING_GROUP_INLAY_DIST, RUN_TYPE_Y + SETTING_GROUP_INLAY_DIST + 35);
-                        text_str("z", box_margin + SETTING_GROUP_INLAY_DIST, RUN_TYPE_Y + SETTING_GROUP_INLAY_DIST + 35, &DrawColor::MainKitty);
+                MenuEvent::Changed(OptionsMenuFocuses::ScreenScale, n) => options.screen_scale = n as u8,
+                MenuEvent::Changed(OptionsMenuFocuses::TouchOverlay, on) => options.show_touch_overlay = on != 0,
+                MenuEvent::Changed(OptionsMenuFocuses::Tutorial, on) => options.tutorial = on != 0,
+                _ => {},
+            }
+            draw_menu(&options_setup.menu, Some(options_setup.current_selection), menu_x, 0, 160, true, game_state.song_timer % 30 >= 15, blit_sub, line, rect, text_str);
+
+            // either button goes back, so swapping them can't strand anyone here.
+            if btns_pressed_this_frame[0] & (BUTTON_1 | BUTTON_2) != 0 {
+                game_state.save_dirty = true;
+                game_state.game_mode = match options_setup.return_to {
+                    OptionsReturnTo::StartScreen => GameMode::StartScreen,
+                    OptionsReturnTo::SelectScreen => select_screen(game_state, SelectMenuFocuses::Options),
+                    OptionsReturnTo::Pause => GameMode::NormalPlay(NormalPlayModes::HoverModal(pause_modal(game_state, center_x, center_y, PauseMenuFocuses::Options))),
+                };
+            }
+        }
+        GameMode::RunSummary(page, menu) => {
+            let menu_x = (sw as i32 - 160) / 2;
+            draw_modal_bg(&AbsoluteBoundingBox{x: 0f32, y: 0f32, width: 159f32, height: 159f32}, 0, &DrawColor::Foreground, line, rect);
 
-                    }
-                },
+            let mut focus = ();
+            if let MenuEvent::Changed((), i) = menu.update(&mut focus, btns_pressed_this_frame[0]) {
+                // every row but the page's name changes, so it's a new menu.
+                *page = RunSummaryPage::ALL[i as usize];
+                *menu = run_summary_menu(&game_state.run_stats, *page);
             }
-            
+            draw_menu(menu, Some(()), menu_x, 0, 160, true, game_state.song_timer % 30 >= 15, blit_sub, line, rect, text_str);
 
-            // Draw box around selection
-            // draw_modal_bg(&AbsoluteBoundingBox{x: selected_box_dims.0 as f32, y: selected_box_dims.1 as f32, width: selected_box_dims.2 as f32, height: selected_box_dims.3 as f32}, 1);
+            if btns_pressed_this_frame[0] & (BUTTON_1 | BUTTON_2) != 0 {
+                game_state.game_mode = GameMode::StartScreen;
+            }
+        }
+        GameMode::AchievementsScreen(menu) => {
+            let menu_x = (sw as i32 - 160) / 2;
+            draw_modal_bg(&AbsoluteBoundingBox{x: 0f32, y: 0f32, width: 159f32, height: 159f32}, 0, &DrawColor::Foreground, line, rect);
+            draw_menu(menu, None, menu_x, 0, 160, true, true, blit_sub, line, rect, text_str);
 
-            render_title(&game_state, sw as i32 / 2 - 76, TITLE_Y - 8, blit_sub);
-            
+            if btns_pressed_this_frame[0] & (BUTTON_1 | BUTTON_2) != 0 {
+                game_state.game_mode = select_screen(game_state, SelectMenuFocuses::Achievements);
+            }
+        }
+    }
+    game_state.handle_achievement_events();
+    play_sound_effects(&game_state.sound_effects, game_state.options.sfx_volume, tone);
+    game_state.sound_effects.clear();
+
+    // ----------- CHECK FOR DESYNCS -----------
+    let log_every = unsafe { DEBUG_OPTIONS.log_state_hash_every_n_frames };
+    let log_now = log_every != 0 && game_state.frame_count % log_every == 0;
+    let checkpoint_now = match &game_state.replay {
+        Some(replay) => replay.needs_checkpoint(),
+        None => false,
+    };
+    if log_now || checkpoint_now {
+        let state_hash = game_state.state_hash();
+        if log_now {
+            log(&format!["frame {} state {:016x}", game_state.frame_count, state_hash]);
+        }
+        if let Some(replay) = &mut game_state.replay {
+            if checkpoint_now {
+                replay.record_checkpoint(state_hash);
+            }
         }
     }
 }
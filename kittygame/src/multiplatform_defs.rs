@@ -3,6 +3,8 @@
 
 pub const BUTTON_1: u8 = 1;
 pub const BUTTON_2: u8 = 2;
+// start or escape. wasm-4 gamepads don't have one, so wasm4_plat sends it for up and down pressed together.
+pub const BUTTON_START: u8 = 4;
 pub const BUTTON_LEFT: u8 = 16;
 pub const BUTTON_RIGHT: u8 = 32;
 pub const BUTTON_UP: u8 = 64;
@@ -28,6 +30,14 @@ pub struct Pallette {
     pub background: u32,
 }
 
+/// Which voice a tone plays on. These are wasm-4's; other platforms can sound them however they like.
+pub enum ToneChannel {
+    Pulse1,
+    Pulse2,
+    Triangle,
+    Noise,
+}
+
 pub enum DrawColor {
     MainKitty,
     PigsLizards,
@@ -40,4 +50,7 @@ pub type LineFunc<'a> = dyn FnMut(i32, i32, i32, i32, &DrawColor) + 'a;
 pub type TextStrFunc<'a> = dyn FnMut(&str, i32, i32, &DrawColor) + 'a;
 pub type RectFunc<'a> = dyn FnMut(i32, i32, u32, u32, &DrawColor) + 'a;
 pub type SwitchPalletteFunc<'a> = dyn FnMut(&Pallette) + 'a;
+pub type LogFunc<'a> = dyn FnMut(&str) + 'a;
+// frequency in hz, duration in frames, and volume out of 100.
+pub type ToneFunc<'a> = dyn FnMut(u32, u32, u32, &ToneChannel) + 'a;
 
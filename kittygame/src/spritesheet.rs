@@ -110,6 +110,7 @@ pub struct Sprite {
 }
 
 #[allow(dead_code)]
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
 pub enum PresetSprites {
     MainCat,
     Kitty1,
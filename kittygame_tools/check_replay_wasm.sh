@@ -0,0 +1,27 @@
+#!/bin/bash
+# Records a few long replays with a native replay_verify, then plays them back with replay_verify built
+# for wasm32-wasip1, so a desync between 64 bit native and 32 bit wasm builds shows up as a failure.
+#
+#   rustup target add wasm32-wasip1
+#   ./kittygame_tools/check_replay_wasm.sh [n frames ...]
+#
+# Runs the wasm build with wasmtime, or whatever WASM_RUNNER is set to (it gets the .wasm file,
+# then the replay file). The replays are written to the current directory's target/ so the runner
+# can read them without extra directory grants.
+set -e
+
+FRAMES=${@:-20000 36000 60000}
+WASM_RUNNER=${WASM_RUNNER:-"wasmtime run --dir=."}
+OUT=target/replay_wasm_check
+
+cargo build --release --package kittygame_tools --bin replay_verify
+cargo build --release --package kittygame_tools --bin replay_verify --target wasm32-wasip1
+mkdir -p $OUT
+
+for n in $FRAMES; do
+    ./target/release/replay_verify --record $n $OUT/$n.txt > /dev/null
+    echo "native $n: $(./target/release/replay_verify $OUT/$n.txt | sed -n 1p)"
+    result=$($WASM_RUNNER target/wasm32-wasip1/release/replay_verify.wasm $OUT/$n.txt | sed -n 1p)
+    echo "wasm   $n: $result"
+    [[ $result == ok* ]] || exit 1
+done
@@ -0,0 +1,131 @@
+//! Time map lookups with the chunk index against checking every chunk, on seeded levels from each
+//! map generation setting. Every lookup is also checked against the linear scan, so this doubles
+//! as a check that the index finds the same chunks.
+//!
+//! ```bash
+//! cargo run --release --package kittygame_tools --bin chunk_index_bench -- [seeds, e.g. 0-9]
+//! ```
+
+use std::{hint::black_box, process::exit, time::Instant};
+
+use kittygame::{
+    game::{
+        collision::{check_absolue_bound_partially_inside_tile_aligned_bound, AbsoluteBoundingBox},
+        game_constants::{LEVELS_PER_MOOD, MAP_GEN_SETTINGS, TILE_HEIGHT_PX, TILE_WIDTH_PX},
+        game_map::GameMap,
+        game_state::GameState,
+        rng::Rng,
+    },
+    spritesheet,
+};
+
+const USAGE: &str = "usage: chunk_index_bench [seeds, e.g. 0-9]";
+
+// how many random points and kitty-sized boxes to look up per map.
+const N_QUERIES: usize = 200_000;
+
+fn fail(msg: &str) -> ! {
+    eprintln!("{}", msg);
+    exit(1)
+}
+
+fn parse_range(s: &str) -> (u32, u32) {
+    let parse = |n: &str| n.trim().parse::<u32>().unwrap_or_else(|_| fail(USAGE));
+    match s.split_once('-') {
+        Some((first, last)) => (parse(first), parse(last)),
+        None => (parse(s), parse(s)),
+    }
+}
+
+// what the map lookups did before the index.
+fn tile_at_abs_linear(map: &GameMap, x: i32, y: i32) -> Option<u8> {
+    map.chunks.iter().find_map(|chunk| chunk.get_tile_abs(x, y).ok())
+}
+
+fn chunks_overlapping_linear(map: &GameMap, bound: &AbsoluteBoundingBox<i32, u32>) -> usize {
+    map.chunks
+        .iter()
+        .enumerate()
+        .filter(|(_, chunk)| check_absolue_bound_partially_inside_tile_aligned_bound(bound, &chunk.bound))
+        .fold(0, |acc, (i, _)| acc * 31 + i + 1)
+}
+
+fn chunks_overlapping_indexed(map: &GameMap, bound: &AbsoluteBoundingBox<i32, u32>) -> usize {
+    map.chunks_overlapping(bound)
+        .map(|chunk| map.chunks.iter().position(|c| std::ptr::eq(c, chunk)).unwrap())
+        .fold(0, |acc, i| acc * 31 + i + 1)
+}
+
+fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if args.iter().any(|a| a == "-h" || a == "--help") || args.len() > 1 {
+        fail(USAGE);
+    }
+    let (first_seed, last_seed) = match args.first() {
+        Some(s) => parse_range(s),
+        None => (0, 4),
+    };
+
+    spritesheet::Sprite::init_all_sprites();
+
+    println!("setting,seed,level,chunks,point_linear_ns,point_indexed_ns,box_linear_ns,box_indexed_ns");
+    let mut totals = [0.0f64; 4];
+    for seed in first_seed..=last_seed {
+        // the last level of each mood, where maps are as big as that setting gets.
+        for setting_i in 0..MAP_GEN_SETTINGS.len() {
+            let level = ((setting_i + 1) * LEVELS_PER_MOOD) as u32;
+            let mut game_state = GameState::new();
+            game_state.regenerate_seeded_map(seed, level);
+            let map = &game_state.map;
+            if !map.chunk_index.is_built_for(&map.chunks) {
+                fail(&format!("seed {} level {}: no chunk index", seed, level));
+            }
+            let extents = map.get_extents();
+            let (x0, y0) = (extents.x * TILE_WIDTH_PX as i32, extents.y * TILE_HEIGHT_PX as i32);
+            let (w, h) = (extents.width * TILE_WIDTH_PX, extents.height * TILE_HEIGHT_PX);
+
+            let mut rng = Rng::new_from_seed(seed);
+            let points: Vec<(i32, i32)> = (0..N_QUERIES)
+                .map(|_| (x0 + (rng.next() as usize % w) as i32, y0 + (rng.next() as usize % h) as i32))
+                .collect();
+            let boxes: Vec<AbsoluteBoundingBox<i32, u32>> =
+                points.iter().map(|&(x, y)| AbsoluteBoundingBox { x, y, width: 8, height: 6 }).collect();
+
+            for (&(x, y), bound) in points.iter().zip(boxes.iter()) {
+                if map.tile_at_abs(x, y) != tile_at_abs_linear(map, x, y)
+                    || chunks_overlapping_indexed(map, bound) != chunks_overlapping_linear(map, bound)
+                {
+                    fail(&format!("seed {} level {}: index disagrees with linear scan at ({}, {})", seed, level, x, y));
+                }
+            }
+
+            let time = |f: &dyn Fn(usize) -> usize| {
+                let start = Instant::now();
+                let mut acc = 0;
+                for i in 0..N_QUERIES {
+                    acc += f(i);
+                }
+                black_box(acc);
+                start.elapsed().as_secs_f64() * 1e9 / N_QUERIES as f64
+            };
+            let ns = [
+                time(&|i| tile_at_abs_linear(map, points[i].0, points[i].1).unwrap_or(0) as usize),
+                time(&|i| map.tile_at_abs(points[i].0, points[i].1).unwrap_or(0) as usize),
+                time(&|i| chunks_overlapping_linear(map, &boxes[i])),
+                time(&|i| map.chunks_overlapping(&boxes[i]).count()),
+            ];
+            for (total, n) in totals.iter_mut().zip(ns.iter()) {
+                *total += n;
+            }
+            println!(
+                "{},{},{},{},{:.1},{:.1},{:.1},{:.1}",
+                setting_i, seed, level, map.chunks.len(), ns[0], ns[1], ns[2], ns[3]
+            );
+        }
+    }
+    eprintln!(
+        "point lookups: {:.1}x faster, box lookups: {:.1}x faster",
+        totals[0] / totals[1],
+        totals[2] / totals[3]
+    );
+}
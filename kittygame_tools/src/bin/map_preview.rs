@@ -0,0 +1,277 @@
+//! Render the map of a seed mode level to a PNG (using the spritesheet tiles) or to ASCII,
+//! so map generation settings and tilesets can be tuned without playing through them.
+//!
+//! ```bash
+//! cargo run --package kittygame_tools --bin map_preview -- <seed> <level> [out.png | --ascii] [--spritesheet assets/kitty-ss.png] [--difficulty easy|medium|hard]
+//! ```
+//!
+//! Map size and the number of NPCs depend on the difficulty, which is medium unless --difficulty says otherwise.
+
+use std::{fs::File, io::BufWriter, process::exit};
+
+use kittygame::{
+    game::{
+        dynamic_solids::DynamicSolidKind,
+        entities::Character,
+        game_constants::{TILE_HEIGHT_PX, TILE_WIDTH_PX},
+        game_map::{TileProperty, MAP_TILESETS},
+        game_state::{Difficulty, GameState},
+        mapchunk::TileAlignedBoundingBox,
+    },
+    spritesheet::{self, PresetSprites, SpriteFrame, KITTY_SPRITESHEET_PALETTES},
+};
+
+/// Colors as they're stored in kitty-ss.png, in the same order as the palettes
+/// in `KITTY_SPRITESHEET_PALETTES` (background, foreground, pigs & lizards, main kitty).
+const ORIGINAL_KITTY_SS_COLORS: [[u8; 3]; 4] = [
+    [0x12, 0x34, 0x56],
+    [0xff, 0xff, 0xff],
+    [0xff, 0x67, 0xd3],
+    [0xee, 0xc3, 0x9a],
+];
+
+const USAGE: &str = "usage: map_preview <seed> <level> [out.png | --ascii] [--spritesheet <path>] [--difficulty easy|medium|hard]";
+
+enum Output {
+    Png(String),
+    Ascii,
+}
+
+struct Image {
+    width: usize,
+    height: usize,
+    rgba: Vec<u8>,
+}
+
+impl Image {
+    fn load_png(path: &str) -> Image {
+        let mut decoder = png::Decoder::new(File::open(path).unwrap_or_else(|e| fail(&format!["can't open {}: {}", path, e])));
+        decoder.set_transformations(png::Transformations::EXPAND);
+        let mut reader = decoder.read_info().unwrap_or_else(|e| fail(&format!["can't read {}: {}", path, e]));
+        let mut buf = vec![0; reader.output_buffer_size()];
+        let info = reader.next_frame(&mut buf).unwrap_or_else(|e| fail(&format!["can't decode {}: {}", path, e]));
+        let rgba = match info.color_type {
+            png::ColorType::Rgba => buf[..info.buffer_size()].to_vec(),
+            png::ColorType::Rgb => buf[..info.buffer_size()].chunks(3).flat_map(|c| [c[0], c[1], c[2], 0xff]).collect(),
+            other => fail(&format!["unsupported spritesheet color type {:?}", other]),
+        };
+        Image { width: info.width as usize, height: info.height as usize, rgba }
+    }
+
+    fn filled(width: usize, height: usize, color: [u8; 3]) -> Image {
+        let rgba = (0..width * height).flat_map(|_| [color[0], color[1], color[2], 0xff]).collect();
+        Image { width, height, rgba }
+    }
+
+    fn set_pixel(self: &mut Self, x: i32, y: i32, color: [u8; 3]) {
+        if x < 0 || y < 0 || x as usize >= self.width || y as usize >= self.height {
+            return;
+        }
+        let i = (y as usize * self.width + x as usize) * 4;
+        self.rgba[i..i + 4].copy_from_slice(&[color[0], color[1], color[2], 0xff]);
+    }
+
+    /// Copy a frame of the spritesheet onto this image, recolored with the level's palette.
+    fn blit(self: &mut Self, sheet: &Image, frame: &SpriteFrame, x: i32, y: i32, palette: &[[u8; 3]; 4]) {
+        for row in 0..frame.height as usize {
+            for col in 0..frame.width as usize {
+                let i = ((frame.start_y as usize + row) * sheet.width + frame.start_x as usize + col) * 4;
+                let src = &sheet.rgba[i..i + 4];
+                if src[3] == 0 {
+                    continue;
+                }
+                match ORIGINAL_KITTY_SS_COLORS.iter().position(|c| c[..] == src[..3]) {
+                    Some(color_i) => self.set_pixel(x + col as i32, y + row as i32, palette[color_i]),
+                    None => {}
+                }
+            }
+        }
+    }
+
+    /// Draw a 1px box, used to call out the spawns.
+    fn outline(self: &mut Self, x: i32, y: i32, w: i32, h: i32, color: [u8; 3]) {
+        for i in 0..w {
+            self.set_pixel(x + i, y, color);
+            self.set_pixel(x + i, y + h - 1, color);
+        }
+        for j in 0..h {
+            self.set_pixel(x, y + j, color);
+            self.set_pixel(x + w - 1, y + j, color);
+        }
+    }
+
+    fn save_png(self: &Self, path: &str) {
+        let file = File::create(path).unwrap_or_else(|e| fail(&format!["can't create {}: {}", path, e]));
+        let mut encoder = png::Encoder::new(BufWriter::new(file), self.width as u32, self.height as u32);
+        encoder.set_color(png::ColorType::Rgba);
+        encoder.set_depth(png::BitDepth::Eight);
+        let mut writer = encoder.write_header().unwrap_or_else(|e| fail(&format!["can't write {}: {}", path, e]));
+        writer.write_image_data(&self.rgba).unwrap_or_else(|e| fail(&format!["can't write {}: {}", path, e]));
+    }
+}
+
+fn fail(msg: &str) -> ! {
+    eprintln!("{}", msg);
+    exit(1)
+}
+
+fn render_png(game_state: &GameState, extents: &TileAlignedBoundingBox, spritesheet_path: &str, out_path: &str) {
+    let sheet = Image::load_png(spritesheet_path);
+
+    let palette = KITTY_SPRITESHEET_PALETTES[game_state.pallette_idx]
+        .map(|c| [(c >> 16) as u8, (c >> 8) as u8, c as u8]);
+    let tileset = &MAP_TILESETS[game_state.tileset_idx];
+
+    let mut image = Image::filled(extents.width * TILE_WIDTH_PX, extents.height * TILE_HEIGHT_PX, palette[0]);
+    let origin_x = extents.x * TILE_WIDTH_PX as i32;
+    let origin_y = extents.y * TILE_HEIGHT_PX as i32;
+
+    for chunk in game_state.map.chunks.iter() {
+        for row in 0..chunk.bound.height {
+            for col in 0..chunk.bound.width {
+                let tile_i = tileset[chunk.get_tile(col, row) as usize] as usize;
+                if tile_i == 0 {
+                    continue;
+                }
+                let x = (chunk.bound.x + col as i32) * TILE_WIDTH_PX as i32 - origin_x;
+                let y = (chunk.bound.y + row as i32) * TILE_HEIGHT_PX as i32 - origin_y;
+                image.blit(&sheet, &game_state.background_tiles[tile_i].frames[0], x, y, &palette);
+            }
+        }
+    }
+
+    let mut draw_spawn = |character: &Character, color: [u8; 3]| {
+        let frame = &character.sprite.frames[0];
+        let x = character.x_pos.to_i32() - origin_x;
+        let y = character.y_pos.to_i32() - origin_y;
+        image.blit(&sheet, frame, x, y, &palette);
+        image.outline(x - 2, y - 2, frame.width as i32 + 4, frame.height as i32 + 4, color);
+    };
+
+    for npc in game_state.npcs.iter() {
+        draw_spawn(npc, palette[2]);
+    }
+    // players all start in the same spot, so only draw one of them.
+    draw_spawn(&Character::new(PresetSprites::MainCat), palette[3]);
+
+    image.save_png(out_path);
+}
+
+fn render_ascii(game_state: &GameState, extents: &TileAlignedBoundingBox) -> String {
+    // ' ' is outside of every chunk, '.' is open space, '#' is solid.
+    // Special tiles: '-' one-way, '^' spikes, '~' ice, '*' spring.
+    // Dynamic solids at their starting spot: '=' moving platform, '%' crumbling block.
+    let mut grid = vec![vec![' '; extents.width]; extents.height];
+    for chunk in game_state.map.chunks.iter() {
+        for row in 0..chunk.bound.height {
+            for col in 0..chunk.bound.width {
+                let gx = (chunk.bound.x + col as i32 - extents.x) as usize;
+                let gy = (chunk.bound.y + row as i32 - extents.y) as usize;
+                grid[gy][gx] = match game_state.map.get_tile_property(chunk.get_tile(col, row)) {
+                    TileProperty::Empty => '.',
+                    TileProperty::Solid => '#',
+                    TileProperty::OneWayUp => '-',
+                    TileProperty::Damaging => '^',
+                    TileProperty::LowFriction => '~',
+                    TileProperty::Bouncy => '*',
+                };
+            }
+        }
+    }
+
+    for solid in game_state.map.dynamic_solids.iter() {
+        let c = match solid.kind {
+            DynamicSolidKind::Crumbling => '%',
+            _ => '=',
+        };
+        let gy = (solid.y_pos / TILE_HEIGHT_PX as i32 - extents.y) as usize;
+        for i in 0..(solid.width / TILE_WIDTH_PX as u32) as i32 {
+            let gx = (solid.x_pos / TILE_WIDTH_PX as i32 + i - extents.x) as usize;
+            grid[gy][gx] = c;
+        }
+    }
+
+    let mut mark = |character: &Character, c: char| {
+        let gx = character.x_pos.to_i32() / TILE_WIDTH_PX as i32 - extents.x;
+        let gy = character.y_pos.to_i32() / TILE_HEIGHT_PX as i32 - extents.y;
+        if gx >= 0 && gy >= 0 && (gx as usize) < extents.width && (gy as usize) < extents.height {
+            grid[gy as usize][gx as usize] = c;
+        }
+    };
+    for npc in game_state.npcs.iter() {
+        let c = match npc.sprite_type {
+            PresetSprites::Pig => 'P',
+            PresetSprites::Lizard => 'L',
+            PresetSprites::BirdIsntReal => 'B',
+            _ => 'K',
+        };
+        mark(npc, c);
+    }
+    mark(&Character::new(PresetSprites::MainCat), '@');
+
+    let mut out = String::with_capacity((extents.width + 1) * extents.height);
+    for row in grid.iter() {
+        out.extend(row.iter());
+        out.push('\n');
+    }
+    out
+}
+
+fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+
+    let mut positional: Vec<&String> = Vec::new();
+    let mut output: Option<Output> = None;
+    let mut spritesheet_path = "assets/kitty-ss.png".to_string();
+    let mut difficulty = Difficulty::Medium;
+    let mut i = 0;
+    while i < args.len() {
+        match args[i].as_str() {
+            "--ascii" => output = Some(Output::Ascii),
+            "--spritesheet" => {
+                i += 1;
+                spritesheet_path = args.get(i).unwrap_or_else(|| fail(USAGE)).clone();
+            }
+            "--difficulty" => {
+                i += 1;
+                difficulty = args.get(i).and_then(|name| Difficulty::from_name(name)).unwrap_or_else(|| fail(USAGE));
+            }
+            "-h" | "--help" => {
+                println!("{}", USAGE);
+                return;
+            }
+            _ => positional.push(&args[i]),
+        }
+        i += 1;
+    }
+
+    let (seed, level) = match positional[..] {
+        [seed, level, ..] => (
+            seed.parse::<u32>().unwrap_or_else(|_| fail(USAGE)),
+            level.parse::<u32>().unwrap_or_else(|_| fail(USAGE)),
+        ),
+        _ => fail(USAGE),
+    };
+    if level == 0 {
+        fail("levels start at 1");
+    }
+    let output = match (output, positional.get(2)) {
+        (Some(o), _) => o,
+        (None, Some(path)) => Output::Png(path.to_string()),
+        (None, None) => Output::Png(format!["map_s{}_l{}.png", seed, level]),
+    };
+
+    spritesheet::Sprite::init_all_sprites();
+    let mut game_state = GameState::new();
+    game_state.settings.difficulty = difficulty;
+    game_state.regenerate_seeded_map(seed, level);
+    let extents = game_state.map.get_extents();
+
+    match output {
+        Output::Ascii => print!("{}", render_ascii(&game_state, &extents)),
+        Output::Png(path) => {
+            render_png(&game_state, &extents, &spritesheet_path, &path);
+            println!("wrote {} ({}x{} tiles, {})", path, extents.width, extents.height, difficulty.name());
+        }
+    }
+}
@@ -0,0 +1,572 @@
+//! Run scripted movement scenarios through update_pos, and compare their trajectories against
+//! the recorded ones in movement_golden.txt. Run this after touching collision or physics code:
+//! any scenario that moves differently shows up as a mismatch.
+//!
+//! ```bash
+//! cargo run --package kittygame_tools --bin movement_regression
+//! # after an intentional behaviour change, re-record the trajectories:
+//! cargo run --package kittygame_tools --bin movement_regression -- --bless
+//! # print where a scenario's characters are every frame, to diff against another build:
+//! cargo run --package kittygame_tools --bin movement_regression -- --trace <scenario>
+//! ```
+//!
+//! Physics is fixed-point, so every platform should record exactly the same trajectories. The golden
+//! file is compiled in, so the same check can run under wasm too:
+//!
+//! ```bash
+//! rustup target add wasm32-wasip1
+//! CARGO_TARGET_WASM32_WASIP1_RUNNER=wasmtime cargo run --target wasm32-wasip1 --package kittygame_tools --bin movement_regression
+//! ```
+
+use std::{fs, process::exit};
+
+use kittygame::{
+    game::{
+        cloud::Cloud,
+        collision::update_pos,
+        dynamic_solids::{DynamicSolid, DynamicSolidKind},
+        entities::{Character, KittyStates, MovingEntity, OptionallyEnabledPlayer},
+        fixed::Fixed,
+        game_map::{GameMap, ICE_TILE, ONE_WAY_TILE, SPECIAL_TILE_PROPERTIES, SPIKE_TILE, SPRING_TILE},
+        game_state::GameState,
+        mapchunk::{MapChunk, TileAlignedBoundingBox},
+        rng::Rng,
+        species::{get_random_input, PlayableCharacter},
+    },
+    multiplatform_defs::{BUTTON_1, BUTTON_DOWN, BUTTON_LEFT, BUTTON_RIGHT, BUTTON_UP},
+    spritesheet::{self, PresetSprites},
+};
+
+const GOLDEN_PATH: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/movement_golden.txt");
+const GOLDEN: &str = include_str!("../../movement_golden.txt");
+const USAGE: &str = "usage: movement_regression [--bless | --trace <scenario>]";
+
+/// Builds a single-chunk map from rows of ascii:
+/// '#' rock, '.' open, '-' one-way, '^' spikes, '~' ice, '*' spring,
+/// '=' horizontal platform, '|' vertical platform, '%' crumbling block.
+/// Platforms are 3 tiles wide and travel 6 tiles.
+fn build_map(rows: &[&str]) -> GameMap {
+    let mut map = GameMap::create_map();
+    map.tile_properties = &SPECIAL_TILE_PROPERTIES;
+    let mut chunk = MapChunk::init();
+    chunk.bound = TileAlignedBoundingBox::init(0, 0, rows[0].len(), rows.len());
+    chunk.initialize();
+    for (row, line) in rows.iter().enumerate() {
+        for (col, c) in line.chars().enumerate() {
+            let tile = match c {
+                '#' => 9,
+                '-' => ONE_WAY_TILE,
+                '^' => SPIKE_TILE,
+                '~' => ICE_TILE,
+                '*' => SPRING_TILE,
+                _ => 0,
+            };
+            chunk.set_tile(col, row, tile);
+            let kind = match c {
+                '=' => DynamicSolidKind::MovingHorizontal,
+                '|' => DynamicSolidKind::MovingVertical,
+                '%' => DynamicSolidKind::Crumbling,
+                _ => continue,
+            };
+            let solid = match kind {
+                DynamicSolidKind::Crumbling => DynamicSolid::new_crumbling(col as i32, row as i32),
+                _ => DynamicSolid::new_platform(kind, col as i32, row as i32, 3, 6),
+            };
+            map.dynamic_solids.push(solid);
+        }
+    }
+    map.chunks.push(chunk);
+    map
+}
+
+/// Folds a trajectory into one number, so the golden file stays small.
+/// When tracing, also prints every step of it.
+struct TrajectoryHash(u64, bool);
+
+impl TrajectoryHash {
+    fn add(self: &mut Self, character: &Character) {
+        let state = match character.state {
+            KittyStates::Sleeping => 0,
+            KittyStates::Walking(t) => 0x100 | t as u32,
+            KittyStates::JumpingUp(t) => 0x200 | t as u32,
+            KittyStates::HuggingWall(b) => 0x300 | b as u32,
+            KittyStates::OnCeiling(t) => 0x400 | t as u32,
+        };
+        if self.1 {
+            println!("{} {} {:03x}", character.x_pos.to_f32(), character.y_pos.to_f32(), state);
+        }
+        for word in [character.x_pos.to_raw(), character.y_pos.to_raw(), character.x_vel.to_raw(), character.y_vel.to_raw()] {
+            self.add_word(word as u32);
+        }
+        self.add_word(state);
+    }
+
+    fn add_word(self: &mut Self, word: u32) {
+        for byte in word.to_le_bytes() {
+            // FNV-1a
+            self.0 ^= byte as u64;
+            self.0 = self.0.wrapping_mul(0x100000001b3);
+        }
+    }
+}
+
+// play as one of the characters from the select screen, who can move differently from NPCs
+// with the same sprite.
+fn play_as(c: &mut Character, playable: PlayableCharacter) {
+    let profile = playable.profile();
+    c.species = &profile.species;
+    c.x_vel_cap = profile.species.physics.x_vel_cap;
+    c.y_vel_cap = profile.species.physics.y_vel_cap;
+    c.can_fly = profile.species.physics.can_fly;
+}
+
+struct Scenario {
+    name: &'static str,
+    map: &'static [&'static str],
+    sprite: PresetSprites,
+    start: (i32, i32),
+    frames: u32,
+    // anything the character starts with, like what a card gave them.
+    setup: fn(&mut Character),
+    input: fn(u32) -> u8,
+}
+
+const BOX: &[&str] = &[
+    "##############################",
+    "#............................#",
+    "#............................#",
+    "#............................#",
+    "#............................#",
+    "#............................#",
+    "#............................#",
+    "#............................#",
+    "#............................#",
+    "#............................#",
+    "#............................#",
+    "##############################",
+];
+
+const STEPS: &[&str] = &[
+    "##############################",
+    "#............................#",
+    "#............................#",
+    "#............................#",
+    "#............................#",
+    "#............................#",
+    "#............................#",
+    "#........................#####",
+    "#...................##########",
+    "#..............###############",
+    "#..........###################",
+    "##############################",
+];
+
+const TALL_WALLS: &[&str] = &[
+    "####################",
+    "#..................#",
+    "#..................#",
+    "#..................#",
+    "#..................#",
+    "#.......#..........#",
+    "#.......#..........#",
+    "#.......#..........#",
+    "#.......#..........#",
+    "#.......#..........#",
+    "#.......#..........#",
+    "#.......#..........#",
+    "#.......#..........#",
+    "#.......#..........#",
+    "#.......#..........#",
+    "#.......#..........#",
+    "#.......#..........#",
+    "#.......#..........#",
+    "#.......#..........#",
+    "####################",
+];
+
+const LOW_CEILING: &[&str] = &[
+    "##############################",
+    "#............................#",
+    "#............................#",
+    "#............................#",
+    "#.........##########.........#",
+    "#............................#",
+    "#............................#",
+    "#............................#",
+    "##############################",
+];
+
+const SPECIAL_TILES: &[&str] = &[
+    "##############################",
+    "#............................#",
+    "#............................#",
+    "#............................#",
+    "#............................#",
+    "#........-------.............#",
+    "#............................#",
+    "#............................#",
+    "#............................#",
+    "#............................#",
+    "#............................#",
+    "#...~~~~~~~~~~~~~~~^^...*....#",
+    "##############################",
+];
+
+const PLATFORMS: &[&str] = &[
+    "##############################",
+    "#............................#",
+    "#............................#",
+    "#.|..........................#",
+    "#............................#",
+    "#............................#",
+    "#.......=....................#",
+    "#............................#",
+    "#...................%%%%.....#",
+    "#............................#",
+    "#............................#",
+    "#............................#",
+    "#............................#",
+    "#............................#",
+    "#............................#",
+    "##############################",
+];
+
+const SCENARIOS: &[Scenario] = &[
+    Scenario { name: "fall_and_rest", map: BOX, sprite: PresetSprites::MainCat, start: (20, 10), frames: 120, setup: |_| {}, input: |_| 0 },
+    Scenario { name: "walk_right_into_wall", map: BOX, sprite: PresetSprites::MainCat, start: (20, 40), frames: 200, setup: |_| {}, input: |_| BUTTON_RIGHT },
+    Scenario { name: "walk_left_into_wall", map: BOX, sprite: PresetSprites::MainCat, start: (120, 40), frames: 200, setup: |_| {}, input: |_| BUTTON_LEFT },
+    Scenario {
+        name: "hop_in_place",
+        map: BOX,
+        sprite: PresetSprites::MainCat,
+        start: (70, 40),
+        frames: 240,
+        setup: |_| {},
+        input: |f| match f % 60 < 10 && f > 30 {
+            true => BUTTON_1,
+            false => 0,
+        },
+    },
+    Scenario { name: "walk_up_steps", map: STEPS, sprite: PresetSprites::MainCat, start: (10, 30), frames: 300, setup: |_| {}, input: |_| BUTTON_RIGHT },
+    Scenario {
+        name: "jump_up_steps",
+        map: STEPS,
+        sprite: PresetSprites::MainCat,
+        start: (10, 30),
+        frames: 300,
+        setup: |_| {},
+        input: |f| BUTTON_RIGHT | if f % 40 < 8 { BUTTON_1 } else { 0 },
+    },
+    Scenario { name: "walk_down_steps", map: STEPS, sprite: PresetSprites::MainCat, start: (135, 20), frames: 300, setup: |_| {}, input: |_| BUTTON_LEFT },
+    Scenario {
+        name: "wall_cling_and_jump",
+        map: TALL_WALLS,
+        sprite: PresetSprites::MainCat,
+        start: (15, 80),
+        frames: 400,
+        setup: |_| {},
+        input: |f| match f {
+            0..=29 => 0,
+            30..=39 => BUTTON_1 | BUTTON_RIGHT,
+            40..=99 => BUTTON_RIGHT,
+            100..=109 => BUTTON_1 | BUTTON_RIGHT,
+            110..=199 => BUTTON_LEFT,
+            200..=209 => BUTTON_1,
+            _ => BUTTON_LEFT,
+        },
+    },
+    Scenario {
+        name: "bonk_ceiling",
+        map: LOW_CEILING,
+        sprite: PresetSprites::MainCat,
+        start: (70, 25),
+        frames: 200,
+        setup: |_| {},
+        input: |f| match f % 50 < 12 {
+            true => BUTTON_1,
+            false => 0,
+        },
+    },
+    Scenario {
+        name: "ceiling_crawl",
+        map: LOW_CEILING,
+        sprite: PresetSprites::MainCat,
+        start: (70, 25),
+        frames: 200,
+        setup: |_| {},
+        input: |f| match f {
+            0..=39 => 0,
+            40..=55 => BUTTON_1,
+            _ => BUTTON_RIGHT,
+        },
+    },
+    Scenario {
+        name: "oneway_jump_through_and_drop",
+        map: SPECIAL_TILES,
+        sprite: PresetSprites::MainCat,
+        start: (60, 40),
+        frames: 300,
+        setup: |_| {},
+        input: |f| match f {
+            30..=45 => BUTTON_1,
+            46..=149 => 0,
+            _ => BUTTON_DOWN,
+        },
+    },
+    Scenario { name: "ice_slide", map: SPECIAL_TILES, sprite: PresetSprites::MainCat, start: (20, 40), frames: 160, setup: |_| {}, input: |f| if f < 60 { BUTTON_RIGHT } else { 0 } },
+    Scenario { name: "spikes_and_spring", map: SPECIAL_TILES, sprite: PresetSprites::MainCat, start: (90, 40), frames: 300, setup: |_| {}, input: |_| BUTTON_RIGHT },
+    Scenario { name: "ride_platforms", map: PLATFORMS, sprite: PresetSprites::MainCat, start: (45, 10), frames: 400, setup: |_| {}, input: |_| 0 },
+    Scenario { name: "ride_vertical_platform", map: PLATFORMS, sprite: PresetSprites::MainCat, start: (11, 5), frames: 400, setup: |_| {}, input: |_| 0 },
+    Scenario { name: "crumble_bridge", map: PLATFORMS, sprite: PresetSprites::MainCat, start: (102, 15), frames: 500, setup: |_| {}, input: |_| BUTTON_LEFT },
+    Scenario {
+        name: "lizard_climb",
+        map: TALL_WALLS,
+        sprite: PresetSprites::Lizard,
+        start: (20, 80),
+        frames: 400,
+        setup: |_| {},
+        input: |f| match f {
+            0..=99 => BUTTON_RIGHT,
+            100..=199 => BUTTON_RIGHT | BUTTON_UP,
+            200..=299 => BUTTON_RIGHT | BUTTON_DOWN,
+            _ => BUTTON_LEFT,
+        },
+    },
+    Scenario {
+        name: "bird_flap",
+        map: BOX,
+        sprite: PresetSprites::BirdIsntReal,
+        start: (70, 30),
+        frames: 300,
+        setup: |_| {},
+        input: |f| BUTTON_RIGHT | if f % 20 < 3 { BUTTON_1 } else { 0 },
+    },
+    Scenario { name: "pig_run", map: STEPS, sprite: PresetSprites::Pig, start: (10, 30), frames: 300, setup: |_| {}, input: |_| BUTTON_RIGHT },
+    Scenario {
+        name: "dash_into_wall",
+        map: BOX,
+        sprite: PresetSprites::MainCat,
+        start: (20, 40),
+        frames: 120,
+        setup: |c| c.dash_frames = 40,
+        input: |f| match f {
+            0..=5 => BUTTON_RIGHT,
+            _ => 0,
+        },
+    },
+    Scenario {
+        name: "double_jump",
+        map: BOX,
+        sprite: PresetSprites::MainCat,
+        start: (70, 40),
+        frames: 200,
+        setup: |c| c.banked_air_jumps = 2,
+        input: |f| match f {
+            30..=39 | 55..=60 | 80..=85 | 105..=110 => BUTTON_1,
+            _ => 0,
+        },
+    },
+    Scenario {
+        // falling off a step counts as mid-air, so jumping then uses an air jump.
+        name: "walk_off_step_air_jump",
+        map: STEPS,
+        sprite: PresetSprites::MainCat,
+        start: (135, 20),
+        frames: 120,
+        setup: |c| c.banked_air_jumps = 1,
+        input: |f| BUTTON_LEFT | if f == 27 { BUTTON_1 } else { 0 },
+    },
+    Scenario {
+        name: "zippy_run",
+        map: BOX,
+        sprite: PresetSprites::Kitty1,
+        start: (20, 40),
+        frames: 200,
+        setup: |c| play_as(c, PlayableCharacter::Kitty1),
+        input: |_| BUTTON_RIGHT,
+    },
+    Scenario {
+        name: "bouncy_jump",
+        map: BOX,
+        sprite: PresetSprites::Kitty2,
+        start: (70, 40),
+        frames: 150,
+        setup: |c| play_as(c, PlayableCharacter::Kitty2),
+        input: |f| match f {
+            30..=45 => BUTTON_1,
+            _ => 0,
+        },
+    },
+    Scenario {
+        name: "sticky_climb",
+        map: BOX,
+        sprite: PresetSprites::Kitty4,
+        start: (20, 40),
+        frames: 200,
+        setup: |c| play_as(c, PlayableCharacter::Kitty4),
+        input: |_| BUTTON_RIGHT | BUTTON_UP,
+    },
+];
+
+fn run_scenario(scenario: &Scenario, trace: bool) -> (f32, f32, u64) {
+    let mut map = build_map(scenario.map);
+    let mut character = Character::new(scenario.sprite);
+    character.x_pos = Fixed::from_int(scenario.start.0);
+    character.y_pos = Fixed::from_int(scenario.start.1);
+    (scenario.setup)(&mut character);
+    let mut clouds: Vec<Cloud> = Vec::new();
+    let mut hash = TrajectoryHash(0xcbf29ce484222325, trace);
+
+    for frame in 0..scenario.frames {
+        for (i, solid) in map.dynamic_solids.iter_mut().enumerate() {
+            solid.update(character.riding_solid == Some(i), false);
+        }
+        update_pos(&map, MovingEntity::NPC(&mut character), (scenario.input)(frame), false, &mut clouds);
+        hash.add(&character);
+    }
+    (character.x_pos.to_f32(), character.y_pos.to_f32(), hash.0)
+}
+
+/// Let every NPC on a generated level mash random buttons for a while.
+fn run_seeded_level(seed: u32, level: u32, frames: u32, trace: bool) -> (f32, f32, u64) {
+    let mut game_state = GameState::new();
+    game_state.regenerate_seeded_map(seed, level);
+    let mut clouds: Vec<Cloud> = Vec::new();
+    let mut rng = Rng::new_from_seed(seed);
+    let mut hash = TrajectoryHash(0xcbf29ce484222325, trace);
+
+    // hold each input for a little while, like a person would.
+    let mut inputs = vec![0; game_state.npcs.len()];
+    for frame in 0..frames {
+        game_state.update_dynamic_solids();
+        for (i, npc) in game_state.npcs.iter_mut().enumerate() {
+            if frame % 12 == 0 {
+                inputs[i] = match rng.next() % 4 {
+                    0 => get_random_input(rng.next() as u8),
+                    1 => BUTTON_LEFT | BUTTON_1,
+                    2 => BUTTON_RIGHT,
+                    _ => BUTTON_LEFT,
+                };
+            }
+            update_pos(&game_state.map, MovingEntity::NPC(npc), inputs[i], false, &mut clouds);
+            hash.add(npc);
+        }
+    }
+    let last = game_state.npcs.last().unwrap();
+    (last.x_pos.to_f32(), last.y_pos.to_f32(), hash.0)
+}
+
+/// Play a long run as player one, with the camera following along, while the NPCs mash buttons.
+/// This is the one to compare across platforms: it runs long enough for any rounding
+/// differences to snowball.
+fn run_long_replay(seed: u32, level: u32, frames: u32, trace: bool) -> (f32, f32, u64) {
+    let mut game_state = GameState::new();
+    game_state.regenerate_seeded_map(seed, level);
+    let mut clouds: Vec<Cloud> = Vec::new();
+    let mut rng = Rng::new_from_seed(seed);
+    let mut hash = TrajectoryHash(0xcbf29ce484222325, trace);
+
+    let mut player_input = 0;
+    let mut inputs = vec![0; game_state.npcs.len()];
+    for frame in 0..frames {
+        game_state.update_dynamic_solids();
+        if frame % 20 == 0 {
+            player_input = match rng.next() % 5 {
+                0 => BUTTON_LEFT | BUTTON_1,
+                1 => BUTTON_RIGHT | BUTTON_1,
+                2 => BUTTON_RIGHT,
+                3 => BUTTON_LEFT,
+                _ => BUTTON_DOWN,
+            };
+        }
+        update_pos(&game_state.map, MovingEntity::OptionalPlayer(&mut game_state.players[0]), player_input, false, &mut clouds);
+        for (i, npc) in game_state.npcs.iter_mut().enumerate() {
+            if frame % 12 == 0 {
+                inputs[i] = get_random_input(rng.next() as u8) | BUTTON_RIGHT;
+            }
+            update_pos(&game_state.map, MovingEntity::NPC(npc), inputs[i], false, &mut clouds);
+            hash.add(npc);
+        }
+        if let OptionallyEnabledPlayer::Enabled(p) = &game_state.players[0] {
+            hash.add(&p.character);
+            game_state.camera.current_viewing_x_target = p.character.x_pos - Fixed::from_int(80);
+            game_state.camera.current_viewing_y_target = p.character.y_pos - Fixed::from_int(80);
+        }
+        game_state.camera.slew();
+        hash.add_word(game_state.camera.current_viewing_x_offset.to_raw() as u32);
+        hash.add_word(game_state.camera.current_viewing_y_offset.to_raw() as u32);
+        clouds.clear();
+    }
+    match &game_state.players[0] {
+        OptionallyEnabledPlayer::Enabled(p) => (p.character.x_pos.to_f32(), p.character.y_pos.to_f32(), hash.0),
+        OptionallyEnabledPlayer::Disabled => (0.0, 0.0, hash.0),
+    }
+}
+
+fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let (bless, trace) = match args.iter().map(|a| a.as_str()).collect::<Vec<&str>>().as_slice() {
+        [] => (false, None),
+        ["--bless"] => (true, None),
+        ["--trace", name] => (false, Some(name.to_string())),
+        _ => {
+            eprintln!("{}", USAGE);
+            exit(1)
+        }
+    };
+
+    spritesheet::Sprite::init_all_sprites();
+
+    let mut results: Vec<String> = Vec::new();
+    for scenario in SCENARIOS.iter() {
+        let traced = trace.as_deref() == Some(scenario.name);
+        let (x, y, hash) = run_scenario(scenario, traced);
+        results.push(format!["{} {} {} {:016x}", scenario.name, x, y, hash]);
+    }
+    // one level from each mood, and a few from the spacey ones with platforms.
+    for (seed, level) in [(1, 1), (2, 6), (3, 11), (4, 16), (5, 21), (6, 26), (7, 31), (8, 36), (9, 38), (10, 41), (11, 45)] {
+        let name = format!["seed_{}_level_{}", seed, level];
+        let traced = trace.as_deref() == Some(name.as_str());
+        let (x, y, hash) = run_seeded_level(seed, level, 600, traced);
+        results.push(format!["{} {} {} {:016x}", name, x, y, hash]);
+    }
+    {
+        let name = "long_replay_seed_42_level_30";
+        let traced = trace.as_deref() == Some(name);
+        let (x, y, hash) = run_long_replay(42, 30, 20000, traced);
+        results.push(format!["{} {} {} {:016x}", name, x, y, hash]);
+    }
+    if trace.is_some() {
+        return;
+    }
+
+    if bless {
+        fs::write(GOLDEN_PATH, results.join("\n") + "\n").unwrap_or_else(|e| {
+            eprintln!("couldn't write {}: {}", GOLDEN_PATH, e);
+            exit(1)
+        });
+        println!("recorded {} scenarios", results.len());
+        return;
+    }
+
+    let golden: Vec<&str> = GOLDEN.lines().collect();
+    let mut n_failed = 0;
+    for result in results.iter() {
+        let name = result.split(' ').next().unwrap();
+        match golden.iter().find(|line| line.split(' ').next() == Some(name)) {
+            Some(expected) if *expected == result => println!("ok       {}", name),
+            Some(expected) => {
+                n_failed += 1;
+                println!("MISMATCH {}\n  expected {}\n  got      {}", name, expected, result);
+            }
+            None => {
+                n_failed += 1;
+                println!("MISSING  {} (not in the golden file, run with --bless)", name);
+            }
+        }
+    }
+    println!("{} of {} scenarios moved the same", results.len() - n_failed, results.len());
+    if n_failed > 0 {
+        exit(1);
+    }
+}
@@ -0,0 +1,164 @@
+//! Play back a replay and check its state hashes, to find where this build stops agreeing with the
+//! one that recorded it. Replays come from running the macroquad build with `--record-replay <file>`,
+//! or from `--record`, which mashes buttons for a while from the title screen on.
+//!
+//! ```bash
+//! cargo run --release --package kittygame_tools --bin replay_verify -- <replay file> [--log-every <n frames>]
+//! cargo run --release --package kittygame_tools --bin replay_verify -- --record <n frames> <replay file>
+//! ```
+//!
+//! `--record` also writes the stats of the last run it played to `<replay file>.stats`, as the
+//! macroquad build does.
+//!
+//! Checkpoints are a second apart. To narrow a desync down to the frame, run both builds with
+//! `--log-every 1` (or the macroquad build with `--log-state-hash 1`) and diff the logs.
+//!
+//! It also builds for wasm32-wasip1, which is how `check_replay_wasm.sh` checks that replays recorded
+//! natively play back the same on 32 bit wasm.
+
+use std::{fs, process::exit};
+
+use kittygame::{
+    game::{
+        game_state::RunType,
+        replay::{Replay, REPLAY_CHECKPOINT_EVERY_N_FRAMES},
+        rng::Rng,
+        species::get_random_input,
+    },
+    kittygame_load_save, kittygame_replay_text, kittygame_run_stats_text, kittygame_set_debug_options, kittygame_state_hash, kittygame_update,
+    multiplatform_defs::{BlitSubFunc, LineFunc, LogFunc, RectFunc, SwitchPalletteFunc, TextStrFunc, ToneFunc, BUTTON_1, BUTTON_START},
+    DebugOptions,
+};
+
+const USAGE: &str = "usage: replay_verify <replay file> [--log-every <n frames>]\n       replay_verify --record <n frames> <replay file>";
+
+// what --record plays at.
+const RECORD_SCREEN_WIDTH: u32 = 160;
+const RECORD_SCREEN_HEIGHT: u32 = 160;
+
+fn fail(msg: &str) -> ! {
+    eprintln!("{}", msg);
+    exit(1)
+}
+
+// run one frame of the game without drawing anything.
+fn run_frame(sw: u32, sh: u32, btns_pressed_this_frame: &[u8; 4], gamepads: &[u8; 4], log_every: u32) {
+    let blit_sub: &mut BlitSubFunc = &mut |_, _, _, _, _, _, _, _| {};
+    let line: &mut LineFunc = &mut |_, _, _, _, _| {};
+    let rect: &mut RectFunc = &mut |_, _, _, _, _| {};
+    let text_str: &mut TextStrFunc = &mut |_, _, _, _| {};
+    let set_palette: &mut SwitchPalletteFunc = &mut |_| {};
+    let log: &mut LogFunc = &mut |s| {
+        if log_every != 0 {
+            println!("{}", s);
+        }
+    };
+    let tone: &mut ToneFunc = &mut |_, _, _, _| {};
+    kittygame_update(blit_sub, line, rect, text_str, set_palette, log, tone, sw, sh, btns_pressed_this_frame, gamepads);
+}
+
+fn record(n_frames: u32, path: &str) {
+    kittygame_set_debug_options(DebugOptions {
+        log_state_hash_every_n_frames: 0,
+        record_replay: true,
+    });
+    let mut rng = Rng::new_from_seed(n_frames);
+    let mut gamepads = [0; 4];
+    for frame in 0..n_frames {
+        let previous = gamepads;
+        // hold each input for a bit, and keep pressing the button so menus get dismissed.
+        if frame % 15 == 0 {
+            gamepads[0] = get_random_input(rng.next() as u8);
+            if frame % 60 == 0 {
+                gamepads[0] |= BUTTON_1;
+            }
+            // and pause now and then, to go through the pause menu too.
+            if frame % 900 == 450 {
+                gamepads[0] |= BUTTON_START;
+            }
+        }
+        let pressed = [0, 1, 2, 3].map(|i| gamepads[i] & !previous[i]);
+        run_frame(RECORD_SCREEN_WIDTH, RECORD_SCREEN_HEIGHT, &pressed, &gamepads, 0);
+    }
+    let text = kittygame_replay_text().unwrap_or_else(|| fail("nothing was recorded"));
+    fs::write(path, text).unwrap_or_else(|e| fail(&format!["couldn't write {}: {}", path, e]));
+    // the stats of the last run played go alongside.
+    if let Some(stats) = kittygame_run_stats_text() {
+        let stats_path = format!["{}.stats", path];
+        fs::write(&stats_path, stats).unwrap_or_else(|e| fail(&format!["couldn't write {}: {}", stats_path, e]));
+    }
+    println!("recorded {} frames to {}", n_frames, path);
+}
+
+fn verify(path: &str, log_every: u32) {
+    let text = fs::read_to_string(path).unwrap_or_else(|e| fail(&format!["couldn't read {}: {}", path, e]));
+    let replay = Replay::from_text(&text).unwrap_or_else(|e| fail(&format!["{}: {}", path, e]));
+    kittygame_set_debug_options(DebugOptions {
+        log_state_hash_every_n_frames: log_every,
+        record_replay: false,
+    });
+    // start from the same options as the recording did.
+    if let Some(save) = &replay.save {
+        kittygame_load_save(save);
+    }
+
+    let mut checkpoints = replay.checkpoints.iter().peekable();
+    let mut last_agreed = 0;
+    for (frame_i, inputs) in replay.frame_inputs().enumerate() {
+        let frame = frame_i as u32 + 1;
+        run_frame(inputs.screen_width, inputs.screen_height, &inputs.btns_pressed_this_frame, &inputs.gamepads, log_every);
+        while let Some(checkpoint) = checkpoints.next_if(|c| c.frame <= frame) {
+            let state_hash = kittygame_state_hash().unwrap_or(0);
+            if checkpoint.frame != frame || checkpoint.state_hash != state_hash {
+                println!(
+                    "DESYNC  between frames {} and {}: expected state {:016x}, got {:016x}",
+                    last_agreed + 1,
+                    checkpoint.frame,
+                    checkpoint.state_hash,
+                    state_hash
+                );
+                exit(1);
+            }
+            last_agreed = frame;
+        }
+    }
+    println!(
+        "ok       {} frames, {} checkpoints every {} frames",
+        replay.n_frames,
+        replay.checkpoints.len(),
+        REPLAY_CHECKPOINT_EVERY_N_FRAMES
+    );
+    for run_start in replay.run_starts.iter() {
+        let run_type = match run_start.run_type {
+            RunType::Random => "random".to_string(),
+            RunType::Speedrun(seed) => format!["seed {}", seed],
+            RunType::Tutorial => "tutorial".to_string(),
+        };
+        let characters: Vec<&str> = run_start.characters.iter().map(|c| c.name()).collect();
+        println!(
+            "         game at frame {}: {}, {}, as {}",
+            run_start.frame,
+            run_start.difficulty.name(),
+            run_type,
+            characters.join(" ")
+        );
+    }
+    for pause in replay.pauses.iter() {
+        println!(
+            "         paused at frame {} for {:.1}s",
+            pause.frame,
+            pause.n_frames as f32 / 60.
+        );
+    }
+}
+
+fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let args: Vec<&str> = args.iter().map(|a| a.as_str()).collect();
+    match args[..] {
+        ["--record", n_frames, path] => record(n_frames.parse().unwrap_or_else(|_| fail(USAGE)), path),
+        [path] if !path.starts_with('-') => verify(path, 0),
+        [path, "--log-every", n] => verify(path, n.parse().unwrap_or_else(|_| fail(USAGE))),
+        _ => fail(USAGE),
+    }
+}
@@ -0,0 +1,119 @@
+//! Sweep seed mode seeds and levels, and print map statistics as CSV. Handy for picking
+//! interesting seeds, and for spotting degenerate map generation settings before shipping them.
+//! NPC distances are straight-line pixel distances from where the players start. Map size and the
+//! number of NPCs depend on the difficulty, which is medium unless --difficulty says otherwise.
+//!
+//! ```bash
+//! cargo run --package kittygame_tools --bin seed_stats -- <seeds, e.g. 0-99> [levels, e.g. 1-45] [--difficulty easy|medium|hard] > stats.csv
+//! ```
+
+use std::process::exit;
+
+use kittygame::{
+    game::{
+        entities::Character,
+        game_constants::{FINAL_LEVEL, MAP_GEN_SETTINGS, START_DIFFICULTY_LEVEL},
+        game_state::{Difficulty, GameState},
+        rng::{GameRng, Rng},
+    },
+    spritesheet::{self, PresetSprites},
+};
+
+const USAGE: &str = "usage: seed_stats <seeds, e.g. 0-99> [levels, e.g. 1-45] [--difficulty easy|medium|hard]";
+
+fn fail(msg: &str) -> ! {
+    eprintln!("{}", msg);
+    exit(1)
+}
+
+/// Parse an inclusive range like "3-10", or a single number like "7".
+fn parse_range(s: &str) -> (u32, u32) {
+    let parse = |n: &str| n.trim().parse::<u32>().unwrap_or_else(|_| fail(USAGE));
+    match s.split_once('-') {
+        Some((first, last)) => (parse(first), parse(last)),
+        None => (parse(s), parse(s)),
+    }
+}
+
+fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if args.iter().any(|a| a == "-h" || a == "--help") {
+        println!("{}", USAGE);
+        return;
+    }
+    let mut positional: Vec<&String> = Vec::new();
+    let mut difficulty = Difficulty::Medium;
+    let mut i = 0;
+    while i < args.len() {
+        match args[i].as_str() {
+            "--difficulty" => {
+                i += 1;
+                difficulty = args.get(i).and_then(|name| Difficulty::from_name(name)).unwrap_or_else(|| fail(USAGE));
+            }
+            _ => positional.push(&args[i]),
+        }
+        i += 1;
+    }
+    let Some(seeds) = positional.first() else {
+        fail(USAGE);
+    };
+    let (first_seed, last_seed) = parse_range(seeds);
+    let (first_level, last_level) = match positional.get(1) {
+        Some(levels) => parse_range(levels),
+        None => (START_DIFFICULTY_LEVEL, FINAL_LEVEL),
+    };
+    if first_level < START_DIFFICULTY_LEVEL || first_seed > last_seed || first_level > last_level {
+        fail(USAGE);
+    }
+
+    spritesheet::Sprite::init_all_sprites();
+    let mut game_state = GameState::new();
+    game_state.settings.difficulty = difficulty;
+    let start = Character::new(PresetSprites::MainCat);
+
+    println!("seed,level,difficulty,map_gen_setting,linear_mapsize_mult,chunks,num_tiles,total_npcs_to_find,extent_x,extent_y,extent_width,extent_height,min_npc_dist,max_npc_dist,npc_dists");
+
+    for seed in first_seed..=last_seed {
+        // levels share a worldgen stream, so play through them in order like a real run does.
+        game_state.rng = GameRng::FixedSeed(Rng::new_from_seed(seed), Rng::new_from_seed(seed));
+        for level in START_DIFFICULTY_LEVEL..=last_level {
+            game_state.difficulty_level = level;
+            game_state.regenerate_map();
+            if level < first_level {
+                continue;
+            }
+
+            let extents = game_state.map.get_extents();
+            let npc_dists: Vec<f32> = game_state
+                .npcs
+                .iter()
+                .map(|npc| {
+                    let dx = (npc.x_pos - start.x_pos).to_f32();
+                    let dy = (npc.y_pos - start.y_pos).to_f32();
+                    (dx * dx + dy * dy).sqrt()
+                })
+                .collect();
+            let min_dist = npc_dists.iter().cloned().fold(f32::INFINITY, f32::min);
+            let max_dist = npc_dists.iter().cloned().fold(0.0, f32::max);
+
+            println!(
+                "{},{},{},{},{},{},{},{},{},{},{},{},{:.0},{:.0},{}",
+                seed,
+                level,
+                difficulty.name(),
+                game_state.map_gen_settings_idx,
+                MAP_GEN_SETTINGS[game_state.map_gen_settings_idx].linear_mapsize_mult,
+                game_state.map.chunks.len(),
+                game_state.map.num_tiles,
+                game_state.total_npcs_to_find,
+                extents.x,
+                extents.y,
+                extents.width,
+                extents.height,
+                min_dist,
+                max_dist,
+                npc_dists.iter().map(|d| format!["{:.0}", d]).collect::<Vec<String>>().join(";"),
+            );
+        }
+    }
+}
@@ -2,7 +2,7 @@ use std::{cell::RefCell, collections::HashMap};
 
 use macroquad::prelude::*;
 
-use kittygame::{kittygame_update, multiplatform_defs::{BlitSubFlags, DrawColor, Pallette, Spritesheet, BUTTON_1, BUTTON_2, BUTTON_DOWN, BUTTON_LEFT, BUTTON_RIGHT, BUTTON_UP}};
+use kittygame::{kittygame_load_save, kittygame_options, kittygame_replay_text, kittygame_run_stats_text, kittygame_set_debug_options, kittygame_take_save, kittygame_update, DebugOptions, multiplatform_defs::{BlitSubFlags, DrawColor, Pallette, Spritesheet, BUTTON_1, BUTTON_2, BUTTON_START, BUTTON_DOWN, BUTTON_LEFT, BUTTON_RIGHT, BUTTON_UP}};
 
 
 const ORIGINAL_KITTY_SS_COLORS: [[u8; 4]; 5] = [
@@ -99,6 +99,25 @@ async fn main() {
         Touchpad
     }
 
+    // debugging: `--log-state-hash <every n frames>` to check for desyncs between builds,
+    // and `--record-replay <file>` to save what's played, for kittygame_tools' replay_verify, with the
+    // run's stats in <file>.stats.
+    let args: Vec<String> = std::env::args().collect();
+    let arg_after = |flag: &str| args.iter().position(|a| a == flag).and_then(|i| args.get(i + 1)).cloned();
+    let replay_path = arg_after("--record-replay");
+    kittygame_set_debug_options(DebugOptions {
+        log_state_hash_every_n_frames: arg_after("--log-state-hash").and_then(|n| n.parse().ok()).unwrap_or(0),
+        record_replay: replay_path.is_some(),
+    });
+    // the options and such from last time, if there are any.
+    const SAVE_PATH: &str = "kittygame.sav";
+    if let Ok(bytes) = std::fs::read(SAVE_PATH) {
+        kittygame_load_save(&bytes);
+    }
+    // how often to save the replay so far, since the window can be closed at any time.
+    const REPLAY_SAVE_EVERY_N_FRAMES: u32 = 600;
+    let mut frames_since_replay_saved = 0;
+
     let mut current_input_mode = InputMode::Touchpad;
 
     let color_palette = RefCell::new(DEFAULT_COLOR_PALLETTE);
@@ -211,8 +230,15 @@ async fn main() {
         let smaller_real_dim = sh.min(sw);
         let larger_real_dim = sh.max(sw);
 
-        let dim_ratio = min_internal_dim as f32 / smaller_real_dim as f32;
+        // a screen scale from the options shows more of the level, as long as it stays
+        // above the minimum and the larger side stays under MAX_SCREEN_DIM.
+        let fit_ratio = min_internal_dim as f32 / smaller_real_dim as f32;
+        let dim_ratio = match kittygame_options().screen_scale {
+            0 => fit_ratio,
+            n => (1. / n as f32).min(MAX_SCREEN_DIM / larger_real_dim).max(fit_ratio),
+        };
 
+        let smaller_internal_dim = (smaller_real_dim * dim_ratio) as i32;
         let other_internal_dim = (larger_real_dim * dim_ratio).min(MAX_SCREEN_DIM) as i32;
 
         let internal_width;
@@ -220,11 +246,11 @@ async fn main() {
 
         match smaller_side {
             Dim::Height => {
-                internal_height = min_internal_dim;
+                internal_height = smaller_internal_dim;
                 internal_width = other_internal_dim;
             }
             _ => {
-                internal_width = min_internal_dim;
+                internal_width = smaller_internal_dim;
                 internal_height = other_internal_dim;
             }
         }
@@ -402,10 +428,12 @@ async fn main() {
         
 
 
-        let mut keymap = HashMap::with_capacity(6);
+        let mut keymap = HashMap::with_capacity(9);
         keymap.insert(KeyCode::X, BUTTON_1);
         keymap.insert(KeyCode::Z, BUTTON_2);
         keymap.insert(KeyCode::Space, BUTTON_1);
+        keymap.insert(KeyCode::Escape, BUTTON_START);
+        keymap.insert(KeyCode::Enter, BUTTON_START);
 
         keymap.insert(KeyCode::Left, BUTTON_LEFT);
         keymap.insert(KeyCode::Right, BUTTON_RIGHT);
@@ -484,10 +512,19 @@ async fn main() {
             }
         }
 
-        kittygame_update(blit_sub, line, rect, text_str, switch_palette, internal_width as u32, internal_height as u32, &btns_pressed_this_frame, &gamepads);
+        let log = &mut |s: &str| {
+            info!("{}", s);
+        };
+
+        // no synth here yet, so music and sound effects are dropped.
+        let tone = &mut |_, _, _, _: &_| {};
+
+        kittygame_update(blit_sub, line, rect, text_str, switch_palette, log, tone, internal_width as u32, internal_height as u32, &btns_pressed_this_frame, &gamepads);
 
         match current_input_mode {
             InputMode::KeyboardDetected => {},
+            // the buttons still work, they're just not drawn.
+            InputMode::Touchpad if !kittygame_options().show_touch_overlay => {},
             InputMode::Touchpad => {
 
                 let left_texture = match &gamepads[0] & BUTTON_LEFT != 0 {
@@ -533,6 +570,30 @@ async fn main() {
                 });
             },
         }
+        if let Some(bytes) = kittygame_take_save() {
+            if let Err(e) = std::fs::write(SAVE_PATH, bytes) {
+                error!("couldn't save to {}: {}", SAVE_PATH, e);
+            }
+        }
+        if let Some(path) = &replay_path {
+            frames_since_replay_saved += 1;
+            if frames_since_replay_saved >= REPLAY_SAVE_EVERY_N_FRAMES {
+                frames_since_replay_saved = 0;
+                if let Some(text) = kittygame_replay_text() {
+                    if let Err(e) = std::fs::write(path, text) {
+                        error!("couldn't save the replay to {}: {}", path, e);
+                    }
+                }
+                // and how the run's going, next to it.
+                if let Some(text) = kittygame_run_stats_text() {
+                    let stats_path = format!("{}.stats", path);
+                    if let Err(e) = std::fs::write(&stats_path, text) {
+                        error!("couldn't save the run stats to {}: {}", stats_path, e);
+                    }
+                }
+            }
+        }
+
         next_frame().await
     }
 }
@@ -5,7 +5,7 @@ mod wasm4;
 mod kitty_ss;
 mod title_ss;
 
-use kittygame::multiplatform_defs::{BlitSubFlags, DrawColor, Pallette, Spritesheet};
+use kittygame::multiplatform_defs::{BlitSubFlags, DrawColor, Pallette, Spritesheet, ToneChannel, BUTTON_START};
 use kittygame::spritesheet::{KITTY_SPRITESHEET_DRAW_COLORS, KITTY_SPRITESHEET_FLAGS};
 use title_ss::OUTPUT_ONLINEPNGTOOLS_FLAGS;
 use wasm4::*;
@@ -70,6 +70,16 @@ fn my_text_str(t: &str, x: i32, y: i32, color: &DrawColor) {
     text(t, x, y);
 }
 
+fn my_tone(frequency: u32, duration: u32, volume: u32, channel: &ToneChannel) {
+    let flags = match channel {
+        ToneChannel::Pulse1 => TONE_PULSE1,
+        ToneChannel::Pulse2 => TONE_PULSE2,
+        ToneChannel::Triangle => TONE_TRIANGLE,
+        ToneChannel::Noise => TONE_NOISE,
+    };
+    tone(frequency, duration, volume, flags);
+}
+
 static mut PREVIOUS_GAMEPAD: [u8; 4] = [0, 0, 0, 0];
 
 /// get joystick inputs from this and last frame.
@@ -80,7 +90,13 @@ fn get_inputs_this_frame() -> [[u8; 4]; 2] {
     for i in 0..gamepads.len() {
         let gamepad = gamepads[i];
         let previous = unsafe { PREVIOUS_GAMEPAD[i] };
-        let pressed_this_frame = gamepad & (gamepad ^ previous);
+        let mut pressed_this_frame = gamepad & (gamepad ^ previous);
+        // there's no start button, and nothing else uses up and down together, so that pauses.
+        // A d-pad can't press both at once, but a keyboard can.
+        let up_and_down = BUTTON_UP | BUTTON_DOWN;
+        if gamepad & up_and_down == up_and_down && pressed_this_frame & up_and_down != 0 {
+            pressed_this_frame |= BUTTON_START;
+        }
         btns_pressed_this_frame[i] = pressed_this_frame;
     }
     unsafe { PREVIOUS_GAMEPAD.copy_from_slice(&gamepads[0..4]) };
@@ -98,9 +114,23 @@ fn set_palette(palette: &Pallette) {
     }
 }
 
+// wasm-4 gives every cart this much disk.
+const DISK_SIZE: usize = 1024;
+
+#[no_mangle]
+fn start() {
+    let mut disk = [0u8; DISK_SIZE];
+    let n_read = unsafe { diskr(disk.as_mut_ptr(), DISK_SIZE as u32) };
+    kittygame::kittygame_load_save(&disk[..n_read as usize]);
+}
+
 #[no_mangle]
 fn update() {
     let [btns_pressed_this_frame, gamepads] = get_inputs_this_frame();
 
-    kittygame::kittygame_update(&mut my_blit_sub, &mut my_line, &mut my_rect, &mut my_text_str, &mut set_palette, 160, 160, &btns_pressed_this_frame, &gamepads);
+    kittygame::kittygame_update(&mut my_blit_sub, &mut my_line, &mut my_rect, &mut my_text_str, &mut set_palette, &mut |s: &str| trace(s), &mut my_tone, 160, 160, &btns_pressed_this_frame, &gamepads);
+
+    if let Some(save) = kittygame::kittygame_take_save() {
+        unsafe { diskw(save.as_ptr(), save.len() as u32) };
+    }
 }
\ No newline at end of file